@@ -88,7 +88,7 @@ fn md_header() -> String {
     )
 }
 
-fn parse_last_md_table(content: &str) -> Vec<MolluskComputeUnitBenchResult> {
+fn parse_last_md_table(content: &str) -> Vec<MolluskComputeUnitBenchResult<'_>> {
     let mut results = vec![];
 
     for line in content.lines().skip(4) {
@@ -0,0 +1,163 @@
+//! Fixture effects regeneration: re-executing fixtures and rewriting their
+//! effects in place, for when a program change intentionally alters its
+//! behavior.
+
+use {
+    crate::runner::ProtoLayout,
+    mollusk_svm::{report::CheckReport, Mollusk},
+    mollusk_svm_fuzz_fs::SerializableFixture,
+    std::fs,
+};
+
+/// A fixture with regenerated effects.
+struct Blessed {
+    /// The report comparing the fixture's previous effects against the new
+    /// ones.
+    report: CheckReport,
+    /// The previous and new versions of the effects.
+    versions: (u32, u32),
+    /// The encoded fixture.
+    blob: Vec<u8>,
+}
+
+impl Blessed {
+    fn changed(&self) -> bool {
+        !self.report.passed() || self.versions.0 != self.versions.1
+    }
+}
+
+fn bless_blob(
+    mollusk: &mut Mollusk,
+    blob: &[u8],
+    chain: bool,
+    proto: &ProtoLayout,
+) -> Result<Blessed, Box<dyn std::error::Error>> {
+    match proto {
+        ProtoLayout::Mollusk if chain => {
+            let mut fixture = mollusk_svm_fuzz_fixture::chain::ChainFixture::decode(blob);
+            // Chains are summarized by their earliest version of effects.
+            let version = |fixture: &mollusk_svm_fuzz_fixture::chain::ChainFixture| {
+                fixture
+                    .output
+                    .iter()
+                    .map(|effects| effects.version)
+                    .min()
+                    .unwrap_or_default()
+            };
+            let previous = version(&fixture);
+            let report = mollusk.bless_chain_fixture(&mut fixture)?;
+            Ok(Blessed {
+                report,
+                versions: (previous, version(&fixture)),
+                blob: SerializableFixture::encode(
+                    &mollusk_svm_fuzz_fixture::proto::ChainFixture::from(fixture),
+                ),
+            })
+        }
+        ProtoLayout::Mollusk => {
+            let mut fixture = mollusk_svm_fuzz_fixture::Fixture::decode(blob);
+            let previous = fixture.output.version;
+            let report = mollusk.bless_fixture(&mut fixture)?;
+            Ok(Blessed {
+                report,
+                versions: (previous, fixture.output.version),
+                blob: SerializableFixture::encode(
+                    &mollusk_svm_fuzz_fixture::proto::InstrFixture::from(fixture),
+                ),
+            })
+        }
+        ProtoLayout::Firedancer if chain => {
+            Err("Chain fixtures are only supported with the Mollusk protobuf layout".into())
+        }
+        ProtoLayout::Firedancer => {
+            let mut fixture = mollusk_svm_fuzz_fixture_firedancer::Fixture::decode(blob);
+            let report = mollusk.bless_firedancer_fixture(&mut fixture)?;
+            Ok(Blessed {
+                report,
+                // Firedancer fixtures only record the original effects.
+                versions: (0, 0),
+                blob: SerializableFixture::encode(
+                    &mollusk_svm_fuzz_fixture_firedancer::proto::InstrFixture::from(fixture),
+                ),
+            })
+        }
+    }
+}
+
+fn print_changes(blessed: &Blessed) {
+    for outcome in blessed.report.failures() {
+        let account = outcome
+            .account
+            .as_ref()
+            .map(|account| format!(" [{}]", account))
+            .unwrap_or_default();
+        match (&outcome.detail, &outcome.expected, &outcome.actual) {
+            (Some(detail), _, _) => println!(
+                "  {}{}:\n    {}",
+                outcome.check,
+                account,
+                detail.trim_end().replace('\n', "\n    ")
+            ),
+            // Long values, such as logs, go on their own lines.
+            (None, Some(previous), Some(new)) if previous.len() + new.len() > 60 => println!(
+                "  {}{}:\n    - {}\n    + {}",
+                outcome.check, account, previous, new
+            ),
+            (None, Some(previous), Some(new)) => {
+                println!("  {}{}: {} -> {}", outcome.check, account, previous, new)
+            }
+            _ => println!("  {}{}", outcome.check, account),
+        }
+    }
+    let (previous, new) = blessed.versions;
+    if previous != new {
+        println!("  effects_version: {} -> {}", previous, new);
+    }
+}
+
+/// Re-execute each fixture and rewrite its effects in place with the
+/// results, printing the effects which changed. Fixtures whose effects
+/// didn't change are left untouched.
+pub fn bless(
+    mollusk: &mut Mollusk,
+    fixture_paths: &[String],
+    chain: bool,
+    proto: ProtoLayout,
+    dry_run: bool,
+    program_logs: bool,
+    verbose: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Disable stdout logging of program logs if not specified.
+    if !program_logs {
+        solana_logger::setup_with("");
+    }
+
+    let mut changed = 0;
+    for fixture_path in fixture_paths {
+        let blob = fs::read(fixture_path)?;
+        let blessed = bless_blob(mollusk, &blob, chain, &proto)
+            .map_err(|err| format!("Failed to bless {}: {}", fixture_path, err))?;
+        if blessed.changed() {
+            println!("CHANGED: {}", fixture_path);
+            print_changes(&blessed);
+            if !dry_run {
+                fs::write(fixture_path, &blessed.blob)?;
+            }
+            changed += 1;
+        } else if verbose {
+            println!("UNCHANGED: {}", fixture_path);
+        }
+    }
+
+    if dry_run {
+        println!(
+            "{} of {} fixtures would change",
+            changed,
+            fixture_paths.len()
+        );
+    } else {
+        println!("Blessed {} of {} fixtures", changed, fixture_paths.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,170 @@
+//! Fixture bundles: packing fixture files into a bundle, listing a bundle's
+//! index and running the fixtures in it.
+
+use {
+    crate::runner::{ProtoLayout, Runner},
+    clap::{Args, ValueEnum},
+    mollusk_svm::Mollusk,
+    mollusk_svm_fuzz_fs::{
+        bundle::{
+            BundleFilter, BundleReader, BundleWriter, IndexableFixture, MAX_DISCRIMINATOR_LEN,
+        },
+        readable, SerializableFixture,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{fs, io::Write, str::FromStr},
+};
+
+/// Filters selecting fixtures from a bundle by its index.
+#[derive(Args)]
+pub struct FilterArgs {
+    /// Only select fixtures invoking this program.
+    #[arg(long, value_parser = Pubkey::from_str)]
+    program: Option<Pubkey>,
+    /// Only select fixtures whose instruction data starts with these bytes,
+    /// as hex. At most 8 bytes.
+    #[arg(long)]
+    discriminator: Option<String>,
+    /// Only select fixtures with this program result code.
+    #[arg(long)]
+    result: Option<u64>,
+    /// Only select fixtures whose program failed.
+    #[arg(long)]
+    failed: bool,
+}
+
+impl FilterArgs {
+    pub fn to_filter(&self) -> Result<BundleFilter, Box<dyn std::error::Error>> {
+        let discriminator = self.discriminator.as_deref().map(parse_hex).transpose()?;
+        if let Some(discriminator) = &discriminator {
+            if discriminator.len() > MAX_DISCRIMINATOR_LEN {
+                return Err(format!(
+                    "Discriminator of {} bytes is longer than the {} bytes bundles record",
+                    discriminator.len(),
+                    MAX_DISCRIMINATOR_LEN
+                )
+                .into());
+            }
+        }
+        Ok(BundleFilter {
+            program_id: self.program,
+            discriminator,
+            program_result: self.result,
+            failed: self.failed,
+        })
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    readable::hex::decode(hex).ok_or_else(|| format!("Invalid hex string: {}", hex))
+}
+
+fn append_all<SF: IndexableFixture, W: Write>(
+    writer: &mut BundleWriter<W>,
+    fixture_paths: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    for fixture_path in fixture_paths {
+        let blob = fs::read(fixture_path)?;
+        let fixture = SF::try_decode(&blob)
+            .map_err(|err| format!("Failed to decode {}: {}", fixture_path, err))?;
+        writer.append(&fixture)?;
+    }
+    Ok(())
+}
+
+/// Pack fixture files into a bundle at the output path.
+pub fn create(
+    fixture_paths: &[String],
+    output: &str,
+    chain: bool,
+    proto: ProtoLayout,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if chain && matches!(proto, ProtoLayout::Firedancer) {
+        return Err("Chain fixtures are only supported with the Mollusk protobuf layout".into());
+    }
+
+    let mut writer = BundleWriter::create(output)?;
+    match proto {
+        ProtoLayout::Mollusk if chain => append_all::<
+            mollusk_svm_fuzz_fixture::proto::ChainFixture,
+            _,
+        >(&mut writer, fixture_paths)?,
+        ProtoLayout::Mollusk => append_all::<mollusk_svm_fuzz_fixture::proto::InstrFixture, _>(
+            &mut writer,
+            fixture_paths,
+        )?,
+        ProtoLayout::Firedancer => append_all::<
+            mollusk_svm_fuzz_fixture_firedancer::proto::InstrFixture,
+            _,
+        >(&mut writer, fixture_paths)?,
+    }
+
+    let count = writer.index().len();
+    writer.finish()?;
+    println!("Bundled {} fixtures into {}", count, output);
+
+    Ok(())
+}
+
+/// Print the entries of a bundle's index matching the filter.
+pub fn list(bundle_path: &str, filter: &BundleFilter) -> Result<(), Box<dyn std::error::Error>> {
+    let reader = BundleReader::open(bundle_path)?;
+    let index = reader.index();
+
+    let mut matched = 0;
+    for entry in index.iter().filter(|entry| filter.matches(entry)) {
+        println!(
+            "{}  program: {}  discriminator: {}  result: {}",
+            entry.file_name(),
+            entry.program_id,
+            readable::hex::encode(&entry.discriminator),
+            entry.program_result,
+        );
+        matched += 1;
+    }
+    println!("{} of {} fixtures", matched, index.len());
+
+    Ok(())
+}
+
+/// Run the fixtures in a bundle matching the filter. Chain fixtures are
+/// recognized by their kind in the index, and every fixture must be of the
+/// runner's protobuf layout.
+pub fn run(
+    runner: &Runner,
+    ground: &mut Mollusk,
+    mut target: Option<&mut Mollusk>,
+    bundle_path: &str,
+    filter: &BundleFilter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reader = BundleReader::open(bundle_path)?;
+    let entries = reader
+        .index()
+        .iter()
+        .filter(|entry| filter.matches(entry))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let layout = runner.proto().to_possible_value().unwrap();
+    for entry in entries {
+        let name = format!("{}:{}", bundle_path, entry.file_name());
+        if entry.layout != layout.get_name() {
+            return Err(format!(
+                "{} is a {} fixture, but the {} layout was selected",
+                name,
+                entry.layout,
+                layout.get_name()
+            )
+            .into());
+        }
+        let blob = reader.read_blob(&entry)?;
+        if entry.kind == mollusk_svm_fuzz_fixture::proto::ChainFixture::FILE_PREFIX {
+            runner.run_chain_blob(ground, target.as_deref_mut(), &name, &blob)?;
+        } else {
+            runner.run_blob(ground, target.as_deref_mut(), &name, &blob)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+//! Fixture conversion between the Mollusk and Firedancer layouts.
+
+use {
+    crate::runner::ProtoLayout,
+    mollusk_svm::fuzz::convert::{firedancer_to_mollusk, mollusk_to_firedancer},
+    mollusk_svm_fuzz_fs::FsHandler,
+};
+
+/// Convert fixtures from one layout to another, writing them to the output
+/// directory, and report the fields each conversion drops.
+pub fn convert(
+    fixture_paths: &[String],
+    from: ProtoLayout,
+    to: ProtoLayout,
+    output: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for fixture_path in fixture_paths {
+        let blob = std::fs::read(fixture_path)?;
+        let dropped = match (&from, &to) {
+            (ProtoLayout::Mollusk, ProtoLayout::Firedancer) => {
+                let fixture = mollusk_svm_fuzz_fixture::Fixture::decode(&blob);
+                let conversion = mollusk_to_firedancer(&fixture);
+                FsHandler::new(conversion.fixture).dump_to_blob_file(output);
+                conversion.dropped
+            }
+            (ProtoLayout::Firedancer, ProtoLayout::Mollusk) => {
+                let fixture = mollusk_svm_fuzz_fixture_firedancer::Fixture::decode(&blob);
+                let conversion = firedancer_to_mollusk(&fixture);
+                FsHandler::new(conversion.fixture).dump_to_blob_file(output);
+                conversion.dropped
+            }
+            _ => return Err("The source and target layouts must differ".into()),
+        };
+
+        println!("CONVERTED: {}", fixture_path);
+        if !dropped.is_empty() {
+            println!("  DROPPED: {}", dropped.join(", "));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,508 @@
+//! Fixture corpora: deduplicating, summarizing, filtering and splitting
+//! directories of fixtures.
+
+use {
+    crate::runner::ProtoLayout,
+    mollusk_svm_fuzz_fs::{
+        bundle::{BundleEntry, BundleFilter, IndexableFixture, MAX_DISCRIMINATOR_LEN},
+        readable,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        cmp::Reverse,
+        collections::{BTreeMap, HashMap},
+        fs,
+        path::Path,
+    },
+};
+
+/// A fixture which can be curated in a corpus.
+trait CorpusFixture: IndexableFixture {
+    /// The addresses of the accounts in the fixture's context.
+    fn account_addresses(&self) -> Vec<Pubkey>;
+}
+
+// Every layout's context holds its accounts in the same way.
+macro_rules! impl_corpus_fixture {
+    ($fixture:ty) => {
+        impl CorpusFixture for $fixture {
+            fn account_addresses(&self) -> Vec<Pubkey> {
+                self.input.as_ref().map_or_else(Vec::new, |input| {
+                    input
+                        .accounts
+                        .iter()
+                        .filter_map(|account| Pubkey::try_from(account.address.as_slice()).ok())
+                        .collect()
+                })
+            }
+        }
+    };
+}
+
+impl_corpus_fixture!(mollusk_svm_fuzz_fixture::proto::InstrFixture);
+impl_corpus_fixture!(mollusk_svm_fuzz_fixture::proto::ChainFixture);
+impl_corpus_fixture!(mollusk_svm_fuzz_fixture_firedancer::proto::InstrFixture);
+
+/// A fixture file in a corpus, summarized as it would be in a bundle's
+/// index.
+struct CorpusEntry {
+    path: String,
+    entry: BundleEntry,
+    accounts: Vec<Pubkey>,
+}
+
+fn load_entries<SF: CorpusFixture>(
+    fixture_paths: &[String],
+) -> Result<Vec<CorpusEntry>, Box<dyn std::error::Error>> {
+    let mut entries = Vec::with_capacity(fixture_paths.len());
+    for fixture_path in fixture_paths {
+        let blob = fs::read(fixture_path)?;
+        let fixture = SF::try_decode(&blob)
+            .map_err(|err| format!("Failed to decode {}: {}", fixture_path, err))?;
+        let instruction_data = fixture.instruction_data();
+        entries.push(CorpusEntry {
+            path: fixture_path.clone(),
+            entry: BundleEntry {
+                kind: SF::FILE_PREFIX.to_string(),
+                layout: SF::LAYOUT.to_string(),
+                hash: fixture.hash(),
+                program_id: fixture.program_id(),
+                discriminator: instruction_data
+                    [..instruction_data.len().min(MAX_DISCRIMINATOR_LEN)]
+                    .to_vec(),
+                program_result: fixture.program_result(),
+                // Not in a bundle.
+                offset: 0,
+                length: blob.len() as u64,
+            },
+            accounts: fixture.account_addresses(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Decode the fixtures at the provided paths, sorted by path so commands are
+/// deterministic. Paths to the same file, ie. from overlapping search paths,
+/// are only loaded once.
+fn load_corpus(
+    fixture_paths: &[String],
+    chain: bool,
+    proto: ProtoLayout,
+) -> Result<Vec<CorpusEntry>, Box<dyn std::error::Error>> {
+    let mut fixture_paths = fixture_paths
+        .iter()
+        .map(|path| {
+            fs::canonicalize(path)
+                .map(|canonical| (canonical, path.clone()))
+                .map_err(|err| format!("Failed to resolve {}: {}", path, err))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    fixture_paths.sort();
+    fixture_paths.dedup_by(|(a, _), (b, _)| a == b);
+    let fixture_paths = fixture_paths
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect::<Vec<_>>();
+    match proto {
+        ProtoLayout::Mollusk if chain => {
+            load_entries::<mollusk_svm_fuzz_fixture::proto::ChainFixture>(&fixture_paths)
+        }
+        ProtoLayout::Mollusk => {
+            load_entries::<mollusk_svm_fuzz_fixture::proto::InstrFixture>(&fixture_paths)
+        }
+        ProtoLayout::Firedancer if chain => {
+            Err("Chain fixtures are only supported with the Mollusk protobuf layout".into())
+        }
+        ProtoLayout::Firedancer => {
+            load_entries::<mollusk_svm_fuzz_fixture_firedancer::proto::InstrFixture>(&fixture_paths)
+        }
+    }
+}
+
+/// Copy a fixture into the directory, refusing to overwrite a different
+/// file with the same name, ie. a fixture from another directory of the
+/// corpus.
+fn copy_to(entry: &CorpusEntry, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let file_name = Path::new(&entry.path)
+        .file_name()
+        .ok_or_else(|| format!("Not a file: {}", entry.path))?;
+    let destination = dir.join(file_name);
+    if destination.exists() {
+        if fs::read(&destination)? != fs::read(&entry.path)? {
+            return Err(format!(
+                "Can't copy {}: {} already exists with different contents",
+                entry.path,
+                destination.display()
+            )
+            .into());
+        }
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+    fs::copy(&entry.path, destination)?;
+    Ok(())
+}
+
+/// Remove fixtures with the same hash as another fixture, keeping the first
+/// by path.
+pub fn dedupe(
+    fixture_paths: &[String],
+    chain: bool,
+    proto: ProtoLayout,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = load_corpus(fixture_paths, chain, proto)?;
+
+    let mut kept = HashMap::new();
+    let mut removed = 0;
+    for entry in &entries {
+        if let Some(original) = kept.get(&entry.entry.hash) {
+            println!("DUPLICATE: {} (of {})", entry.path, original);
+            if !dry_run {
+                fs::remove_file(&entry.path)?;
+            }
+            removed += 1;
+        } else {
+            kept.insert(entry.entry.hash, entry.path.clone());
+        }
+    }
+
+    let verb = if dry_run { "Found" } else { "Removed" };
+    println!(
+        "{} {} duplicates, {} unique fixtures",
+        verb,
+        removed,
+        kept.len()
+    );
+
+    Ok(())
+}
+
+fn print_counts<K: Ord>(title: &str, counts: BTreeMap<K, usize>, display: impl Fn(&K) -> String) {
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    // Most common first, ties in key order.
+    counts.sort_by_key(|(_, count)| Reverse(*count));
+    println!("{}:", title);
+    for (key, count) in counts {
+        println!("  {}: {}", display(&key), count);
+    }
+}
+
+/// Print the number of fixtures per program, program result and instruction
+/// discriminator.
+pub fn stats(
+    fixture_paths: &[String],
+    chain: bool,
+    proto: ProtoLayout,
+    discriminator_len: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = load_corpus(fixture_paths, chain, proto)?;
+
+    let mut programs = BTreeMap::new();
+    let mut results = BTreeMap::new();
+    let mut discriminators = BTreeMap::new();
+    for CorpusEntry { entry, .. } in &entries {
+        *programs.entry(entry.program_id).or_default() += 1;
+        *results.entry(entry.program_result).or_default() += 1;
+        let len = entry.discriminator.len().min(discriminator_len);
+        *discriminators
+            .entry(entry.discriminator[..len].to_vec())
+            .or_default() += 1;
+    }
+
+    println!("Fixtures: {}", entries.len());
+    print_counts("Programs", programs, Pubkey::to_string);
+    print_counts("Results", results, u64::to_string);
+    print_counts("Discriminators", discriminators, |discriminator| {
+        readable::hex::encode(discriminator)
+    });
+
+    Ok(())
+}
+
+/// Copy the fixtures matching the filter, and using the account if
+/// provided, to the output directory.
+pub fn filter(
+    fixture_paths: &[String],
+    output: &str,
+    chain: bool,
+    proto: ProtoLayout,
+    filter: &BundleFilter,
+    account: Option<Pubkey>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = load_corpus(fixture_paths, chain, proto)?;
+
+    let mut matched = 0;
+    for entry in &entries {
+        if filter.matches(&entry.entry)
+            && account.map_or(true, |account| entry.accounts.contains(&account))
+        {
+            copy_to(entry, Path::new(output))?;
+            matched += 1;
+        }
+    }
+    println!(
+        "Copied {} of {} fixtures to {}",
+        matched,
+        entries.len(),
+        output
+    );
+
+    Ok(())
+}
+
+/// Copy the fixtures into a training set and a regression set.
+///
+/// Fixtures are assigned by hash rather than at random, so each fixture stays
+/// in the same set as the corpus grows.
+pub fn split(
+    fixture_paths: &[String],
+    train: &str,
+    regression: &str,
+    regression_percent: u8,
+    chain: bool,
+    proto: ProtoLayout,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if regression_percent > 100 {
+        return Err("The regression percentage must be at most 100".into());
+    }
+
+    let entries = load_corpus(fixture_paths, chain, proto)?;
+
+    let mut regression_count = 0;
+    for entry in &entries {
+        let bucket = u64::from_le_bytes(entry.entry.hash.to_bytes()[..8].try_into().unwrap()) % 100;
+        if bucket < u64::from(regression_percent) {
+            copy_to(entry, Path::new(regression))?;
+            regression_count += 1;
+        } else {
+            copy_to(entry, Path::new(train))?;
+        }
+    }
+    println!(
+        "Split {} fixtures: {} to {}, {} to {}",
+        entries.len(),
+        entries.len() - regression_count,
+        train,
+        regression_count,
+        regression,
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        mollusk_svm::{fuzz::mollusk::build_fixture_from_mollusk_test, Mollusk},
+        mollusk_svm_fuzz_fs::SerializableFixture,
+        solana_sdk::{account::Account, system_instruction, system_program},
+        std::path::PathBuf,
+    };
+
+    /// An empty directory for a test's corpus, under the target directory.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../target/tmp/corpus")
+            .join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write a transfer fixture between the provided accounts to the path.
+    fn write_fixture(path: &Path, sender: Pubkey, recipient: Pubkey, amount: u64) -> String {
+        let mollusk = Mollusk::default();
+        let instruction = system_instruction::transfer(&sender, &recipient, amount);
+        let accounts = vec![
+            (sender, Account::new(1_000_000, 0, &system_program::id())),
+            (recipient, Account::new(1_000_000, 0, &system_program::id())),
+        ];
+        let result = mollusk.process_instruction(&instruction, &accounts);
+        let fixture =
+            build_fixture_from_mollusk_test(&mollusk, &instruction, &accounts, &result).unwrap();
+        let blob = SerializableFixture::encode(
+            &mollusk_svm_fuzz_fixture::proto::InstrFixture::from(fixture),
+        );
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, blob).unwrap();
+        path.display().to_string()
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_dedupe() {
+        let dir = test_dir("dedupe");
+        let (sender, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let paths = vec![
+            write_fixture(&dir.join("a.fix"), sender, recipient, 1),
+            write_fixture(&dir.join("b.fix"), sender, recipient, 1),
+            write_fixture(&dir.join("c.fix"), sender, recipient, 2),
+        ];
+
+        // Dry runs don't remove anything.
+        dedupe(&paths, false, ProtoLayout::Mollusk, true).unwrap();
+        assert_eq!(file_names(&dir), vec!["a.fix", "b.fix", "c.fix"]);
+
+        // The first duplicate by path is kept, whatever the order provided.
+        let reversed = paths.iter().rev().cloned().collect::<Vec<_>>();
+        dedupe(&reversed, false, ProtoLayout::Mollusk, false).unwrap();
+        assert_eq!(file_names(&dir), vec!["a.fix", "c.fix"]);
+    }
+
+    #[test]
+    fn test_dedupe_same_file() {
+        let dir = test_dir("dedupe_same_file");
+        let (sender, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let path = write_fixture(&dir.join("a.fix"), sender, recipient, 1);
+
+        // The same file, found twice by overlapping search paths, isn't a
+        // duplicate of itself.
+        let paths = vec![
+            path.clone(),
+            dir.join(".").join("a.fix").display().to_string(),
+            path,
+        ];
+        dedupe(&paths, false, ProtoLayout::Mollusk, false).unwrap();
+        assert_eq!(file_names(&dir), vec!["a.fix"]);
+    }
+
+    #[test]
+    fn test_filter() {
+        let dir = test_dir("filter");
+        let (sender, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let other = Pubkey::new_unique();
+        let paths = vec![
+            write_fixture(&dir.join("corpus/a.fix"), sender, recipient, 1),
+            write_fixture(&dir.join("corpus/b.fix"), sender, other, 1),
+            // Overdraws the sender.
+            write_fixture(&dir.join("corpus/c.fix"), sender, other, 2_000_000),
+        ];
+        let output = dir.join("output");
+        let output_str = output.display().to_string();
+
+        let failed = BundleFilter {
+            failed: true,
+            ..Default::default()
+        };
+        filter(
+            &paths,
+            &output_str,
+            false,
+            ProtoLayout::Mollusk,
+            &failed,
+            None,
+        )
+        .unwrap();
+        assert_eq!(file_names(&output), vec!["c.fix"]);
+
+        filter(
+            &paths,
+            &output_str,
+            false,
+            ProtoLayout::Mollusk,
+            &BundleFilter::default(),
+            Some(other),
+        )
+        .unwrap();
+        assert_eq!(file_names(&output), vec!["b.fix", "c.fix"]);
+    }
+
+    #[test]
+    fn test_split() {
+        let dir = test_dir("split");
+        let (sender, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let paths = (1..=10)
+            .map(|amount| {
+                write_fixture(
+                    &dir.join(format!("corpus/{}.fix", amount)),
+                    sender,
+                    recipient,
+                    amount,
+                )
+            })
+            .collect::<Vec<_>>();
+        let split_into = |name: &str, regression_percent: u8| {
+            let train = dir.join(name).join("train");
+            let regression = dir.join(name).join("regression");
+            split(
+                &paths,
+                &train.display().to_string(),
+                &regression.display().to_string(),
+                regression_percent,
+                false,
+                ProtoLayout::Mollusk,
+            )
+            .unwrap();
+            (file_names(&train), file_names(&regression))
+        };
+
+        let (train, regression) = split_into("none", 0);
+        assert_eq!((train.len(), regression.len()), (10, 0));
+        let (train, regression) = split_into("all", 100);
+        assert_eq!((train.len(), regression.len()), (0, 10));
+
+        // Each fixture lands in exactly one set, the same one every time.
+        let (train, regression) = split_into("half", 50);
+        assert_eq!(train.len() + regression.len(), 10);
+        assert!(train.iter().all(|name| !regression.contains(name)));
+        assert_eq!(split_into("half_again", 50), (train, regression));
+
+        assert!(split(
+            &paths,
+            "train",
+            "regression",
+            101,
+            false,
+            ProtoLayout::Mollusk
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_copy_collisions() {
+        let dir = test_dir("collisions");
+        let (sender, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let paths = vec![
+            write_fixture(&dir.join("corpus/a/same.fix"), sender, recipient, 1),
+            write_fixture(&dir.join("corpus/b/same.fix"), sender, recipient, 2),
+        ];
+        let output = dir.join("output");
+        let output_str = output.display().to_string();
+
+        let err = filter(
+            &paths,
+            &output_str,
+            false,
+            ProtoLayout::Mollusk,
+            &BundleFilter::default(),
+            None,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+
+        // Copying identical fixtures again is fine.
+        filter(
+            &paths[..1],
+            &output_str,
+            false,
+            ProtoLayout::Mollusk,
+            &BundleFilter::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(file_names(&output), vec!["same.fix"]);
+    }
+}
@@ -0,0 +1,485 @@
+//! Fixture inspection: decoding a fixture into a human-readable view of its
+//! context and effects.
+
+use {
+    crate::runner::ProtoLayout,
+    mollusk_svm_fuzz_fixture::proto as mollusk,
+    mollusk_svm_fuzz_fixture_firedancer::proto as firedancer,
+    mollusk_svm_fuzz_fs::{readable, SerializableFixture},
+    serde::Serialize,
+    solana_sdk::{program_error::ProgramError, pubkey::Pubkey},
+};
+
+/// Addresses are shown in base58, and anything else which isn't 32 bytes in
+/// hex.
+fn address(bytes: &[u8]) -> String {
+    Pubkey::try_from(bytes).map_or_else(
+        |_| readable::hex::encode(bytes),
+        |pubkey| pubkey.to_string(),
+    )
+}
+
+#[derive(Serialize)]
+struct DataView {
+    hex: String,
+    /// The data as text, if it's printable UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    utf8: Option<String>,
+}
+
+impl DataView {
+    fn new(data: &[u8]) -> Self {
+        let utf8 = std::str::from_utf8(data)
+            .ok()
+            .filter(|text| !text.is_empty() && !text.chars().any(char::is_control))
+            .map(str::to_string);
+        Self {
+            hex: readable::hex::encode(data),
+            utf8,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SeedAddressView {
+    base: String,
+    seed: DataView,
+    owner: String,
+}
+
+#[derive(Serialize)]
+struct AccountView {
+    address: String,
+    lamports: u64,
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+    data: DataView,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed_address: Option<SeedAddressView>,
+}
+
+impl From<&mollusk::AcctState> for AccountView {
+    fn from(account: &mollusk::AcctState) -> Self {
+        Self {
+            address: address(&account.address),
+            lamports: account.lamports,
+            owner: address(&account.owner),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: DataView::new(&account.data),
+            seed_address: None,
+        }
+    }
+}
+
+impl From<&firedancer::AcctState> for AccountView {
+    fn from(account: &firedancer::AcctState) -> Self {
+        Self {
+            address: address(&account.address),
+            lamports: account.lamports,
+            owner: address(&account.owner),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: DataView::new(&account.data),
+            seed_address: account.seed_addr.as_ref().map(|seed| SeedAddressView {
+                base: address(&seed.base),
+                seed: DataView::new(&seed.seed),
+                owner: address(&seed.owner),
+            }),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct InstructionAccountView {
+    /// The index of the account in the fixture's accounts, if the account is
+    /// referenced by index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<u32>,
+    address: String,
+    signer: bool,
+    writable: bool,
+}
+
+#[derive(Serialize)]
+struct InstructionView {
+    program_id: String,
+    accounts: Vec<InstructionAccountView>,
+    data: DataView,
+}
+
+impl InstructionView {
+    /// An instruction whose accounts are indices into the fixture's
+    /// accounts.
+    fn new(
+        program_id: &[u8],
+        instruction_accounts: impl Iterator<Item = (u32, bool, bool)>,
+        data: &[u8],
+        accounts: &[AccountView],
+    ) -> Self {
+        Self {
+            program_id: address(program_id),
+            accounts: instruction_accounts
+                .map(|(index, signer, writable)| InstructionAccountView {
+                    index: Some(index),
+                    address: accounts
+                        .get(index as usize)
+                        .map_or_else(|| "<missing>".to_string(), |a| a.address.clone()),
+                    signer,
+                    writable,
+                })
+                .collect(),
+            data: DataView::new(data),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct InnerInstructionView {
+    stack_height: u32,
+    #[serde(flatten)]
+    instruction: InstructionView,
+}
+
+impl From<&mollusk::InnerInstr> for InnerInstructionView {
+    fn from(inner: &mollusk::InnerInstr) -> Self {
+        Self {
+            stack_height: inner.stack_height,
+            instruction: InstructionView {
+                program_id: address(&inner.program_id),
+                accounts: inner
+                    .accounts
+                    .iter()
+                    .map(|account| InstructionAccountView {
+                        index: None,
+                        address: address(&account.address),
+                        signer: account.is_signer,
+                        writable: account.is_writable,
+                    })
+                    .collect(),
+                data: DataView::new(&inner.data),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FeatureView {
+    /// The feature's ID in the fixture, ie. the first 8 bytes of its address.
+    id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+fn features(ids: &[u64]) -> Vec<FeatureView> {
+    ids.iter()
+        .map(|&id| {
+            let feature = readable::features::lookup(id);
+            FeatureView {
+                id,
+                address: feature.map(|(address, _)| address.to_string()),
+                name: feature.map(|(_, name)| name.to_string()),
+            }
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct EpochRewardsView {
+    distribution_starting_block_height: u64,
+    num_partitions: u64,
+    parent_blockhash: String,
+    total_points: u128,
+    total_rewards: u64,
+    distributed_rewards: u64,
+    active: bool,
+}
+
+#[derive(Serialize)]
+struct SlotHashView {
+    slot: u64,
+    hash: String,
+}
+
+#[derive(Serialize)]
+struct SysvarsView {
+    clock: Option<mollusk::Clock>,
+    epoch_rewards: Option<EpochRewardsView>,
+    epoch_schedule: Option<mollusk::EpochSchedule>,
+    rent: Option<mollusk::Rent>,
+    slot_hashes: Vec<SlotHashView>,
+    stake_history: Vec<mollusk::StakeHistoryEntry>,
+}
+
+impl From<&mollusk::SysvarContext> for SysvarsView {
+    fn from(sysvars: &mollusk::SysvarContext) -> Self {
+        Self {
+            clock: sysvars.clock.clone(),
+            epoch_rewards: sysvars
+                .epoch_rewards
+                .as_ref()
+                .map(|epoch_rewards| EpochRewardsView {
+                    distribution_starting_block_height: epoch_rewards
+                        .distribution_starting_block_height,
+                    num_partitions: epoch_rewards.num_partitions,
+                    parent_blockhash: address(&epoch_rewards.parent_blockhash),
+                    total_points: epoch_rewards
+                        .total_points
+                        .as_slice()
+                        .try_into()
+                        .map_or(0, u128::from_le_bytes),
+                    total_rewards: epoch_rewards.total_rewards,
+                    distributed_rewards: epoch_rewards.distributed_rewards,
+                    active: epoch_rewards.active,
+                }),
+            epoch_schedule: sysvars.epoch_schedule.clone(),
+            rent: sysvars.rent.clone(),
+            slot_hashes: sysvars
+                .slot_hashes
+                .iter()
+                .flat_map(|slot_hashes| &slot_hashes.slot_hashes)
+                .map(|entry| SlotHashView {
+                    slot: entry.slot,
+                    hash: address(&entry.hash),
+                })
+                .collect(),
+            stake_history: sysvars
+                .stake_history
+                .iter()
+                .flat_map(|stake_history| stake_history.stake_history.clone())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EffectsView {
+    version: u32,
+    program_result: u64,
+    /// The program error, if the program failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    program_error: Option<String>,
+    compute_units_consumed: u64,
+    execution_time: u64,
+    return_data_program_id: String,
+    return_data: DataView,
+    resulting_accounts: Vec<AccountView>,
+    logs: Vec<String>,
+    inner_instructions: Vec<InnerInstructionView>,
+}
+
+impl From<&mollusk::InstrEffects> for EffectsView {
+    fn from(effects: &mollusk::InstrEffects) -> Self {
+        Self {
+            version: effects.version,
+            program_result: effects.program_result,
+            program_error: (effects.program_result != 0)
+                .then(|| ProgramError::from(effects.program_result).to_string()),
+            compute_units_consumed: effects.compute_units_consumed,
+            execution_time: effects.execution_time,
+            return_data_program_id: address(&effects.return_data_program_id),
+            return_data: DataView::new(&effects.return_data),
+            resulting_accounts: effects.resulting_accounts.iter().map(Into::into).collect(),
+            logs: effects.logs.clone(),
+            inner_instructions: effects.inner_instructions.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ProgramView {
+    program_id: String,
+    loader: String,
+    elf_size: usize,
+}
+
+impl From<&mollusk::ProgramElf> for ProgramView {
+    fn from(program: &mollusk::ProgramElf) -> Self {
+        Self {
+            program_id: address(&program.program_id),
+            loader: address(&program.loader),
+            elf_size: program.elf.len(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MolluskFixtureView {
+    instruction: InstructionView,
+    accounts: Vec<AccountView>,
+    compute_budget: Option<mollusk::ComputeBudget>,
+    features: Vec<FeatureView>,
+    sysvars: Option<SysvarsView>,
+    effects: Option<EffectsView>,
+    programs: Vec<ProgramView>,
+}
+
+impl From<&mollusk::InstrFixture> for MolluskFixtureView {
+    fn from(fixture: &mollusk::InstrFixture) -> Self {
+        let input = fixture.input.clone().unwrap_or_default();
+        let accounts = input.accounts.iter().map(Into::into).collect::<Vec<_>>();
+        Self {
+            instruction: InstructionView::new(
+                &input.program_id,
+                input
+                    .instr_accounts
+                    .iter()
+                    .map(|a| (a.index, a.is_signer, a.is_writable)),
+                &input.data,
+                &accounts,
+            ),
+            accounts,
+            compute_budget: input.compute_budget,
+            features: features(&input.feature_set.unwrap_or_default().features),
+            sysvars: input.sysvars.as_ref().map(Into::into),
+            effects: fixture.output.as_ref().map(Into::into),
+            programs: fixture.programs.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ChainFixtureView {
+    instructions: Vec<InstructionView>,
+    accounts: Vec<AccountView>,
+    compute_budget: Option<mollusk::ComputeBudget>,
+    features: Vec<FeatureView>,
+    sysvars: Option<SysvarsView>,
+    effects: Vec<EffectsView>,
+    programs: Vec<ProgramView>,
+}
+
+impl From<&mollusk::ChainFixture> for ChainFixtureView {
+    fn from(fixture: &mollusk::ChainFixture) -> Self {
+        let input = fixture.input.clone().unwrap_or_default();
+        let accounts = input.accounts.iter().map(Into::into).collect::<Vec<_>>();
+        Self {
+            instructions: input
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    InstructionView::new(
+                        &instruction.program_id,
+                        instruction
+                            .instr_accounts
+                            .iter()
+                            .map(|a| (a.index, a.is_signer, a.is_writable)),
+                        &instruction.data,
+                        &accounts,
+                    )
+                })
+                .collect(),
+            accounts,
+            compute_budget: input.compute_budget,
+            features: features(&input.feature_set.unwrap_or_default().features),
+            sysvars: input.sysvars.as_ref().map(Into::into),
+            effects: fixture.output.iter().map(Into::into).collect(),
+            programs: fixture.programs.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FiredancerEffectsView {
+    result: i32,
+    custom_err: u32,
+    compute_units_available: u64,
+    return_data: DataView,
+    modified_accounts: Vec<AccountView>,
+}
+
+#[derive(Serialize)]
+struct FiredancerFixtureView {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entrypoint: Option<String>,
+    instruction: InstructionView,
+    accounts: Vec<AccountView>,
+    compute_units_available: u64,
+    slot: u64,
+    features: Vec<FeatureView>,
+    effects: Option<FiredancerEffectsView>,
+}
+
+impl From<&firedancer::InstrFixture> for FiredancerFixtureView {
+    fn from(fixture: &firedancer::InstrFixture) -> Self {
+        let input = fixture.input.clone().unwrap_or_default();
+        let accounts = input.accounts.iter().map(Into::into).collect::<Vec<_>>();
+        Self {
+            entrypoint: fixture
+                .metadata
+                .as_ref()
+                .map(|metadata| metadata.fn_entrypoint.clone()),
+            instruction: InstructionView::new(
+                &input.program_id,
+                input
+                    .instr_accounts
+                    .iter()
+                    .map(|a| (a.index, a.is_signer, a.is_writable)),
+                &input.data,
+                &accounts,
+            ),
+            accounts,
+            compute_units_available: input.cu_avail,
+            slot: input
+                .slot_context
+                .map_or(0, |slot_context| slot_context.slot),
+            features: features(
+                &input
+                    .epoch_context
+                    .and_then(|epoch_context| epoch_context.features)
+                    .unwrap_or_default()
+                    .features,
+            ),
+            effects: fixture
+                .output
+                .as_ref()
+                .map(|effects| FiredancerEffectsView {
+                    result: effects.result,
+                    custom_err: effects.custom_err,
+                    compute_units_available: effects.cu_avail,
+                    return_data: DataView::new(&effects.return_data),
+                    modified_accounts: effects.modified_accounts.iter().map(Into::into).collect(),
+                }),
+        }
+    }
+}
+
+fn print<V: Serialize>(view: &V, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(view)?);
+    } else {
+        print!("{}", serde_yaml::to_string(view)?);
+    }
+    Ok(())
+}
+
+/// Decode a fixture and print its context and effects, as YAML or JSON.
+pub fn inspect(
+    fixture_path: &str,
+    chain: bool,
+    proto: ProtoLayout,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let blob = std::fs::read(fixture_path)?;
+    let decode_err = |err| format!("Failed to decode {}: {}", fixture_path, err);
+    match proto {
+        ProtoLayout::Mollusk if chain => {
+            let fixture = mollusk::ChainFixture::try_decode(&blob).map_err(decode_err)?;
+            print(&ChainFixtureView::from(&fixture), json)
+        }
+        ProtoLayout::Mollusk => {
+            let fixture = mollusk::InstrFixture::try_decode(&blob).map_err(decode_err)?;
+            print(&MolluskFixtureView::from(&fixture), json)
+        }
+        ProtoLayout::Firedancer if chain => {
+            Err("Chain fixtures are only supported with the Mollusk protobuf layout".into())
+        }
+        ProtoLayout::Firedancer => {
+            let fixture = firedancer::InstrFixture::try_decode(&blob).map_err(decode_err)?;
+            print(&FiredancerFixtureView::from(&fixture), json)
+        }
+    }
+}
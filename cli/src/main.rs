@@ -1,13 +1,21 @@
 //! Mollusk CLI.
 
+mod bless;
+mod bundle;
 mod config;
+mod convert;
+mod corpus;
+mod inspect;
 mod runner;
 
 use {
-    crate::runner::{ProtoLayout, Runner},
+    crate::{
+        bundle::FilterArgs,
+        runner::{ProtoLayout, Runner},
+    },
     clap::{Parser, Subcommand},
     config::ConfigFile,
-    mollusk_svm::{result::Compare, Mollusk},
+    mollusk_svm::Mollusk,
     solana_sdk::{bpf_loader_upgradeable, pubkey::Pubkey},
     std::{fs, path::Path, str::FromStr},
 };
@@ -30,9 +38,18 @@ enum SubCommand {
         /// Path to the config file for validation checks.
         #[arg(short, long)]
         config: Option<String>,
+        /// Execute chain fixtures, containing a chain of instructions, rather
+        /// than single instruction fixtures. Requires the Mollusk protobuf
+        /// layout.
+        #[arg(long)]
+        chain: bool,
         /// Just execute the fixture without any validation.
         #[arg(short, long)]
         inputs_only: bool,
+        /// Print each fixture's report, listing every check and its outcome,
+        /// as a line of JSON rather than PASS or FAIL.
+        #[arg(long, conflicts_with = "verbose")]
+        json: bool,
         /// Enable emission of program logs to stdout. Disabled by default.
         #[arg(long)]
         program_logs: bool,
@@ -44,6 +61,37 @@ enum SubCommand {
         #[arg(short, long)]
         verbose: bool,
     },
+    /// Execute self-contained fixtures, using only the programs embedded in
+    /// them at ejection time (see `EJECT_FUZZ_FIXTURES_PROGRAMS`). Requires
+    /// the Mollusk protobuf layout.
+    Replay {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+
+        /// Path to the config file for validation checks.
+        #[arg(short, long)]
+        config: Option<String>,
+        /// Execute chain fixtures, containing a chain of instructions, rather
+        /// than single instruction fixtures.
+        #[arg(long)]
+        chain: bool,
+        /// Just execute the fixture without any validation.
+        #[arg(short, long)]
+        inputs_only: bool,
+        /// Print each fixture's report, listing every check and its outcome,
+        /// as a line of JSON rather than PASS or FAIL.
+        #[arg(long, conflicts_with = "verbose")]
+        json: bool,
+        /// Enable emission of program logs to stdout. Disabled by default.
+        #[arg(long)]
+        program_logs: bool,
+        /// Enable verbose mode for fixture effects. Does not enable program
+        /// logs. Disabled by default.
+        #[arg(short, long)]
+        verbose: bool,
+    },
     /// Execute a fixture across two Mollusk instances to compare the results
     /// of two versions of a program.
     RunTest {
@@ -65,12 +113,240 @@ enum SubCommand {
         /// Path to the config file for validation checks.
         #[arg(short, long)]
         config: Option<String>,
+        /// Execute chain fixtures, containing a chain of instructions, rather
+        /// than single instruction fixtures. Requires the Mollusk protobuf
+        /// layout.
+        #[arg(long)]
+        chain: bool,
+        /// Print each fixture's report, listing every check and its outcome,
+        /// as a line of JSON rather than PASS or FAIL.
+        #[arg(long, conflicts_with = "verbose")]
+        json: bool,
+        /// Enable emission of program logs to stdout. Disabled by default.
+        #[arg(long)]
+        program_logs: bool,
+        /// Protobuf layout to use when executing the fixture.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+        /// Enable verbose mode for fixture effects. Does not enable program
+        /// logs. Disabled by default.
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Find the smallest compute unit limit at which each fixture's
+    /// instruction still succeeds with identical effects.
+    MinComputeUnits {
+        /// The path to the ELF file.
+        #[arg(required = true)]
+        elf_path: String,
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+        /// The ID to use for the program.
+        #[arg(value_parser = Pubkey::from_str)]
+        program_id: Pubkey,
+
+        /// Enable emission of program logs to stdout. Disabled by default.
+        #[arg(long)]
+        program_logs: bool,
+        /// Protobuf layout to use when executing the fixture.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+        /// Enable verbose mode. Does not enable program logs. Disabled by
+        /// default.
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Minimize a fixture reproducing a program error, or a divergence
+    /// between two versions of a program, to the smallest inputs which still
+    /// reproduce it.
+    Minimize {
+        /// The path to the ELF file of the "ground truth" program.
+        #[arg(required = true)]
+        elf_path: String,
+        /// Path to an instruction fixture (`.fix` file).
+        #[arg(required = true)]
+        fixture: String,
+        /// The ID to use for the program.
+        #[arg(value_parser = Pubkey::from_str)]
+        program_id: Pubkey,
+
+        /// The path to the ELF file of a test program. If provided, the
+        /// fixture is minimized while both programs diverge on the same
+        /// checks. Otherwise, it's minimized while the program fails with the
+        /// same error.
+        #[arg(short, long)]
+        target: Option<String>,
+        /// Path to the config file for the checks compared between programs.
+        #[arg(short, long)]
+        config: Option<String>,
+        /// Path to write the minimized fixture to. Defaults to the fixture's
+        /// path with a `.min.fix` extension.
+        #[arg(short, long)]
+        output: Option<String>,
         /// Enable emission of program logs to stdout. Disabled by default.
         #[arg(long)]
         program_logs: bool,
         /// Protobuf layout to use when executing the fixture.
         #[arg(long, default_value = "mollusk")]
         proto: ProtoLayout,
+        /// Enable verbose mode. Does not enable program logs. Disabled by
+        /// default.
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Re-execute fixtures and rewrite their effects in place with the
+    /// results, for when a program change intentionally alters its behavior.
+    /// Prints the effects of each fixture which changed.
+    Bless {
+        /// The path to the ELF file.
+        #[arg(required = true)]
+        elf_path: String,
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+        /// The ID to use for the program.
+        #[arg(value_parser = Pubkey::from_str)]
+        program_id: Pubkey,
+
+        /// Bless chain fixtures, containing a chain of instructions, rather
+        /// than single instruction fixtures. Requires the Mollusk protobuf
+        /// layout.
+        #[arg(long)]
+        chain: bool,
+        /// Only report the effects which would change, without rewriting any
+        /// fixtures.
+        #[arg(long)]
+        dry_run: bool,
+        /// Enable emission of program logs to stdout. Disabled by default.
+        #[arg(long)]
+        program_logs: bool,
+        /// Protobuf layout of the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+        /// Enable verbose mode, also listing unchanged fixtures. Does not
+        /// enable program logs. Disabled by default.
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Convert fixtures between the Mollusk and Firedancer layouts, keeping
+    /// as much context as the target layout supports. Fields the target
+    /// layout can't represent are reported.
+    Convert {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+        /// Protobuf layout of the fixtures.
+        #[arg(long)]
+        from: ProtoLayout,
+        /// Protobuf layout to convert the fixtures to.
+        #[arg(long)]
+        to: ProtoLayout,
+        /// Path to the directory to write the converted fixtures to.
+        #[arg(short, long, required = true)]
+        output: String,
+    },
+    /// Decode a fixture and pretty-print its context and effects.
+    Inspect {
+        /// Path to a fixture (`.fix` file).
+        #[arg(required = true)]
+        fixture: String,
+
+        /// Inspect a chain fixture, containing a chain of instructions, rather
+        /// than a single instruction fixture. Requires the Mollusk protobuf
+        /// layout.
+        #[arg(long)]
+        chain: bool,
+        /// Print JSON rather than YAML.
+        #[arg(long)]
+        json: bool,
+        /// Protobuf layout of the fixture.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+    /// Work with fixture bundles: many fixtures packed into a single
+    /// compressed file, with an index.
+    Bundle {
+        #[clap(subcommand)]
+        command: BundleCommand,
+    },
+    /// Curate directories of fixtures.
+    Corpus {
+        #[clap(subcommand)]
+        command: CorpusCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum BundleCommand {
+    /// Pack fixtures into a bundle.
+    Create {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+        /// Path to write the bundle to.
+        #[arg(short, long, required = true)]
+        output: String,
+
+        /// Pack chain fixtures, containing a chain of instructions, rather
+        /// than single instruction fixtures. Requires the Mollusk protobuf
+        /// layout.
+        #[arg(long)]
+        chain: bool,
+        /// Protobuf layout of the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+    /// List the fixtures in a bundle, from its index.
+    List {
+        /// Path to the bundle.
+        #[arg(required = true)]
+        bundle: String,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Execute the fixtures in a bundle using Mollusk, either validating
+    /// them against their effects or, with a target program, comparing the
+    /// results of two versions of a program.
+    Run {
+        /// The path to the ELF file of the "ground truth" program.
+        #[arg(required = true)]
+        elf_path: String,
+        /// Path to the bundle.
+        #[arg(required = true)]
+        bundle: String,
+        /// The ID to use for the program.
+        #[arg(value_parser = Pubkey::from_str)]
+        program_id: Pubkey,
+
+        /// The path to the ELF file of a test program. If provided, each
+        /// fixture is executed against both programs and their results are
+        /// compared, as with `run-test`.
+        #[arg(short, long)]
+        target: Option<String>,
+        /// Path to the config file for validation checks.
+        #[arg(short, long)]
+        config: Option<String>,
+        #[command(flatten)]
+        filter: FilterArgs,
+        /// Just execute the fixtures without any validation.
+        #[arg(short, long)]
+        inputs_only: bool,
+        /// Print each fixture's report, listing every check and its outcome,
+        /// as a line of JSON rather than PASS or FAIL.
+        #[arg(long, conflicts_with = "verbose")]
+        json: bool,
+        /// Enable emission of program logs to stdout. Disabled by default.
+        #[arg(long)]
+        program_logs: bool,
+        /// Protobuf layout to use when executing the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
         /// Enable verbose mode for fixture effects. Does not enable program
         /// logs. Disabled by default.
         #[arg(short, long)]
@@ -78,6 +354,98 @@ enum SubCommand {
     },
 }
 
+#[derive(Subcommand)]
+enum CorpusCommand {
+    /// Remove fixtures with the same hash as another fixture, keeping the
+    /// first by path.
+    Dedupe {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+
+        /// Chain fixtures, containing a chain of instructions, rather than
+        /// single instruction fixtures. Requires the Mollusk protobuf layout.
+        #[arg(long)]
+        chain: bool,
+        /// Only report duplicates, without removing them.
+        #[arg(long)]
+        dry_run: bool,
+        /// Protobuf layout of the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+    /// Report the number of fixtures per program, program result and
+    /// instruction discriminator.
+    Stats {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+
+        /// Chain fixtures, containing a chain of instructions, rather than
+        /// single instruction fixtures. Requires the Mollusk protobuf layout.
+        #[arg(long)]
+        chain: bool,
+        /// The number of leading instruction data bytes making up the
+        /// discriminator. At most 8.
+        #[arg(long, default_value_t = 8)]
+        discriminator_len: usize,
+        /// Protobuf layout of the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+    /// Copy the fixtures matching the filters to a directory.
+    Filter {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+        /// Path to the directory to copy matching fixtures to.
+        #[arg(short, long, required = true)]
+        output: String,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+        /// Only select fixtures with this account in their context.
+        #[arg(long, value_parser = Pubkey::from_str)]
+        account: Option<Pubkey>,
+        /// Chain fixtures, containing a chain of instructions, rather than
+        /// single instruction fixtures. Requires the Mollusk protobuf layout.
+        #[arg(long)]
+        chain: bool,
+        /// Protobuf layout of the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+    /// Split fixtures into a training set, ie. a fuzzing corpus, and a
+    /// regression set. Fixtures are assigned by hash, so each one stays in
+    /// the same set as the corpus grows.
+    Split {
+        /// Path to an instruction fixture (`.fix` file) or a directory
+        /// containing them.
+        #[arg(required = true)]
+        fixture: String,
+        /// Path to the directory to copy the training set to.
+        #[arg(long, required = true)]
+        train: String,
+        /// Path to the directory to copy the regression set to.
+        #[arg(long, required = true)]
+        regression: String,
+
+        /// The percentage of fixtures in the regression set.
+        #[arg(long, default_value_t = 20)]
+        regression_percent: u8,
+        /// Chain fixtures, containing a chain of instructions, rather than
+        /// single instruction fixtures. Requires the Mollusk protobuf layout.
+        #[arg(long)]
+        chain: bool,
+        /// Protobuf layout of the fixtures.
+        #[arg(long, default_value = "mollusk")]
+        proto: ProtoLayout,
+    },
+}
+
 #[derive(Parser)]
 struct Cli {
     #[clap(subcommand)]
@@ -118,7 +486,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             fixture,
             program_id,
             config,
+            chain,
             inputs_only,
+            json,
             program_logs,
             proto,
             verbose,
@@ -126,17 +496,53 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let mut mollusk = Mollusk::default();
             add_elf_to_mollusk(&mut mollusk, &elf_path, &program_id);
 
-            let checks = if let Some(config_path) = config {
-                ConfigFile::try_load(&config_path)?.checks
-            } else {
-                // Defaults to all checks.
-                Compare::everything()
-            };
+            // Defaults to all the checks the fixtures record.
+            let checks = config
+                .map(|config_path| ConfigFile::try_load(&config_path).map(|config| config.checks))
+                .transpose()?;
 
-            let runner = Runner::new(checks, inputs_only, program_logs, proto, verbose);
+            let runner = Runner::new(checks, inputs_only, json, program_logs, proto, verbose);
 
             for fixture_path in search_paths(&fixture, "fix")? {
-                runner.run(&mut mollusk, None, &fixture_path)?;
+                if chain {
+                    runner.run_chain(&mut mollusk, None, &fixture_path)?;
+                } else {
+                    runner.run(&mut mollusk, None, &fixture_path)?;
+                }
+            }
+        }
+        SubCommand::Replay {
+            fixture,
+            config,
+            chain,
+            inputs_only,
+            json,
+            program_logs,
+            verbose,
+        } => {
+            // Defaults to all the checks the fixtures record.
+            let checks = config
+                .map(|config_path| ConfigFile::try_load(&config_path).map(|config| config.checks))
+                .transpose()?;
+
+            let runner = Runner::new(
+                checks,
+                inputs_only,
+                json,
+                program_logs,
+                ProtoLayout::Mollusk,
+                verbose,
+            );
+
+            for fixture_path in search_paths(&fixture, "fix")? {
+                // A fresh instance per fixture, so programs embedded in one
+                // fixture aren't used to replay another.
+                let mut mollusk = Mollusk::default();
+                if chain {
+                    runner.run_chain(&mut mollusk, None, &fixture_path)?;
+                } else {
+                    runner.run(&mut mollusk, None, &fixture_path)?;
+                }
             }
         }
         SubCommand::RunTest {
@@ -145,6 +551,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             fixture,
             program_id,
             config,
+            chain,
+            json,
             program_logs,
             proto,
             verbose,
@@ -157,25 +565,256 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let mut mollusk_test = Mollusk::default();
             add_elf_to_mollusk(&mut mollusk_test, &elf_path_target, &program_id);
 
-            let checks = if let Some(config_path) = config {
-                ConfigFile::try_load(&config_path)?.checks
-            } else {
-                // Defaults to all checks.
-                Compare::everything()
-            };
+            // Defaults to all the checks the fixtures record.
+            let checks = config
+                .map(|config_path| ConfigFile::try_load(&config_path).map(|config| config.checks))
+                .transpose()?;
 
             let runner = Runner::new(
                 checks,
                 /* inputs_only */ true,
+                json,
+                program_logs,
+                proto,
+                verbose,
+            );
+
+            for fixture_path in search_paths(&fixture, "fix")? {
+                if chain {
+                    runner.run_chain(
+                        &mut mollusk_ground,
+                        Some(&mut mollusk_test),
+                        &fixture_path,
+                    )?;
+                } else {
+                    runner.run(&mut mollusk_ground, Some(&mut mollusk_test), &fixture_path)?;
+                }
+            }
+        }
+        SubCommand::MinComputeUnits {
+            elf_path,
+            fixture,
+            program_id,
+            program_logs,
+            proto,
+            verbose,
+        } => {
+            let mut mollusk = Mollusk::default();
+            add_elf_to_mollusk(&mut mollusk, &elf_path, &program_id);
+
+            let runner = Runner::new(
+                Some(vec![]),
+                /* inputs_only */ true,
+                /* json */ false,
                 program_logs,
                 proto,
                 verbose,
             );
 
             for fixture_path in search_paths(&fixture, "fix")? {
-                runner.run(&mut mollusk_ground, Some(&mut mollusk_test), &fixture_path)?;
+                runner.find_min_compute_units(&mut mollusk, &fixture_path)?;
             }
         }
+        SubCommand::Minimize {
+            elf_path,
+            fixture,
+            program_id,
+            target,
+            config,
+            output,
+            program_logs,
+            proto,
+            verbose,
+        } => {
+            let mut mollusk_ground = Mollusk::default();
+            add_elf_to_mollusk(&mut mollusk_ground, &elf_path, &program_id);
+
+            let mut mollusk_test = target.map(|elf_path_target| {
+                let mut mollusk = Mollusk::default();
+                add_elf_to_mollusk(&mut mollusk, &elf_path_target, &program_id);
+                mollusk
+            });
+
+            // Defaults to all the checks the fixtures record.
+            let checks = config
+                .map(|config_path| ConfigFile::try_load(&config_path).map(|config| config.checks))
+                .transpose()?;
+
+            let output = output.unwrap_or_else(|| {
+                Path::new(&fixture)
+                    .with_extension("min.fix")
+                    .to_string_lossy()
+                    .into_owned()
+            });
+
+            let runner = Runner::new(
+                checks,
+                /* inputs_only */ true,
+                /* json */ false,
+                program_logs,
+                proto,
+                verbose,
+            );
+
+            runner.minimize(
+                &mut mollusk_ground,
+                mollusk_test.as_mut(),
+                &fixture,
+                &output,
+            )?;
+        }
+        SubCommand::Bless {
+            elf_path,
+            fixture,
+            program_id,
+            chain,
+            dry_run,
+            program_logs,
+            proto,
+            verbose,
+        } => {
+            let mut mollusk = Mollusk::default();
+            // Fixtures embedding programs are rewritten with the blessed ones.
+            mollusk.program_cache.set_elf_retention(true);
+            add_elf_to_mollusk(&mut mollusk, &elf_path, &program_id);
+
+            bless::bless(
+                &mut mollusk,
+                &search_paths(&fixture, "fix")?,
+                chain,
+                proto,
+                dry_run,
+                program_logs,
+                verbose,
+            )?;
+        }
+        SubCommand::Convert {
+            fixture,
+            from,
+            to,
+            output,
+        } => {
+            convert::convert(&search_paths(&fixture, "fix")?, from, to, &output)?;
+        }
+        SubCommand::Inspect {
+            fixture,
+            chain,
+            json,
+            proto,
+        } => {
+            inspect::inspect(&fixture, chain, proto, json)?;
+        }
+        SubCommand::Bundle { command } => match command {
+            BundleCommand::Create {
+                fixture,
+                output,
+                chain,
+                proto,
+            } => {
+                bundle::create(&search_paths(&fixture, "fix")?, &output, chain, proto)?;
+            }
+            BundleCommand::List { bundle, filter } => {
+                bundle::list(&bundle, &filter.to_filter()?)?;
+            }
+            BundleCommand::Run {
+                elf_path,
+                bundle,
+                program_id,
+                target,
+                config,
+                filter,
+                inputs_only,
+                json,
+                program_logs,
+                proto,
+                verbose,
+            } => {
+                let mut mollusk_ground = Mollusk::default();
+                add_elf_to_mollusk(&mut mollusk_ground, &elf_path, &program_id);
+
+                let mut mollusk_test = target.map(|elf_path_target| {
+                    let mut mollusk = Mollusk::default();
+                    add_elf_to_mollusk(&mut mollusk, &elf_path_target, &program_id);
+                    mollusk
+                });
+
+                // Defaults to all the checks the fixtures record.
+                let checks = config
+                    .map(|config_path| {
+                        ConfigFile::try_load(&config_path).map(|config| config.checks)
+                    })
+                    .transpose()?;
+
+                // Like `run-test`, comparing two programs ignores the effects.
+                let inputs_only = inputs_only || mollusk_test.is_some();
+                let runner = Runner::new(checks, inputs_only, json, program_logs, proto, verbose);
+
+                bundle::run(
+                    &runner,
+                    &mut mollusk_ground,
+                    mollusk_test.as_mut(),
+                    &bundle,
+                    &filter.to_filter()?,
+                )?;
+            }
+        },
+        SubCommand::Corpus { command } => match command {
+            CorpusCommand::Dedupe {
+                fixture,
+                chain,
+                dry_run,
+                proto,
+            } => {
+                corpus::dedupe(&search_paths(&fixture, "fix")?, chain, proto, dry_run)?;
+            }
+            CorpusCommand::Stats {
+                fixture,
+                chain,
+                discriminator_len,
+                proto,
+            } => {
+                corpus::stats(
+                    &search_paths(&fixture, "fix")?,
+                    chain,
+                    proto,
+                    discriminator_len,
+                )?;
+            }
+            CorpusCommand::Filter {
+                fixture,
+                output,
+                filter,
+                account,
+                chain,
+                proto,
+            } => {
+                corpus::filter(
+                    &search_paths(&fixture, "fix")?,
+                    &output,
+                    chain,
+                    proto,
+                    &filter.to_filter()?,
+                    account,
+                )?;
+            }
+            CorpusCommand::Split {
+                fixture,
+                train,
+                regression,
+                regression_percent,
+                chain,
+                proto,
+            } => {
+                corpus::split(
+                    &search_paths(&fixture, "fix")?,
+                    &train,
+                    &regression,
+                    regression_percent,
+                    chain,
+                    proto,
+                )?;
+            }
+        },
     }
     Ok(())
 }
@@ -3,9 +3,13 @@
 use {
     clap::ValueEnum,
     mollusk_svm::{
-        result::{Compare, Config, InstructionResult},
+        report::{CheckOutcome, CheckReport},
+        result::{Compare, Config, InstructionResult, ProgramResult},
         Mollusk,
     },
+    mollusk_svm_fuzz_fs::{FsHandler, SerializableFixture},
+    mollusk_svm_fuzz_mutator::{FixtureAccount, MutableFixture},
+    solana_sdk::transaction::TransactionError,
 };
 
 #[derive(Clone, Debug, Default, ValueEnum)]
@@ -17,73 +21,303 @@ pub enum ProtoLayout {
     Firedancer,
 }
 
+/// What a fixture reproduces, which minimization must preserve.
+#[derive(Debug, PartialEq)]
+enum Reproduction {
+    /// The program's error.
+    Error(ProgramResult),
+    /// The checks, and accounts checked, on which two programs diverge.
+    Divergence(Vec<(String, Option<String>)>),
+}
+
+fn describe<F: MutableFixture>(fixture: &F) -> String {
+    format!(
+        "{} accounts, {} account data bytes, {} instruction accounts, {} instruction data bytes",
+        fixture.accounts().len(),
+        fixture
+            .accounts()
+            .iter()
+            .map(|account| account.data().len())
+            .sum::<usize>(),
+        fixture.instruction_accounts().len(),
+        fixture.instruction_data().len(),
+    )
+}
+
+/// The report of a fixture's checks, as printed with `--json`.
+#[derive(serde::Serialize)]
+struct FixtureReport<'a> {
+    fixture: &'a str,
+    passed: bool,
+    #[serde(flatten)]
+    report: &'a CheckReport,
+}
+
+/// Prefix the name of each check in a report, ie. with the program it was
+/// run against.
+fn prefixed(mut report: CheckReport, prefix: &str) -> CheckReport {
+    report
+        .outcomes
+        .iter_mut()
+        .for_each(|outcome| outcome.check = format!("{}: {}", prefix, outcome.check));
+    report
+}
+
+/// The results of each instruction in a chain, the effects of each one and
+/// the earliest version of the effects.
+type ChainRun = (Vec<InstructionResult>, Vec<InstructionResult>, u32);
+
 pub struct Runner {
     checks: Vec<Compare>,
+    /// Whether the checks were requested, rather than the defaults.
+    requested_checks: bool,
     inputs_only: bool,
+    json: bool,
     program_logs: bool,
     proto: ProtoLayout,
     verbose: bool,
 }
 
 impl Runner {
+    /// Without requested checks, fixtures are checked against everything their
+    /// layout records.
     pub fn new(
-        checks: Vec<Compare>,
+        checks: Option<Vec<Compare>>,
         inputs_only: bool,
+        json: bool,
         program_logs: bool,
         proto: ProtoLayout,
         verbose: bool,
     ) -> Self {
+        let requested_checks = checks.is_some();
+        let checks = checks.unwrap_or_else(|| match proto {
+            ProtoLayout::Mollusk => Compare::all_effects(),
+            ProtoLayout::Firedancer => Compare::everything(),
+        });
         Self {
             checks,
+            requested_checks,
             inputs_only,
+            json,
             program_logs,
             proto,
             verbose,
         }
     }
 
-    // Returns the result from the instruction, and the effects converted to
-    // `InstrucionResult`.
+    /// The protobuf layout of the fixtures run.
+    pub fn proto(&self) -> &ProtoLayout {
+        &self.proto
+    }
+
+    // Returns the result from the instruction, the effects converted to
+    // `InstrucionResult`, and the version of the effects.
     fn run_fixture(
         &self,
         mollusk: &mut Mollusk,
-        fixture_path: &str,
-    ) -> (InstructionResult, InstructionResult) {
+        blob: &[u8],
+    ) -> (InstructionResult, InstructionResult, u32) {
         match self.proto {
             ProtoLayout::Mollusk => {
-                let fixture = mollusk_svm_fuzz_fixture::Fixture::load_from_blob_file(fixture_path);
+                let fixture = mollusk_svm_fuzz_fixture::Fixture::decode(blob);
                 let result = mollusk.process_fixture(&fixture);
                 let effects = (&fixture.output).into();
-                (result, effects)
+                (result, effects, fixture.output.version)
             }
             ProtoLayout::Firedancer => {
-                let fixture =
-                    mollusk_svm_fuzz_fixture_firedancer::Fixture::load_from_blob_file(fixture_path);
+                let fixture = mollusk_svm_fuzz_fixture_firedancer::Fixture::decode(blob);
                 let result = mollusk.process_firedancer_fixture(&fixture);
                 let (_, effects) = mollusk_svm::fuzz::firedancer::load_firedancer_fixture(&fixture);
-                (result, effects)
+                // Firedancer fixtures only record the original effects.
+                (result, effects, 0)
             }
         }
     }
 
+    // Returns the result of each instruction in a chain, the effects of each
+    // one converted to `InstructionResult`, and the earliest version of the
+    // effects.
+    fn run_chain_fixture(
+        &self,
+        mollusk: &mut Mollusk,
+        blob: &[u8],
+    ) -> Result<ChainRun, Box<dyn std::error::Error>> {
+        match self.proto {
+            ProtoLayout::Mollusk => {
+                let fixture = mollusk_svm_fuzz_fixture::chain::ChainFixture::decode(blob);
+                let results = mollusk.process_chain_fixture(&fixture);
+                let (_, effects) = mollusk_svm::fuzz::mollusk::load_chain_fixture(&fixture);
+                let version = fixture
+                    .output
+                    .iter()
+                    .map(|effects| effects.version)
+                    .min()
+                    .unwrap_or_default();
+                Ok((results, effects, version))
+            }
+            ProtoLayout::Firedancer => {
+                Err("Chain fixtures are only supported with the Mollusk protobuf layout".into())
+            }
+        }
+    }
+
+    fn config(&self) -> Config {
+        Config {
+            panic: false,
+            verbose: self.verbose,
+        }
+    }
+
+    // Compares a result against fixture effects of the provided version.
+    // Requested checks of fields the effects don't record fail, while the
+    // defaults are limited to the fields they record.
+    fn compare_effects(
+        &self,
+        result: &InstructionResult,
+        effects: &InstructionResult,
+        version: u32,
+    ) -> CheckReport {
+        if self.requested_checks {
+            result.compare_with_effects_report(effects, &self.checks, version, &self.config())
+        } else {
+            result.compare_with_report(
+                effects,
+                &Compare::for_effects_version(&self.checks, version),
+                &self.config(),
+            )
+        }
+    }
+
+    // Compares the results of each instruction in a chain, which must stop
+    // at the same instruction.
+    fn compare_chain(
+        &self,
+        a: &[InstructionResult],
+        b: &[InstructionResult],
+        compare: impl Fn(&InstructionResult, &InstructionResult) -> CheckReport,
+    ) -> CheckReport {
+        let mut report = CheckReport::default();
+        if a.len() != b.len() {
+            report.outcomes.push(CheckOutcome {
+                check: "instruction_count".to_string(),
+                account: None,
+                passed: false,
+                expected: Some(a.len().to_string()),
+                actual: Some(b.len().to_string()),
+                detail: None,
+            });
+        }
+        for (index, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+            let mut instruction_report = compare(a, b);
+            instruction_report.outcomes.iter_mut().for_each(|outcome| {
+                outcome.check = format!("instructions[{}].{}", index, outcome.check)
+            });
+            report.extend(instruction_report);
+        }
+        report
+    }
+
+    // Prints whether the fixture passed, along with the checks which failed,
+    // or the whole report as a line of JSON.
+    fn print_report(
+        &self,
+        fixture_path: &str,
+        report: &CheckReport,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.json {
+            let report = FixtureReport {
+                fixture: fixture_path,
+                passed: report.passed(),
+                report,
+            };
+            println!("{}", serde_json::to_string(&report)?);
+        } else if report.passed() {
+            println!("PASS: {}", fixture_path);
+        } else {
+            println!("FAIL: {}", fixture_path);
+            // Verbose mode already printed each failure as it was checked.
+            if !self.verbose {
+                for failure in report.failures() {
+                    println!("  {}", failure.to_string().replace('\n', "\n  "));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn find_min_compute_units(
+        &self,
+        mollusk: &mut Mollusk,
+        fixture_path: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Disable stdout logging of program logs if not specified.
+        if !self.program_logs {
+            solana_logger::setup_with("");
+        }
+
+        let (instruction, accounts) = match self.proto {
+            ProtoLayout::Mollusk => {
+                let fixture = mollusk_svm_fuzz_fixture::Fixture::load_from_blob_file(fixture_path);
+                let (context, _) = mollusk_svm::fuzz::mollusk::load_fixture(&fixture);
+                mollusk.compute_budget = context.compute_budget;
+                mollusk.feature_set = context.feature_set;
+                mollusk.sysvars = context.sysvars;
+                (context.instruction, context.accounts)
+            }
+            ProtoLayout::Firedancer => {
+                let fixture =
+                    mollusk_svm_fuzz_fixture_firedancer::Fixture::load_from_blob_file(fixture_path);
+                let (context, _) = mollusk_svm::fuzz::firedancer::load_firedancer_fixture(&fixture);
+                mollusk.compute_budget = context.compute_budget;
+                mollusk.feature_set = context.feature_set;
+                mollusk.slot = context.slot;
+                (context.instruction, context.accounts)
+            }
+        };
+
+        if self.verbose {
+            println!("FIX: {}", fixture_path);
+        }
+
+        match mollusk.find_min_compute_units(&instruction, &accounts) {
+            Some(units) => println!("{}: {}", fixture_path, units),
+            None => println!("FAIL: {}", fixture_path),
+        }
+
+        Ok(())
+    }
+
     pub fn run(
         &self,
         ground: &mut Mollusk,
         target: Option<&mut Mollusk>,
         fixture_path: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let blob = std::fs::read(fixture_path)?;
+        self.run_blob(ground, target, fixture_path, &blob)
+    }
+
+    /// Like `run`, but for a fixture blob already in memory, such as one
+    /// read from a bundle. The path is only used for reporting.
+    pub fn run_blob(
+        &self,
+        ground: &mut Mollusk,
+        target: Option<&mut Mollusk>,
+        fixture_path: &str,
+        blob: &[u8],
     ) -> Result<(), Box<dyn std::error::Error>> {
         // Disable stdout logging of program logs if not specified.
         if !self.program_logs {
             solana_logger::setup_with("");
         }
 
-        let mut pass = true;
+        let mut report = CheckReport::default();
 
         if self.verbose {
             println!("[GROUND]: FIX: {}", fixture_path);
         }
 
-        let (ground_result, effects) = self.run_fixture(ground, fixture_path);
+        let (ground_result, effects, version) = self.run_fixture(ground, blob);
 
         if self.inputs_only && self.verbose {
             println!("[GROUND]: RESULT:\n{:?}", &ground_result);
@@ -91,14 +325,12 @@ impl Runner {
 
         if !self.inputs_only {
             // Compare against the effects.
-            pass &= ground_result.compare_with_config(
-                &effects,
-                &self.checks,
-                &Config {
-                    panic: false,
-                    verbose: self.verbose,
-                },
-            );
+            let ground_report = self.compare_effects(&ground_result, &effects, version);
+            report.extend(if target.is_some() {
+                prefixed(ground_report, "ground")
+            } else {
+                ground_report
+            });
         }
 
         if let Some(target) = target {
@@ -108,39 +340,241 @@ impl Runner {
                 println!("[TARGET]: FIX: {}", &fixture_path);
             }
 
-            let (target_result, _) = self.run_fixture(target, fixture_path);
+            let (target_result, ..) = self.run_fixture(target, blob);
 
-            if self.inputs_only || self.verbose {
+            if (self.inputs_only || self.verbose) && !self.json {
                 println!("[TARGET]: RESULT:\n{:?}", &target_result);
             }
 
             if !self.inputs_only {
                 // Compare against the effects.
-                pass &= target_result.compare_with_config(
-                    &effects,
-                    &self.checks,
-                    &Config {
-                        panic: false,
-                        verbose: self.verbose,
-                    },
-                );
+                report.extend(prefixed(
+                    self.compare_effects(&target_result, &effects, version),
+                    "target",
+                ));
             }
 
             // Compare the two results.
-            pass &= ground_result.compare_with_config(
+            report.extend(ground_result.compare_with_report(
                 &target_result,
                 &self.checks,
-                &Config {
-                    panic: false,
-                    verbose: self.verbose,
-                },
-            );
+                &self.config(),
+            ));
         }
 
-        if pass {
-            println!("PASS: {}", &fixture_path);
-        } else {
-            println!("FAIL: {}", &fixture_path);
+        self.print_report(fixture_path, &report)
+    }
+
+    pub fn run_chain(
+        &self,
+        ground: &mut Mollusk,
+        target: Option<&mut Mollusk>,
+        fixture_path: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let blob = std::fs::read(fixture_path)?;
+        self.run_chain_blob(ground, target, fixture_path, &blob)
+    }
+
+    /// Like `run_chain`, but for a fixture blob already in memory, such as one
+    /// read from a bundle. The path is only used for reporting.
+    pub fn run_chain_blob(
+        &self,
+        ground: &mut Mollusk,
+        target: Option<&mut Mollusk>,
+        fixture_path: &str,
+        blob: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Disable stdout logging of program logs if not specified.
+        if !self.program_logs {
+            solana_logger::setup_with("");
+        }
+
+        let mut report = CheckReport::default();
+
+        if self.verbose {
+            println!("[GROUND]: FIX: {}", fixture_path);
+        }
+
+        let (ground_results, effects, version) = self.run_chain_fixture(ground, blob)?;
+        let compare_effects = |result: &InstructionResult, effects: &InstructionResult| {
+            self.compare_effects(result, effects, version)
+        };
+        let compare_results = |a: &InstructionResult, b: &InstructionResult| {
+            a.compare_with_report(b, &self.checks, &self.config())
+        };
+
+        if self.inputs_only && self.verbose {
+            for (i, result) in ground_results.iter().enumerate() {
+                println!("[GROUND]: RESULT {}:\n{:?}", i, result);
+            }
+        }
+
+        if !self.inputs_only {
+            // Compare against the effects.
+            let ground_report = self.compare_chain(&ground_results, &effects, compare_effects);
+            report.extend(if target.is_some() {
+                prefixed(ground_report, "ground")
+            } else {
+                ground_report
+            });
+        }
+
+        if let Some(target) = target {
+            // Command `run-test`.
+
+            if self.verbose {
+                println!("[TARGET]: FIX: {}", &fixture_path);
+            }
+
+            let (target_results, ..) = self.run_chain_fixture(target, blob)?;
+
+            if (self.inputs_only || self.verbose) && !self.json {
+                for (i, result) in target_results.iter().enumerate() {
+                    println!("[TARGET]: RESULT {}:\n{:?}", i, result);
+                }
+            }
+
+            if !self.inputs_only {
+                // Compare against the effects.
+                report.extend(prefixed(
+                    self.compare_chain(&target_results, &effects, compare_effects),
+                    "target",
+                ));
+            }
+
+            // Compare the two results.
+            report.extend(self.compare_chain(&ground_results, &target_results, compare_results));
+        }
+
+        self.print_report(fixture_path, &report)
+    }
+
+    fn reproduction<F>(
+        &self,
+        ground: &mut Mollusk,
+        target: Option<&mut Mollusk>,
+        fixture: &F,
+        process: &impl Fn(&mut Mollusk, &F) -> InstructionResult,
+    ) -> Option<Reproduction> {
+        let ground_result = process(ground, fixture);
+        match target {
+            Some(target) => {
+                let target_result = process(target, fixture);
+                let divergence = ground_result
+                    .compare_with_report(
+                        &target_result,
+                        &self.checks,
+                        &Config {
+                            panic: false,
+                            verbose: false,
+                        },
+                    )
+                    .failures()
+                    .map(|outcome| (outcome.check.clone(), outcome.account.clone()))
+                    .collect::<Vec<_>>();
+                (!divergence.is_empty()).then_some(Reproduction::Divergence(divergence))
+            }
+            None => ground_result
+                .program_result
+                .is_err()
+                .then_some(Reproduction::Error(ground_result.program_result)),
+        }
+    }
+
+    // Minimizes a fixture's inputs while it reproduces the same error or
+    // divergence. Returns `None` if the fixture reproduces neither.
+    fn minimize_fixture<F: MutableFixture + Clone>(
+        &self,
+        ground: &mut Mollusk,
+        mut target: Option<&mut Mollusk>,
+        fixture: &F,
+        process: impl Fn(&mut Mollusk, &F) -> InstructionResult,
+    ) -> Option<F> {
+        let original = self.reproduction(ground, target.as_deref_mut(), fixture, &process)?;
+        if self.verbose {
+            println!("REPRODUCES: {:?}", original);
+        }
+        Some(mollusk_svm_fuzz_mutator::minimize(fixture, |candidate| {
+            self.reproduction(ground, target.as_deref_mut(), candidate, &process)
+                .is_some_and(|reproduction| reproduction == original)
+        }))
+    }
+
+    pub fn minimize(
+        &self,
+        ground: &mut Mollusk,
+        target: Option<&mut Mollusk>,
+        fixture_path: &str,
+        output_path: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Disable stdout logging of program logs if not specified.
+        if !self.program_logs {
+            solana_logger::setup_with("");
+        }
+
+        // The effects of the minimized fixture are those of the ground truth
+        // program.
+        let minimized = match self.proto {
+            ProtoLayout::Mollusk => {
+                let fixture: mollusk_svm_fuzz_fixture::proto::InstrFixture =
+                    FsHandler::load_from_blob_file(fixture_path);
+                self.minimize_fixture(ground, target, &fixture, |mollusk, fixture| {
+                    mollusk.process_fixture(&fixture.clone().into())
+                })
+                .map(|minimized| {
+                    println!("BEFORE: {}", describe(&fixture));
+                    println!("AFTER: {}", describe(&minimized));
+                    let fixture = mollusk_svm_fuzz_fixture::Fixture::from(minimized);
+                    let result = ground.process_fixture(&fixture);
+                    let (context, _) = mollusk_svm::fuzz::mollusk::load_fixture(&fixture);
+                    let mut rebuilt = mollusk_svm::fuzz::mollusk::build_fixture_from_mollusk_test(
+                        ground,
+                        &context.instruction,
+                        &context.accounts,
+                        &result,
+                    )?;
+                    // Keep any embedded programs, so the minimized fixture
+                    // can still be replayed on its own.
+                    rebuilt.programs = fixture.programs;
+                    Ok::<_, TransactionError>(SerializableFixture::encode(
+                        &mollusk_svm_fuzz_fixture::proto::InstrFixture::from(rebuilt),
+                    ))
+                })
+                .transpose()?
+            }
+            ProtoLayout::Firedancer => {
+                let fixture: mollusk_svm_fuzz_fixture_firedancer::proto::InstrFixture =
+                    FsHandler::load_from_blob_file(fixture_path);
+                self.minimize_fixture(ground, target, &fixture, |mollusk, fixture| {
+                    mollusk.process_firedancer_fixture(&fixture.clone().into())
+                })
+                .map(|minimized| {
+                    println!("BEFORE: {}", describe(&fixture));
+                    println!("AFTER: {}", describe(&minimized));
+                    let fixture = mollusk_svm_fuzz_fixture_firedancer::Fixture::from(minimized);
+                    let result = ground.process_firedancer_fixture(&fixture);
+                    let (context, _) =
+                        mollusk_svm::fuzz::firedancer::load_firedancer_fixture(&fixture);
+                    let fixture = mollusk_svm::fuzz::firedancer::build_fixture_from_mollusk_test(
+                        ground,
+                        &context.instruction,
+                        &context.accounts,
+                        &result,
+                    )?;
+                    Ok::<_, TransactionError>(SerializableFixture::encode(
+                        &mollusk_svm_fuzz_fixture_firedancer::proto::InstrFixture::from(fixture),
+                    ))
+                })
+                .transpose()?
+            }
+        };
+
+        match minimized {
+            Some(blob) => {
+                std::fs::write(output_path, blob)?;
+                println!("MINIMIZED: {} -> {}", fixture_path, output_path);
+            }
+            None => println!("NOTHING TO MINIMIZE: {}", fixture_path),
         }
 
         Ok(())
@@ -1,5 +1,22 @@
 use std::io::Result;
 
+// Fields serialized in a readable form, by way of the helpers in
+// `mollusk_svm_fuzz_fs::readable`.
+const BASE58_FIELDS: &[&str] = &[
+    "AcctState.address",
+    "AcctState.owner",
+    "InstrContext.program_id",
+    "SeedAddress.base",
+    "SeedAddress.owner",
+];
+const HEX_FIELDS: &[&str] = &[
+    "AcctState.data",
+    "InstrContext.data",
+    "InstrEffects.return_data",
+    "SeedAddress.seed",
+];
+const FEATURES_FIELDS: &[&str] = &["FeatureSet.features"];
+
 fn main() -> Result<()> {
     let proto_base_path = std::path::PathBuf::from("proto");
 
@@ -13,9 +30,28 @@ fn main() -> Result<()> {
         .iter()
         .for_each(|proto| println!("cargo:rerun-if-changed={}", proto.display()));
 
-    prost_build::Config::new()
+    let mut config = prost_build::Config::new();
+    config
         .type_attribute(".", "#[derive(serde::Deserialize, serde::Serialize)]")
-        .compile_protos(protos, &[proto_base_path])?;
+        // Fields missing from hand-written fixtures take their protobuf
+        // defaults.
+        .type_attribute(".", "#[serde(default)]");
+    for (fields, helper) in [
+        (BASE58_FIELDS, "base58"),
+        (HEX_FIELDS, "hex"),
+        (FEATURES_FIELDS, "features"),
+    ] {
+        for field in fields {
+            config.field_attribute(
+                format!(".org.solana.sealevel.v1.{}", field),
+                format!(
+                    "#[serde(with = \"mollusk_svm_fuzz_fs::readable::{}\")]",
+                    helper
+                ),
+            );
+        }
+    }
+    config.compile_protos(protos, &[proto_base_path])?;
 
     Ok(())
 }
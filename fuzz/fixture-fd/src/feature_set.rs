@@ -1,16 +1,12 @@
 //! Runtime feature set.
 
+// Omit "test features" (they have the same u64 ID).
+pub use mollusk_svm_fuzz_fs::readable::features::OMITTED_FEATURES;
 use {
     super::proto::FeatureSet as ProtoFeatureSet,
-    solana_sdk::{feature_set::FeatureSet, keccak::Hasher, pubkey::Pubkey},
+    solana_sdk::{feature_set::FeatureSet, keccak::Hasher},
 };
 
-// Omit "test features" (they have the same u64 ID).
-pub static OMITTED_FEATURES: &[Pubkey] = &[
-    solana_sdk::feature_set::disable_sbpf_v1_execution::id(),
-    solana_sdk::feature_set::reenable_sbpf_v1_execution::id(),
-];
-
 impl From<ProtoFeatureSet> for FeatureSet {
     fn from(value: ProtoFeatureSet) -> Self {
         let mut feature_set = Self::default();
@@ -23,8 +23,13 @@ use {
     crate::{
         context::Context, effects::Effects, metadata::Metadata, proto::InstrFixture as ProtoFixture,
     },
-    mollusk_svm_fuzz_fs::{FsHandler, IntoSerializableFixture, SerializableFixture},
-    solana_sdk::keccak::{Hash, Hasher},
+    mollusk_svm_fuzz_fs::{
+        bundle::IndexableFixture, FsHandler, IntoSerializableFixture, SerializableFixture,
+    },
+    solana_sdk::{
+        keccak::{Hash, Hasher},
+        pubkey::Pubkey,
+    },
 };
 
 /// A fixture for invoking a single instruction against a simulated SVM
@@ -54,6 +59,11 @@ impl Fixture {
         let proto_fixture: ProtoFixture = FsHandler::load_from_json_file(file_path);
         proto_fixture.into()
     }
+
+    pub fn load_from_yaml_file(file_path: &str) -> Self {
+        let proto_fixture: ProtoFixture = FsHandler::load_from_yaml_file(file_path);
+        proto_fixture.into()
+    }
 }
 
 impl From<ProtoFixture> for Fixture {
@@ -94,6 +104,27 @@ impl SerializableFixture for ProtoFixture {
     }
 }
 
+impl IndexableFixture for ProtoFixture {
+    const LAYOUT: &'static str = "firedancer";
+
+    fn program_id(&self) -> Pubkey {
+        self.input
+            .as_ref()
+            .and_then(|input| Pubkey::try_from(input.program_id.as_slice()).ok())
+            .unwrap_or_default()
+    }
+
+    fn instruction_data(&self) -> &[u8] {
+        self.input.as_ref().map_or(&[], |input| &input.data)
+    }
+
+    fn program_result(&self) -> u64 {
+        self.output
+            .as_ref()
+            .map_or(0, |output| u64::try_from(output.result).unwrap_or(u64::MAX))
+    }
+}
+
 impl IntoSerializableFixture for Fixture {
     type Fixture = ProtoFixture;
 
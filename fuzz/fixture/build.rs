@@ -1,21 +1,68 @@
 use std::io::Result;
 
+// Fields serialized in a readable form, by way of the helpers in
+// `mollusk_svm_fuzz_fs::readable`.
+const BASE58_FIELDS: &[&str] = &[
+    "AcctState.address",
+    "AcctState.owner",
+    "ChainInstr.program_id",
+    "EpochRewards.parent_blockhash",
+    "InnerInstr.program_id",
+    "InnerInstrAcct.address",
+    "InstrContext.program_id",
+    "InstrEffects.return_data_program_id",
+    "ProgramElf.loader",
+    "ProgramElf.program_id",
+    "SlotHashEntry.hash",
+];
+const HEX_FIELDS: &[&str] = &[
+    "AcctState.data",
+    "ChainInstr.data",
+    "EpochRewards.total_points",
+    "InnerInstr.data",
+    "InstrContext.data",
+    "InstrEffects.return_data",
+    "ProgramElf.elf",
+];
+const FEATURES_FIELDS: &[&str] = &["FeatureSet.features"];
+
 fn main() -> Result<()> {
     let proto_base_path = std::path::PathBuf::from("proto");
 
     let protos = &[
         proto_base_path.join("compute_budget.proto"),
         proto_base_path.join("sysvars.proto"),
+        proto_base_path.join("program.proto"),
         proto_base_path.join("invoke.proto"),
+        proto_base_path.join("chain.proto"),
     ];
 
     protos
         .iter()
         .for_each(|proto| println!("cargo:rerun-if-changed={}", proto.display()));
 
-    prost_build::Config::new()
+    let mut config = prost_build::Config::new();
+    config
         .type_attribute(".", "#[derive(serde::Deserialize, serde::Serialize)]")
-        .compile_protos(protos, &[proto_base_path])?;
+        // Fields missing from hand-written fixtures take their protobuf
+        // defaults.
+        .type_attribute(".", "#[serde(default)]");
+    for (fields, helper) in [
+        (BASE58_FIELDS, "base58"),
+        (HEX_FIELDS, "hex"),
+        (FEATURES_FIELDS, "features"),
+    ] {
+        for field in fields {
+            config.field_attribute(
+                format!(".org.mollusk.svm.{}", field),
+                format!(
+                    "#[serde(with = \"mollusk_svm_fuzz_fs::readable::{}\")]",
+                    helper
+                ),
+            );
+        }
+    }
+    config.compile_protos(protos, &[proto_base_path])?;
 
     Ok(())
 }
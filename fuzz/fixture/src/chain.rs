@@ -0,0 +1,306 @@
+//! A chain of instructions, processed in order against the same accounts.
+
+use {
+    crate::{
+        context::{
+            hash_proto_instruction_accounts, instruction_accounts_from_proto,
+            instruction_accounts_to_proto,
+        },
+        effects::Effects,
+        program::ProgramElf,
+        proto::{
+            ChainContext as ProtoChainContext, ChainFixture as ProtoChainFixture,
+            ChainInstr as ProtoChainInstruction,
+        },
+        sysvars::Sysvars,
+    },
+    mollusk_svm_fuzz_fs::{
+        bundle::IndexableFixture, FsHandler, IntoSerializableFixture, SerializableFixture,
+    },
+    solana_compute_budget::compute_budget::ComputeBudget,
+    solana_sdk::{
+        account::Account,
+        feature_set::FeatureSet,
+        instruction::Instruction,
+        keccak::{Hash, Hasher},
+        pubkey::Pubkey,
+    },
+};
+
+/// Instruction chain context fixture.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChainContext {
+    /// The compute budget to use for the simulation.
+    pub compute_budget: ComputeBudget,
+    /// The feature set to use for the simulation.
+    pub feature_set: FeatureSet,
+    /// The runtime sysvars to use for the simulation.
+    pub sysvars: Sysvars,
+    /// The instructions, in the order they are processed. Each instruction
+    /// is processed against the accounts resulting from the previous one.
+    pub instructions: Vec<Instruction>,
+    /// Input accounts with state, before the first instruction.
+    pub accounts: Vec<(Pubkey, Account)>,
+}
+
+impl From<ProtoChainContext> for ChainContext {
+    fn from(value: ProtoChainContext) -> Self {
+        let accounts: Vec<(Pubkey, Account)> = value.accounts.into_iter().map(Into::into).collect();
+
+        let instructions = value
+            .instructions
+            .into_iter()
+            .map(
+                |ProtoChainInstruction {
+                     program_id,
+                     instr_accounts,
+                     data,
+                 }| {
+                    let program_id_bytes: [u8; 32] =
+                        program_id.try_into().expect("Invalid bytes for program ID");
+                    Instruction {
+                        program_id: Pubkey::new_from_array(program_id_bytes),
+                        accounts: instruction_accounts_from_proto(&accounts, instr_accounts),
+                        data,
+                    }
+                },
+            )
+            .collect();
+
+        Self {
+            compute_budget: value.compute_budget.map(Into::into).unwrap_or_default(),
+            feature_set: value.feature_set.map(Into::into).unwrap_or_default(),
+            sysvars: value.sysvars.map(Into::into).unwrap_or_default(),
+            instructions,
+            accounts,
+        }
+    }
+}
+
+impl From<ChainContext> for ProtoChainContext {
+    fn from(value: ChainContext) -> Self {
+        let instructions = value
+            .instructions
+            .into_iter()
+            .map(
+                |Instruction {
+                     program_id,
+                     accounts,
+                     data,
+                 }| ProtoChainInstruction {
+                    program_id: program_id.to_bytes().to_vec(),
+                    instr_accounts: instruction_accounts_to_proto(&value.accounts, accounts),
+                    data,
+                },
+            )
+            .collect();
+
+        let accounts = value.accounts.into_iter().map(Into::into).collect();
+
+        Self {
+            compute_budget: Some(value.compute_budget.into()),
+            feature_set: Some(value.feature_set.into()),
+            sysvars: Some(value.sysvars.into()),
+            instructions,
+            accounts,
+        }
+    }
+}
+
+fn hash_proto_chain_context(hasher: &mut Hasher, context: &ProtoChainContext) {
+    if let Some(compute_budget) = &context.compute_budget {
+        crate::compute_budget::hash_proto_compute_budget(hasher, compute_budget);
+    }
+    if let Some(feature_set) = &context.feature_set {
+        crate::feature_set::hash_proto_feature_set(hasher, feature_set);
+    }
+    if let Some(sysvars) = &context.sysvars {
+        crate::sysvars::hash_proto_sysvars(hasher, sysvars);
+    }
+    for instruction in context.instructions.iter() {
+        hasher.hash(&instruction.program_id);
+        hash_proto_instruction_accounts(hasher, &instruction.instr_accounts);
+        hasher.hash(&instruction.data);
+    }
+    crate::account::hash_proto_accounts(hasher, &context.accounts);
+}
+
+/// A fixture for invoking a chain of instructions against a simulated SVM
+/// program runtime environment.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChainFixture {
+    /// The fixture inputs.
+    pub input: ChainContext,
+    /// The effects of each processed instruction, in order. The chain stops
+    /// at the first failing instruction, so there may be fewer effects than
+    /// instructions.
+    pub output: Vec<Effects>,
+    /// The programs in the cache when the fixture was ejected, if embedded.
+    /// Replaying the fixture loads any of them missing from the cache.
+    pub programs: Vec<ProgramElf>,
+}
+
+impl ChainFixture {
+    pub fn decode(blob: &[u8]) -> Self {
+        let proto_fixture = <ProtoChainFixture as SerializableFixture>::decode(blob);
+        proto_fixture.into()
+    }
+
+    pub fn load_from_blob_file(file_path: &str) -> Self {
+        let proto_fixture: ProtoChainFixture = FsHandler::load_from_blob_file(file_path);
+        proto_fixture.into()
+    }
+
+    pub fn load_from_json_file(file_path: &str) -> Self {
+        let proto_fixture: ProtoChainFixture = FsHandler::load_from_json_file(file_path);
+        proto_fixture.into()
+    }
+
+    pub fn load_from_yaml_file(file_path: &str) -> Self {
+        let proto_fixture: ProtoChainFixture = FsHandler::load_from_yaml_file(file_path);
+        proto_fixture.into()
+    }
+}
+
+impl From<ProtoChainFixture> for ChainFixture {
+    fn from(value: ProtoChainFixture) -> Self {
+        // All blobs should have an input.
+        Self {
+            input: value.input.unwrap().into(),
+            output: value.output.into_iter().map(Into::into).collect(),
+            programs: value.programs.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ChainFixture> for ProtoChainFixture {
+    fn from(value: ChainFixture) -> Self {
+        Self {
+            input: Some(value.input.into()),
+            output: value.output.into_iter().map(Into::into).collect(),
+            programs: value.programs.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl SerializableFixture for ProtoChainFixture {
+    const FILE_PREFIX: &'static str = "chain";
+
+    // Manually implemented for deterministic hashes.
+    fn hash(&self) -> Hash {
+        let mut hasher = Hasher::default();
+        if let Some(input) = &self.input {
+            hash_proto_chain_context(&mut hasher, input);
+        }
+        for output in self.output.iter() {
+            crate::effects::hash_proto_effects(&mut hasher, output);
+        }
+        crate::program::hash_proto_programs(&mut hasher, &self.programs);
+        hasher.result()
+    }
+}
+
+impl IndexableFixture for ProtoChainFixture {
+    const LAYOUT: &'static str = "mollusk";
+
+    fn program_id(&self) -> Pubkey {
+        self.input
+            .as_ref()
+            .and_then(|input| input.instructions.first())
+            .and_then(|instruction| Pubkey::try_from(instruction.program_id.as_slice()).ok())
+            .unwrap_or_default()
+    }
+
+    fn instruction_data(&self) -> &[u8] {
+        self.input
+            .as_ref()
+            .and_then(|input| input.instructions.first())
+            .map_or(&[], |instruction| &instruction.data)
+    }
+
+    fn program_result(&self) -> u64 {
+        self.output.last().map_or(0, |output| output.program_result)
+    }
+}
+
+impl IntoSerializableFixture for ChainFixture {
+    type Fixture = ProtoChainFixture;
+
+    fn into(self) -> Self::Fixture {
+        Into::into(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{instruction::AccountMeta, system_program},
+    };
+
+    #[test]
+    fn test_chain_fixture_roundtrip() {
+        let accounts = (0..3)
+            .map(|i| {
+                (
+                    Pubkey::new_unique(),
+                    Account::new(42 + i, 8, &system_program::id()),
+                )
+            })
+            .collect::<Vec<_>>();
+        let instructions = vec![
+            Instruction::new_with_bytes(
+                system_program::id(),
+                &[1, 2, 3],
+                vec![
+                    AccountMeta::new(accounts[2].0, true),
+                    AccountMeta::new_readonly(accounts[0].0, false),
+                ],
+            ),
+            Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[4],
+                vec![AccountMeta::new(accounts[1].0, false)],
+            ),
+        ];
+        let fixture = ChainFixture {
+            input: ChainContext {
+                compute_budget: ComputeBudget::default(),
+                feature_set: FeatureSet::all_enabled(),
+                sysvars: Sysvars::default(),
+                instructions,
+                accounts,
+            },
+            output: vec![
+                Effects {
+                    compute_units_consumed: 150,
+                    resulting_accounts: vec![],
+                    ..Default::default()
+                },
+                Effects {
+                    program_result: 2,
+                    ..Default::default()
+                },
+            ],
+            programs: vec![ProgramElf {
+                program_id: Pubkey::new_unique(),
+                loader_key: Pubkey::new_unique(),
+                elf: vec![0x7f, b'E', b'L', b'F'],
+            }],
+        };
+
+        let proto_fixture = ProtoChainFixture::from(fixture.clone());
+        let blob = SerializableFixture::encode(&proto_fixture);
+        let decoded = ChainFixture::decode(&blob);
+        assert_eq!(decoded.input.instructions, fixture.input.instructions);
+        assert_eq!(decoded.input.accounts, fixture.input.accounts);
+        assert_eq!(decoded.output, fixture.output);
+        assert_eq!(decoded.programs, fixture.programs);
+
+        // Hashes are deterministic, and cover the order of instructions.
+        assert_eq!(proto_fixture.hash(), proto_fixture.clone().hash());
+        let mut reordered = proto_fixture.clone();
+        reordered.input.as_mut().unwrap().instructions.reverse();
+        assert_ne!(proto_fixture.hash(), reordered.hash());
+    }
+}
@@ -41,26 +41,7 @@ impl From<ProtoContext> for Context {
 
         let accounts: Vec<(Pubkey, Account)> = value.accounts.into_iter().map(Into::into).collect();
 
-        let instruction_accounts: Vec<AccountMeta> = value
-            .instr_accounts
-            .into_iter()
-            .map(
-                |ProtoInstructionAccount {
-                     index,
-                     is_signer,
-                     is_writable,
-                 }| {
-                    let (pubkey, _) = accounts
-                        .get(index as usize)
-                        .expect("Invalid index for instruction account");
-                    AccountMeta {
-                        pubkey: *pubkey,
-                        is_signer,
-                        is_writable,
-                    }
-                },
-            )
-            .collect();
+        let instruction_accounts = instruction_accounts_from_proto(&accounts, value.instr_accounts);
 
         Self {
             compute_budget: value.compute_budget.map(Into::into).unwrap_or_default(),
@@ -76,28 +57,8 @@ impl From<ProtoContext> for Context {
 
 impl From<Context> for ProtoContext {
     fn from(value: Context) -> Self {
-        let instr_accounts: Vec<ProtoInstructionAccount> = value
-            .instruction_accounts
-            .into_iter()
-            .map(
-                |AccountMeta {
-                     pubkey,
-                     is_signer,
-                     is_writable,
-                 }| {
-                    let index_of_account = value
-                        .accounts
-                        .iter()
-                        .position(|(key, _)| key == &pubkey)
-                        .unwrap();
-                    ProtoInstructionAccount {
-                        index: index_of_account as u32,
-                        is_signer,
-                        is_writable,
-                    }
-                },
-            )
-            .collect();
+        let instr_accounts =
+            instruction_accounts_to_proto(&value.accounts, value.instruction_accounts);
 
         let accounts = value.accounts.into_iter().map(Into::into).collect();
 
@@ -113,6 +74,65 @@ impl From<Context> for ProtoContext {
     }
 }
 
+pub(crate) fn instruction_accounts_from_proto(
+    accounts: &[(Pubkey, Account)],
+    instr_accounts: Vec<ProtoInstructionAccount>,
+) -> Vec<AccountMeta> {
+    instr_accounts
+        .into_iter()
+        .map(
+            |ProtoInstructionAccount {
+                 index,
+                 is_signer,
+                 is_writable,
+             }| {
+                let (pubkey, _) = accounts
+                    .get(index as usize)
+                    .expect("Invalid index for instruction account");
+                AccountMeta {
+                    pubkey: *pubkey,
+                    is_signer,
+                    is_writable,
+                }
+            },
+        )
+        .collect()
+}
+
+pub(crate) fn instruction_accounts_to_proto(
+    accounts: &[(Pubkey, Account)],
+    instruction_accounts: Vec<AccountMeta>,
+) -> Vec<ProtoInstructionAccount> {
+    instruction_accounts
+        .into_iter()
+        .map(
+            |AccountMeta {
+                 pubkey,
+                 is_signer,
+                 is_writable,
+             }| {
+                let index_of_account = accounts.iter().position(|(key, _)| key == &pubkey).unwrap();
+                ProtoInstructionAccount {
+                    index: index_of_account as u32,
+                    is_signer,
+                    is_writable,
+                }
+            },
+        )
+        .collect()
+}
+
+pub(crate) fn hash_proto_instruction_accounts(
+    hasher: &mut Hasher,
+    instr_accounts: &[ProtoInstructionAccount],
+) {
+    for account in instr_accounts.iter() {
+        hasher.hash(&account.index.to_le_bytes());
+        hasher.hash(&[account.is_signer as u8]);
+        hasher.hash(&[account.is_writable as u8]);
+    }
+}
+
 pub(crate) fn hash_proto_context(hasher: &mut Hasher, context: &ProtoContext) {
     if let Some(compute_budget) = &context.compute_budget {
         crate::compute_budget::hash_proto_compute_budget(hasher, compute_budget);
@@ -124,11 +144,7 @@ pub(crate) fn hash_proto_context(hasher: &mut Hasher, context: &ProtoContext) {
         crate::sysvars::hash_proto_sysvars(hasher, sysvars);
     }
     hasher.hash(&context.program_id);
-    for account in context.instr_accounts.iter() {
-        hasher.hash(&account.index.to_le_bytes());
-        hasher.hash(&[account.is_signer as u8]);
-        hasher.hash(&[account.is_writable as u8]);
-    }
+    hash_proto_instruction_accounts(hasher, &context.instr_accounts);
     hasher.hash(&context.data);
     crate::account::hash_proto_accounts(hasher, &context.accounts);
 }
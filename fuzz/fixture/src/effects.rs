@@ -1,10 +1,116 @@
 //! Post-invocation effects of an instruction.
 
 use {
-    super::proto::{AcctState as ProtoAccount, InstrEffects as ProtoEffects},
-    solana_sdk::{account::Account, keccak::Hasher, pubkey::Pubkey},
+    super::proto::{
+        AcctState as ProtoAccount, InnerInstr as ProtoInnerInstruction,
+        InnerInstrAcct as ProtoInnerInstructionAccount, InstrEffects as ProtoEffects,
+    },
+    solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        keccak::Hasher,
+        pubkey::Pubkey,
+    },
 };
 
+/// The current version of the recorded effects.
+///
+/// * `0`: Compute units, execution time, program result, return data and
+///   resulting accounts.
+/// * `1`: Adds program logs, inner instructions and the return data program ID.
+pub const EFFECTS_VERSION: u32 = 1;
+
+/// An instruction invoked by a program through CPI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InnerInstruction {
+    /// The invoked instruction.
+    pub instruction: Instruction,
+    /// The invocation stack height, starting at 2 for instructions invoked
+    /// directly by the top-level program.
+    pub stack_height: u8,
+}
+
+impl From<ProtoInnerInstruction> for InnerInstruction {
+    fn from(value: ProtoInnerInstruction) -> Self {
+        let ProtoInnerInstruction {
+            program_id,
+            accounts,
+            data,
+            stack_height,
+        } = value;
+
+        let accounts = accounts
+            .into_iter()
+            .map(
+                |ProtoInnerInstructionAccount {
+                     address,
+                     is_signer,
+                     is_writable,
+                 }| AccountMeta {
+                    pubkey: pubkey_from_bytes(address),
+                    is_signer,
+                    is_writable,
+                },
+            )
+            .collect();
+
+        Self {
+            instruction: Instruction {
+                program_id: pubkey_from_bytes(program_id),
+                accounts,
+                data,
+            },
+            stack_height: stack_height as u8,
+        }
+    }
+}
+
+impl From<InnerInstruction> for ProtoInnerInstruction {
+    fn from(value: InnerInstruction) -> Self {
+        let InnerInstruction {
+            instruction:
+                Instruction {
+                    program_id,
+                    accounts,
+                    data,
+                },
+            stack_height,
+        } = value;
+
+        let accounts = accounts
+            .into_iter()
+            .map(
+                |AccountMeta {
+                     pubkey,
+                     is_signer,
+                     is_writable,
+                 }| ProtoInnerInstructionAccount {
+                    address: pubkey.to_bytes().to_vec(),
+                    is_signer,
+                    is_writable,
+                },
+            )
+            .collect();
+
+        Self {
+            program_id: program_id.to_bytes().to_vec(),
+            accounts,
+            data,
+            stack_height: stack_height as u32,
+        }
+    }
+}
+
+// Empty bytes, as written by fixtures without a return data program ID,
+// map to the default pubkey.
+fn pubkey_from_bytes(bytes: Vec<u8>) -> Pubkey {
+    if bytes.is_empty() {
+        return Pubkey::default();
+    }
+    let bytes: [u8; 32] = bytes.try_into().expect("Invalid bytes for pubkey");
+    Pubkey::new_from_array(bytes)
+}
+
 /// Represents the effects of a single instruction.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Effects {
@@ -17,6 +123,15 @@ pub struct Effects {
     pub return_data: Vec<u8>,
     /// Resulting accounts with state, to be checked post-simulation.
     pub resulting_accounts: Vec<(Pubkey, Account)>,
+    /// The version of the recorded effects. See `EFFECTS_VERSION`. Fields
+    /// introduced after this version are empty, and shouldn't be checked.
+    pub version: u32,
+    /// Program log messages.
+    pub logs: Vec<String>,
+    /// Instructions invoked through CPI, in the order they were invoked.
+    pub inner_instructions: Vec<InnerInstruction>,
+    /// The program that set the return data.
+    pub return_data_program_id: Pubkey,
 }
 
 impl From<ProtoEffects> for Effects {
@@ -27,6 +142,10 @@ impl From<ProtoEffects> for Effects {
             program_result,
             return_data,
             resulting_accounts,
+            version,
+            logs,
+            inner_instructions,
+            return_data_program_id,
         } = value;
 
         let resulting_accounts: Vec<(Pubkey, Account)> =
@@ -38,6 +157,10 @@ impl From<ProtoEffects> for Effects {
             program_result,
             return_data,
             resulting_accounts,
+            version,
+            logs,
+            inner_instructions: inner_instructions.into_iter().map(Into::into).collect(),
+            return_data_program_id: pubkey_from_bytes(return_data_program_id),
         }
     }
 }
@@ -50,17 +173,32 @@ impl From<Effects> for ProtoEffects {
             program_result,
             return_data,
             resulting_accounts,
+            version,
+            logs,
+            inner_instructions,
+            return_data_program_id,
         } = value;
 
         let resulting_accounts: Vec<ProtoAccount> =
             resulting_accounts.into_iter().map(Into::into).collect();
 
+        // Version 0 fixtures have no return data program ID.
+        let return_data_program_id = if version == 0 {
+            vec![]
+        } else {
+            return_data_program_id.to_bytes().to_vec()
+        };
+
         Self {
             compute_units_consumed,
             execution_time,
             program_result,
             return_data,
             resulting_accounts,
+            version,
+            logs,
+            inner_instructions: inner_instructions.into_iter().map(Into::into).collect(),
+            return_data_program_id,
         }
     }
 }
@@ -70,4 +208,74 @@ pub(crate) fn hash_proto_effects(hasher: &mut Hasher, effects: &ProtoEffects) {
     hasher.hash(&effects.execution_time.to_le_bytes());
     hasher.hash(&effects.program_result.to_le_bytes());
     crate::account::hash_proto_accounts(hasher, &effects.resulting_accounts);
+    // Only hashed from version 1, so hashes of earlier fixtures are
+    // unchanged.
+    if effects.version > 0 {
+        hasher.hash(&effects.version.to_le_bytes());
+        for log in effects.logs.iter() {
+            hasher.hash(log.as_bytes());
+        }
+        for inner in effects.inner_instructions.iter() {
+            hasher.hash(&inner.program_id);
+            for account in inner.accounts.iter() {
+                hasher.hash(&account.address);
+                hasher.hash(&[account.is_signer as u8]);
+                hasher.hash(&[account.is_writable as u8]);
+            }
+            hasher.hash(&inner.data);
+            hasher.hash(&inner.stack_height.to_le_bytes());
+        }
+        hasher.hash(&effects.return_data_program_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effects_versions() {
+        // Version 0 effects roundtrip without any of the version 1 fields.
+        let legacy = ProtoEffects {
+            compute_units_consumed: 150,
+            program_result: 0,
+            return_data: vec![1, 2, 3],
+            ..Default::default()
+        };
+        let effects = Effects::from(legacy.clone());
+        assert_eq!(effects.version, 0);
+        assert_eq!(effects.return_data_program_id, Pubkey::default());
+        assert_eq!(ProtoEffects::from(effects), legacy);
+
+        let mut legacy_hasher = Hasher::default();
+        hash_proto_effects(&mut legacy_hasher, &legacy);
+
+        // Version 1 effects roundtrip with them.
+        let effects = Effects {
+            compute_units_consumed: 150,
+            return_data: vec![1, 2, 3],
+            version: EFFECTS_VERSION,
+            logs: vec!["Program log: hello".to_string()],
+            inner_instructions: vec![InnerInstruction {
+                instruction: Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &[4, 5],
+                    vec![
+                        AccountMeta::new(Pubkey::new_unique(), true),
+                        AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                    ],
+                ),
+                stack_height: 2,
+            }],
+            return_data_program_id: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        let proto = ProtoEffects::from(effects.clone());
+        assert_eq!(Effects::from(proto.clone()), effects);
+
+        // The hash covers the version 1 fields.
+        let mut hasher = Hasher::default();
+        hash_proto_effects(&mut hasher, &proto);
+        assert_ne!(hasher.result(), legacy_hasher.result());
+    }
 }
@@ -6,19 +6,29 @@
 //! They can be used to fuzz a custom entrypoint of the developer's choice.
 
 pub mod account;
+pub mod chain;
 pub mod compute_budget;
 pub mod context;
 pub mod effects;
 pub mod feature_set;
+pub mod program;
 pub mod proto {
     include!(concat!(env!("OUT_DIR"), "/org.mollusk.svm.rs"));
 }
 pub mod sysvars;
 
 use {
-    crate::{context::Context, effects::Effects, proto::InstrFixture as ProtoFixture},
-    mollusk_svm_fuzz_fs::{FsHandler, IntoSerializableFixture, SerializableFixture},
-    solana_sdk::keccak::{Hash, Hasher},
+    crate::{
+        context::Context, effects::Effects, program::ProgramElf,
+        proto::InstrFixture as ProtoFixture,
+    },
+    mollusk_svm_fuzz_fs::{
+        bundle::IndexableFixture, FsHandler, IntoSerializableFixture, SerializableFixture,
+    },
+    solana_sdk::{
+        keccak::{Hash, Hasher},
+        pubkey::Pubkey,
+    },
 };
 
 /// A fixture for invoking a single instruction against a simulated SVM
@@ -29,6 +39,9 @@ pub struct Fixture {
     pub input: Context,
     /// The fixture outputs.
     pub output: Effects,
+    /// The programs in the cache when the fixture was ejected, if embedded.
+    /// Replaying the fixture loads any of them missing from the cache.
+    pub programs: Vec<ProgramElf>,
 }
 
 impl Fixture {
@@ -46,6 +59,11 @@ impl Fixture {
         let proto_fixture: ProtoFixture = FsHandler::load_from_json_file(file_path);
         proto_fixture.into()
     }
+
+    pub fn load_from_yaml_file(file_path: &str) -> Self {
+        let proto_fixture: ProtoFixture = FsHandler::load_from_yaml_file(file_path);
+        proto_fixture.into()
+    }
 }
 
 impl From<ProtoFixture> for Fixture {
@@ -54,6 +72,7 @@ impl From<ProtoFixture> for Fixture {
         Self {
             input: value.input.unwrap().into(),
             output: value.output.unwrap().into(),
+            programs: value.programs.into_iter().map(Into::into).collect(),
         }
     }
 }
@@ -63,6 +82,7 @@ impl From<Fixture> for ProtoFixture {
         Self {
             input: Some(value.input.into()),
             output: Some(value.output.into()),
+            programs: value.programs.into_iter().map(Into::into).collect(),
         }
     }
 }
@@ -77,10 +97,32 @@ impl SerializableFixture for ProtoFixture {
         if let Some(output) = &self.output {
             crate::effects::hash_proto_effects(&mut hasher, output);
         }
+        crate::program::hash_proto_programs(&mut hasher, &self.programs);
         hasher.result()
     }
 }
 
+impl IndexableFixture for ProtoFixture {
+    const LAYOUT: &'static str = "mollusk";
+
+    fn program_id(&self) -> Pubkey {
+        self.input
+            .as_ref()
+            .and_then(|input| Pubkey::try_from(input.program_id.as_slice()).ok())
+            .unwrap_or_default()
+    }
+
+    fn instruction_data(&self) -> &[u8] {
+        self.input.as_ref().map_or(&[], |input| &input.data)
+    }
+
+    fn program_result(&self) -> u64 {
+        self.output
+            .as_ref()
+            .map_or(0, |output| output.program_result)
+    }
+}
+
 impl IntoSerializableFixture for Fixture {
     type Fixture = ProtoFixture;
 
@@ -93,8 +135,11 @@ impl IntoSerializableFixture for Fixture {
 mod tests {
     use {
         super::{proto::InstrFixture, Fixture},
-        crate::{context::Context, effects::Effects, sysvars::Sysvars},
-        mollusk_svm_fuzz_fs::SerializableFixture,
+        crate::{context::Context, effects::Effects, program::ProgramElf, sysvars::Sysvars},
+        mollusk_svm_fuzz_fs::{
+            bundle::{BundleFilter, BundleReader, BundleWriter},
+            SerializableFixture,
+        },
         solana_compute_budget::compute_budget::ComputeBudget,
         solana_sdk::{
             account::Account, feature_set::FeatureSet, instruction::AccountMeta, keccak::Hash,
@@ -141,6 +186,7 @@ mod tests {
         let fixture = Fixture {
             input: context,
             output: effects,
+            programs: vec![],
         };
 
         let mut last_hash = produce_hash(&fixture);
@@ -150,4 +196,181 @@ mod tests {
             last_hash = new_hash;
         }
     }
+
+    #[test]
+    fn test_bundle() {
+        let program_a = Pubkey::new_unique();
+        let program_b = Pubkey::new_unique();
+        let fixture = |program_id: Pubkey, data: Vec<u8>, program_result: u64| {
+            InstrFixture::from(Fixture {
+                input: Context {
+                    program_id,
+                    instruction_data: data,
+                    accounts: vec![(Pubkey::new_unique(), Account::new(42, 42, &program_id))],
+                    ..Default::default()
+                },
+                output: Effects {
+                    program_result,
+                    ..Default::default()
+                },
+                programs: vec![],
+            })
+        };
+        let fixtures = vec![
+            fixture(program_a, vec![1, 2, 3], 0),
+            fixture(program_a, vec![2; 16], 7),
+            fixture(program_b, vec![1, 4], 0),
+        ];
+
+        let mut writer = BundleWriter::new(std::io::Cursor::new(Vec::new())).unwrap();
+        for fixture in fixtures.iter() {
+            writer.append(fixture).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut reader = BundleReader::new(std::io::Cursor::new(bytes.clone())).unwrap();
+        let index = reader.index().to_vec();
+        assert_eq!(index.len(), 3);
+        assert_eq!(index[0].hash, fixtures[0].hash());
+        assert_eq!(index[0].layout, "mollusk");
+        assert_eq!(index[1].program_id, program_a);
+        assert_eq!(index[1].discriminator, vec![2; 8]);
+        assert_eq!(index[1].program_result, 7);
+        assert_eq!(index[2].discriminator, vec![1, 4]);
+
+        // Fixtures can be read in any order.
+        assert_eq!(reader.read::<InstrFixture>(&index[2]).unwrap(), fixtures[2]);
+        assert_eq!(reader.read::<InstrFixture>(&index[0]).unwrap(), fixtures[0]);
+
+        // But only as their own kind and layout.
+        assert!(reader
+            .read::<crate::proto::ChainFixture>(&index[0])
+            .is_err());
+        let mut foreign = index[0].clone();
+        foreign.layout = "firedancer".to_string();
+        assert!(reader.read::<InstrFixture>(&foreign).is_err());
+
+        let matching = |reader: &mut BundleReader<_>, filter: BundleFilter| {
+            reader
+                .fixtures::<InstrFixture>(&filter)
+                .map(|result| result.unwrap().1)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(matching(&mut reader, BundleFilter::default()), fixtures);
+        assert_eq!(
+            matching(
+                &mut reader,
+                BundleFilter {
+                    discriminator: Some(vec![1]),
+                    ..Default::default()
+                }
+            ),
+            vec![fixtures[0].clone(), fixtures[2].clone()],
+        );
+        assert_eq!(
+            matching(
+                &mut reader,
+                BundleFilter {
+                    program_id: Some(program_a),
+                    failed: true,
+                    ..Default::default()
+                }
+            ),
+            vec![fixtures[1].clone()],
+        );
+
+        // Truncated bundles are rejected.
+        let mut truncated = bytes;
+        truncated.pop();
+        assert!(BundleReader::new(std::io::Cursor::new(truncated)).is_err());
+    }
+
+    #[test]
+    fn test_readable_round_trip() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let fixture = InstrFixture::from(Fixture {
+            input: Context {
+                compute_budget: ComputeBudget::default(),
+                feature_set: FeatureSet::all_enabled(),
+                sysvars: Sysvars::default(),
+                program_id,
+                instruction_accounts: vec![AccountMeta::new(key, true)],
+                instruction_data: vec![0xde, 0xad, 0xbe, 0xef],
+                accounts: vec![(key, Account::new(42, 3, &program_id))],
+            },
+            output: Effects {
+                program_result: 7,
+                return_data: vec![1, 2, 3],
+                return_data_program_id: program_id,
+                resulting_accounts: vec![(key, Account::new(41, 3, &program_id))],
+                logs: vec!["Program log: hello".to_string()],
+                ..Default::default()
+            },
+            programs: vec![ProgramElf {
+                program_id,
+                loader_key: Pubkey::new_unique(),
+                elf: vec![0x7f, b'E', b'L', b'F'],
+            }],
+        });
+
+        let json = serde_json::to_string_pretty(&fixture).unwrap();
+        assert!(json.contains(&format!("\"program_id\": \"{}\"", program_id)));
+        assert!(json.contains("\"data\": \"deadbeef\""));
+        assert!(json.contains("\"name\": \"secp256k1_recover syscall\""));
+        assert_eq!(
+            serde_json::from_str::<InstrFixture>(&json).unwrap(),
+            fixture
+        );
+
+        let yaml = serde_yaml::to_string(&fixture).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<InstrFixture>(&yaml).unwrap(),
+            fixture
+        );
+
+        // Hand-written fixtures can omit fields, and write features by
+        // address or ID.
+        let feature = solana_sdk::feature_set::secp256k1_recover_syscall_enabled::id();
+        let hand_written = format!(
+            r#"{{
+                "input": {{
+                    "program_id": "{}",
+                    "data": "0102",
+                    "feature_set": {{ "features": ["{}", 42] }}
+                }},
+                "output": {{ "program_result": 1 }}
+            }}"#,
+            program_id, feature,
+        );
+        let fixture = serde_json::from_str::<InstrFixture>(&hand_written).unwrap();
+        let input = fixture.input.unwrap();
+        assert_eq!(input.program_id, program_id.to_bytes());
+        assert_eq!(input.data, vec![1, 2]);
+        assert_eq!(
+            input.feature_set.unwrap().features,
+            vec![
+                u64::from_le_bytes(feature.to_bytes()[..8].try_into().unwrap()),
+                42
+            ],
+        );
+        assert_eq!(fixture.output.unwrap().program_result, 1);
+
+        // Fixtures written before the readable form hold bytes as arrays.
+        let legacy = format!(
+            r#"{{
+                "input": {{
+                    "program_id": {:?},
+                    "data": [1, 2]
+                }}
+            }}"#,
+            program_id.to_bytes(),
+        );
+        let input = serde_json::from_str::<InstrFixture>(&legacy)
+            .unwrap()
+            .input
+            .unwrap();
+        assert_eq!(input.program_id, program_id.to_bytes());
+        assert_eq!(input.data, vec![1, 2]);
+    }
 }
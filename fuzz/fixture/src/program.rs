@@ -0,0 +1,63 @@
+//! A program's ELF, embedded in a fixture.
+
+use {
+    super::proto::ProgramElf as ProtoProgramElf,
+    solana_sdk::{keccak::Hasher, pubkey::Pubkey},
+};
+
+/// A program's ELF, as loaded in the program cache when the fixture was
+/// ejected. Fixtures embedding the ELFs of every program they invoke can be
+/// replayed on their own.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProgramElf {
+    /// The program ID.
+    pub program_id: Pubkey,
+    /// The loader owning the program.
+    pub loader_key: Pubkey,
+    /// The program's ELF.
+    pub elf: Vec<u8>,
+}
+
+impl From<ProtoProgramElf> for ProgramElf {
+    fn from(value: ProtoProgramElf) -> Self {
+        let ProtoProgramElf {
+            program_id,
+            loader,
+            elf,
+        } = value;
+
+        let program_id_bytes: [u8; 32] =
+            program_id.try_into().expect("Invalid bytes for program ID");
+        let loader_bytes: [u8; 32] = loader.try_into().expect("Invalid bytes for loader");
+
+        Self {
+            program_id: Pubkey::new_from_array(program_id_bytes),
+            loader_key: Pubkey::new_from_array(loader_bytes),
+            elf,
+        }
+    }
+}
+
+impl From<ProgramElf> for ProtoProgramElf {
+    fn from(value: ProgramElf) -> Self {
+        let ProgramElf {
+            program_id,
+            loader_key,
+            elf,
+        } = value;
+
+        Self {
+            program_id: program_id.to_bytes().to_vec(),
+            loader: loader_key.to_bytes().to_vec(),
+            elf,
+        }
+    }
+}
+
+pub(crate) fn hash_proto_programs(hasher: &mut Hasher, programs: &[ProtoProgramElf]) {
+    for program in programs {
+        hasher.hash(&program.program_id);
+        hasher.hash(&program.loader);
+        hasher.hash(&program.elf);
+    }
+}
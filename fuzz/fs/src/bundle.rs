@@ -0,0 +1,312 @@
+//! Fixture bundles: many fixtures in a single compressed archive, with an
+//! index.
+//!
+//! Corpora of hundreds of thousands of fixtures make for as many tiny files.
+//! A bundle packs them into one file instead, where each fixture blob is
+//! compressed on its own, so any fixture can be read without decompressing
+//! the others. An index of every fixture's kind, protobuf layout, hash,
+//! program ID, instruction discriminator and result is written at the end of
+//! the bundle, so bundles can be listed and filtered without decoding any
+//! fixture.
+//!
+//! Layout:
+//!
+//! ```text
+//! MAGIC | VERSION (u32) | compressed blobs... | compressed index |
+//! index offset (u64) | MAGIC
+//! ```
+//!
+//! All integers are little-endian. Both the blobs and the (JSON) index are
+//! compressed with DEFLATE.
+//!
+//! Bundles are written and read in a streaming fashion: `BundleWriter`
+//! appends fixtures one at a time and writes the index when finished, and
+//! `BundleReader` reads fixtures one at a time, in any order.
+
+use {
+    crate::SerializableFixture,
+    flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression},
+    serde::{Deserialize, Serialize},
+    solana_sdk::{keccak::Hash, pubkey::Pubkey},
+    std::{
+        fs::File,
+        io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+        path::Path,
+    },
+};
+
+/// The magic bytes at the start and end of every bundle.
+pub const BUNDLE_MAGIC: &[u8; 8] = b"MOLLUSKB";
+
+/// The version of the bundle layout.
+pub const BUNDLE_VERSION: u32 = 2;
+
+/// The maximum number of leading instruction data bytes recorded as an
+/// instruction's discriminator.
+pub const MAX_DISCRIMINATOR_LEN: usize = 8;
+
+/// A fixture which can be summarized in a bundle's index.
+pub trait IndexableFixture: SerializableFixture {
+    /// The protobuf layout of the fixture, ie. `mollusk` or `firedancer`.
+    /// Fixtures of different layouts can share a kind.
+    const LAYOUT: &'static str;
+
+    /// The ID of the program invoked by the fixture. For chains, the program
+    /// invoked first.
+    fn program_id(&self) -> Pubkey;
+
+    /// The instruction data of the fixture. For chains, that of the first
+    /// instruction.
+    fn instruction_data(&self) -> &[u8];
+
+    /// The program result code recorded in the fixture's effects. Zero is
+    /// success, errors are non-zero. For chains, the result of the last
+    /// processed instruction.
+    fn program_result(&self) -> u64;
+}
+
+/// An entry in a bundle's index.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BundleEntry {
+    /// The kind of fixture, ie. its file prefix.
+    pub kind: String,
+    /// The protobuf layout of the fixture, ie. `mollusk` or `firedancer`.
+    pub layout: String,
+    /// The fixture's hash, as used in its file name.
+    pub hash: Hash,
+    /// The ID of the program invoked by the fixture.
+    pub program_id: Pubkey,
+    /// The leading bytes of the instruction data, up to
+    /// `MAX_DISCRIMINATOR_LEN`.
+    pub discriminator: Vec<u8>,
+    /// The program result code. Zero is success, errors are non-zero.
+    pub program_result: u64,
+    /// The offset of the compressed fixture blob in the bundle.
+    pub offset: u64,
+    /// The length of the compressed fixture blob.
+    pub length: u64,
+}
+
+impl BundleEntry {
+    /// The file name the fixture would be written to by `FsHandler`.
+    pub fn file_name(&self) -> String {
+        format!(
+            "{}-{}.fix",
+            self.kind,
+            bs58::encode(self.hash).into_string()
+        )
+    }
+}
+
+/// Filters on the entries of a bundle's index. Unset filters match any
+/// entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BundleFilter {
+    /// Only match fixtures invoking this program.
+    pub program_id: Option<Pubkey>,
+    /// Only match fixtures whose instruction data starts with these bytes.
+    pub discriminator: Option<Vec<u8>>,
+    /// Only match fixtures with this program result code.
+    pub program_result: Option<u64>,
+    /// Only match fixtures whose program failed (non-zero result code).
+    pub failed: bool,
+}
+
+impl BundleFilter {
+    /// Whether the entry matches all of the filters.
+    pub fn matches(&self, entry: &BundleEntry) -> bool {
+        self.program_id
+            .map_or(true, |program_id| entry.program_id == program_id)
+            && self.discriminator.as_ref().map_or(true, |discriminator| {
+                // Discriminators longer than those recorded can't be checked.
+                discriminator.len() <= MAX_DISCRIMINATOR_LEN
+                    && entry.discriminator.starts_with(discriminator)
+            })
+            && self.program_result.map_or(true, |program_result| {
+                entry.program_result == program_result
+            })
+            && (!self.failed || entry.program_result != 0)
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Writes fixtures to a bundle, one at a time.
+///
+/// The bundle is only complete once `finish` writes its index.
+pub struct BundleWriter<W: Write> {
+    writer: W,
+    offset: u64,
+    index: Vec<BundleEntry>,
+}
+
+impl BundleWriter<BufWriter<File>> {
+    /// Create a bundle file at the provided path.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> BundleWriter<W> {
+    /// Start writing a bundle to the provided writer.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(BUNDLE_MAGIC)?;
+        writer.write_all(&BUNDLE_VERSION.to_le_bytes())?;
+        Ok(Self {
+            writer,
+            offset: (BUNDLE_MAGIC.len() + 4) as u64,
+            index: Vec::new(),
+        })
+    }
+
+    fn write_compressed(&mut self, data: &[u8]) -> io::Result<u64> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+        self.writer.write_all(&compressed)?;
+        let length = compressed.len() as u64;
+        self.offset += length;
+        Ok(length)
+    }
+
+    /// Append a fixture to the bundle.
+    pub fn append<SF: IndexableFixture>(&mut self, fixture: &SF) -> io::Result<()> {
+        let instruction_data = fixture.instruction_data();
+        let discriminator =
+            instruction_data[..instruction_data.len().min(MAX_DISCRIMINATOR_LEN)].to_vec();
+        let offset = self.offset;
+        let length = self.write_compressed(&SerializableFixture::encode(fixture))?;
+        self.index.push(BundleEntry {
+            kind: SF::FILE_PREFIX.to_string(),
+            layout: SF::LAYOUT.to_string(),
+            hash: fixture.hash(),
+            program_id: fixture.program_id(),
+            discriminator,
+            program_result: fixture.program_result(),
+            offset,
+            length,
+        });
+        Ok(())
+    }
+
+    /// The index of the fixtures appended so far.
+    pub fn index(&self) -> &[BundleEntry] {
+        &self.index
+    }
+
+    /// Write the index, completing the bundle, and return the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let index_offset = self.offset;
+        let index = serde_json::to_vec(&self.index).map_err(io::Error::other)?;
+        self.write_compressed(&index)?;
+        self.writer.write_all(&index_offset.to_le_bytes())?;
+        self.writer.write_all(BUNDLE_MAGIC)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads fixtures from a bundle, one at a time.
+pub struct BundleReader<R: Read + Seek> {
+    reader: R,
+    index: Vec<BundleEntry>,
+}
+
+impl BundleReader<BufReader<File>> {
+    /// Open the bundle file at the provided path.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> BundleReader<R> {
+    /// Start reading a bundle from the provided reader, reading its index.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        let mut version = [0; 4];
+        reader.read_exact(&mut magic)?;
+        reader.read_exact(&mut version)?;
+        if &magic != BUNDLE_MAGIC {
+            return Err(invalid_data("Not a fixture bundle"));
+        }
+        if u32::from_le_bytes(version) != BUNDLE_VERSION {
+            return Err(invalid_data("Unsupported fixture bundle version"));
+        }
+
+        // The footer holds the index offset, followed by the magic bytes,
+        // which are missing if the bundle was never finished.
+        let mut index_offset = [0; 8];
+        let end = reader.seek(SeekFrom::End(-16))?;
+        reader.read_exact(&mut index_offset)?;
+        reader.read_exact(&mut magic)?;
+        if &magic != BUNDLE_MAGIC {
+            return Err(invalid_data("Incomplete fixture bundle"));
+        }
+        let index_offset = u64::from_le_bytes(index_offset);
+        let index_length = end
+            .checked_sub(index_offset)
+            .ok_or_else(|| invalid_data("Invalid fixture bundle index offset"))?;
+
+        let index = read_compressed(&mut reader, index_offset, index_length)?;
+        let index = serde_json::from_slice(&index).map_err(io::Error::other)?;
+
+        Ok(Self { reader, index })
+    }
+
+    /// The index of every fixture in the bundle, in the order they were
+    /// appended.
+    pub fn index(&self) -> &[BundleEntry] {
+        &self.index
+    }
+
+    /// Read the protobuf blob of an indexed fixture.
+    pub fn read_blob(&mut self, entry: &BundleEntry) -> io::Result<Vec<u8>> {
+        read_compressed(&mut self.reader, entry.offset, entry.length)
+    }
+
+    /// Read and decode an indexed fixture, which must be of the fixture's
+    /// kind and layout.
+    pub fn read<SF: IndexableFixture>(&mut self, entry: &BundleEntry) -> io::Result<SF> {
+        if entry.kind != SF::FILE_PREFIX || entry.layout != SF::LAYOUT {
+            return Err(invalid_data(&format!(
+                "Expected a {} {} fixture, found a {} {} fixture",
+                SF::LAYOUT,
+                SF::FILE_PREFIX,
+                entry.layout,
+                entry.kind
+            )));
+        }
+        let blob = self.read_blob(entry)?;
+        SF::try_decode(&blob).map_err(|err| invalid_data(&err.to_string()))
+    }
+
+    /// Iterate over the fixtures matching a filter, reading each one as it's
+    /// reached.
+    pub fn fixtures<'a, SF: IndexableFixture>(
+        &'a mut self,
+        filter: &'a BundleFilter,
+    ) -> impl Iterator<Item = io::Result<(BundleEntry, SF)>> + 'a {
+        let entries = self
+            .index
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .cloned()
+            .collect::<Vec<_>>();
+        entries
+            .into_iter()
+            .map(move |entry| self.read(&entry).map(|fixture| (entry, fixture)))
+    }
+}
+
+fn read_compressed<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    length: u64,
+) -> io::Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::new();
+    DeflateDecoder::new(reader.take(length)).read_to_end(&mut data)?;
+    Ok(data)
+}
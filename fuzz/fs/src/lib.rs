@@ -1,3 +1,6 @@
+pub mod bundle;
+pub mod readable;
+
 use {
     prost::Message,
     serde::{de::DeserializeOwned, Serialize},
@@ -10,12 +13,21 @@ use {
 
 /// Represents a serializable fuzz fixture.
 pub trait SerializableFixture: Default + DeserializeOwned + Message + Serialize + Sized {
+    /// The prefix of the fixture's file names, identifying its kind.
+    const FILE_PREFIX: &'static str = "instr";
+
     /// Decode a `Protobuf` blob into a fixture.
     fn decode(blob: &[u8]) -> Self {
         <Self as Message>::decode(blob)
             .unwrap_or_else(|err| panic!("Failed to decode fixture: {}", err))
     }
 
+    /// Decode a `Protobuf` blob into a fixture, returning an error rather
+    /// than panicking if the blob is invalid.
+    fn try_decode(blob: &[u8]) -> Result<Self, prost::DecodeError> {
+        <Self as Message>::decode(blob)
+    }
+
     /// Encode the fixture into a `Protobuf` blob.
     fn encode(&self) -> Vec<u8> {
         let mut buf = Vec::new();
@@ -53,28 +65,55 @@ where
     }
 
     /// Dumps the fixture to a protobuf binary blob file.
-    /// The file name is a hash of the fixture with the `.fix` extension.
+    /// The file name is the fixture's prefix and hash, with the `.fix`
+    /// extension.
     pub fn dump_to_blob_file(&self, dir: &str) {
         let blob = SerializableFixture::encode(&self.serializable_fixture);
 
         let hash = self.serializable_fixture.hash();
-        let file_name = format!("instr-{}.fix", bs58::encode(hash).into_string());
+        let file_name = format!(
+            "{}-{}.fix",
+            SF::FILE_PREFIX,
+            bs58::encode(hash).into_string()
+        );
 
         write_file(Path::new(dir), &file_name, &blob);
     }
 
     /// Dumps the fixture to a JSON file.
-    /// The file name is a hash of the fixture with the `.json` extension.
+    /// The file name is the fixture's prefix and hash, with the `.json`
+    /// extension.
     pub fn dump_to_json_file(self, dir_path: &str) {
         let json = serde_json::to_string_pretty(&self.serializable_fixture)
             .expect("Failed to serialize fixture to JSON");
 
         let hash = self.serializable_fixture.hash();
-        let file_name = format!("instr-{}.json", bs58::encode(hash).into_string());
+        let file_name = format!(
+            "{}-{}.json",
+            SF::FILE_PREFIX,
+            bs58::encode(hash).into_string()
+        );
 
         write_file(Path::new(dir_path), &file_name, json.as_bytes());
     }
 
+    /// Dumps the fixture to a YAML file.
+    /// The file name is the fixture's prefix and hash, with the `.yaml`
+    /// extension.
+    pub fn dump_to_yaml_file(&self, dir_path: &str) {
+        let yaml = serde_yaml::to_string(&self.serializable_fixture)
+            .expect("Failed to serialize fixture to YAML");
+
+        let hash = self.serializable_fixture.hash();
+        let file_name = format!(
+            "{}-{}.yaml",
+            SF::FILE_PREFIX,
+            bs58::encode(hash).into_string()
+        );
+
+        write_file(Path::new(dir_path), &file_name, yaml.as_bytes());
+    }
+
     /// Loads a fixture from a protobuf binary blob file.
     pub fn load_from_blob_file(file_path: &str) -> SF {
         if !file_path.ends_with(".fix") {
@@ -98,6 +137,18 @@ where
             .expect("Failed to read fixture file");
         serde_json::from_str(&json).expect("Failed to deserialize fixture from JSON")
     }
+
+    /// Loads a fixture from a YAML file.
+    pub fn load_from_yaml_file(file_path: &str) -> SF {
+        if !file_path.ends_with(".yaml") && !file_path.ends_with(".yml") {
+            panic!("Invalid fixture file extension: {}", file_path);
+        }
+        let mut file = File::open(file_path).expect("Failed to open fixture file");
+        let mut yaml = String::new();
+        file.read_to_string(&mut yaml)
+            .expect("Failed to read fixture file");
+        serde_yaml::from_str(&yaml).expect("Failed to deserialize fixture from YAML")
+    }
 }
 
 fn write_file(dir: &Path, file_name: &str, data: &[u8]) {
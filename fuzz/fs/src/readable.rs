@@ -0,0 +1,158 @@
+//! Serde helpers for readable JSON and YAML fixtures.
+//!
+//! The protobuf fixture types use these through `#[serde(with = ...)]`, so
+//! fixtures serialize with addresses and hashes in base58, other bytes in
+//! hex, and features by address and name, rather than as arrays of numbers.
+//! Every representation decodes back to the exact bytes it was encoded from,
+//! so readable fixtures round-trip losslessly with their protobuf form.
+//!
+//! Fixtures written before these representations, with bytes as arrays of
+//! numbers, are still accepted when reading.
+
+use serde::Deserialize;
+
+/// Bytes as either their readable encoding, or an array of numbers.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BytesInput {
+    Encoded(String),
+    Raw(Vec<u8>),
+}
+
+/// Bytes as a base58 string, for addresses and hashes.
+pub mod base58 {
+    use {
+        super::BytesInput,
+        serde::{de::Error, Deserialize, Deserializer, Serializer},
+    };
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&bs58::encode(bytes).into_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        match BytesInput::deserialize(deserializer)? {
+            BytesInput::Encoded(string) => bs58::decode(&string).into_vec().map_err(|err| {
+                D::Error::custom(format!("Invalid base58 string {}: {}", string, err))
+            }),
+            BytesInput::Raw(bytes) => Ok(bytes),
+        }
+    }
+}
+
+/// Bytes as a lowercase hex string, for data.
+pub mod hex {
+    use {
+        super::BytesInput,
+        serde::{de::Error, Deserialize, Deserializer, Serializer},
+    };
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        match BytesInput::deserialize(deserializer)? {
+            BytesInput::Encoded(string) => decode(&string)
+                .ok_or_else(|| D::Error::custom(format!("Invalid hex string: {}", string))),
+            BytesInput::Raw(bytes) => Ok(bytes),
+        }
+    }
+
+    /// Encode bytes as a lowercase hex string.
+    pub fn encode(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Decode a hex string, in either case, into bytes.
+    pub fn decode(hex: &str) -> Option<Vec<u8>> {
+        if !hex.is_ascii() || hex.len() % 2 != 0 {
+            return None;
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect()
+    }
+}
+
+/// Feature IDs, the first 8 bytes of a feature's address, as the address
+/// and name of the feature.
+///
+/// Features unknown to this version of the runtime are written by ID. When
+/// reading, a feature can also be written as just its address or ID, and
+/// its name is ignored.
+pub mod features {
+    use {
+        serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer},
+        solana_sdk::{feature_set::FEATURE_NAMES, pubkey::Pubkey},
+        std::str::FromStr,
+    };
+
+    /// Test features, which share their ID with another feature, so they're
+    /// omitted from fixtures.
+    pub static OMITTED_FEATURES: &[Pubkey] = &[
+        solana_sdk::feature_set::disable_sbpf_v1_execution::id(),
+        solana_sdk::feature_set::reenable_sbpf_v1_execution::id(),
+    ];
+
+    #[derive(Serialize)]
+    #[serde(untagged)]
+    enum Feature {
+        Named { address: String, name: String },
+        Unknown { id: u64 },
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum FeatureInput {
+        Id(u64),
+        Address(String),
+        Named { address: String },
+        Unknown { id: u64 },
+    }
+
+    /// The ID of the feature at the address.
+    pub fn id(address: &Pubkey) -> u64 {
+        u64::from_le_bytes(address.to_bytes()[..8].try_into().unwrap())
+    }
+
+    /// The address and name of the feature with the ID, if it's known to
+    /// this version of the runtime and not omitted from fixtures.
+    pub fn lookup(int_id: u64) -> Option<(Pubkey, &'static str)> {
+        // Some features share an ID, so pick the lowest address for a
+        // stable name.
+        FEATURE_NAMES
+            .iter()
+            .filter(|(address, _)| !OMITTED_FEATURES.contains(address) && id(address) == int_id)
+            .min_by_key(|(address, _)| **address)
+            .map(|(address, name)| (*address, *name))
+    }
+
+    pub fn serialize<S: Serializer>(ids: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(ids.iter().map(|&int_id| {
+            lookup(int_id).map_or(Feature::Unknown { id: int_id }, |(address, name)| {
+                Feature::Named {
+                    address: address.to_string(),
+                    name: name.to_string(),
+                }
+            })
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+        Vec::<FeatureInput>::deserialize(deserializer)?
+            .into_iter()
+            .map(|feature| match feature {
+                FeatureInput::Id(id) | FeatureInput::Unknown { id } => Ok(id),
+                FeatureInput::Address(address) | FeatureInput::Named { address } => {
+                    Pubkey::from_str(&address)
+                        .map(|address| id(&address))
+                        .map_err(|_| {
+                            D::Error::custom(format!("Invalid feature address: {}", address))
+                        })
+                }
+            })
+            .collect()
+    }
+}
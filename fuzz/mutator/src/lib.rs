@@ -0,0 +1,184 @@
+//! Mollusk SVM Fuzz: Structure-aware mutation of instruction fixtures.
+//!
+//! Produces mutated variants of existing fixtures, in either Mollusk's or
+//! Firedancer's protobuf layouts. Mutations include flipping instruction data
+//! bytes, resizing account data, changing account owners and lamports,
+//! toggling the signer and writable flags of instruction accounts, and
+//! reordering instruction accounts.
+//!
+//! The mutator can serve as a custom mutator for fuzzers, or expand a corpus
+//! of fixtures ejected from tests.
+//!
+//! The minimizer does the reverse, reducing a fixture which reproduces a
+//! failure or divergence to the smallest inputs which still reproduce it.
+//!
+//! Note: Only the inputs of a fixture are mutated or minimized. Its effects are
+//! left as is, since they can only be known by executing the mutated fixture.
+
+pub mod minimize;
+pub mod mutation;
+pub mod mutator;
+
+pub use {
+    minimize::minimize,
+    mutation::Mutation,
+    mutator::{mutate_blob, Mutator},
+};
+
+/// An account in the inputs of a fixture.
+pub trait FixtureAccount {
+    fn address(&self) -> &[u8];
+    fn owner(&self) -> &[u8];
+    fn set_owner(&mut self, owner: Vec<u8>);
+    fn lamports(&self) -> u64;
+    fn set_lamports(&mut self, lamports: u64);
+    fn data(&self) -> &[u8];
+    fn data_mut(&mut self) -> &mut Vec<u8>;
+}
+
+/// An instruction account in the inputs of a fixture, referencing one of its
+/// accounts by index.
+pub trait FixtureInstructionAccount {
+    fn index(&self) -> u32;
+    fn set_index(&mut self, index: u32);
+    fn is_signer(&self) -> bool;
+    fn set_signer(&mut self, is_signer: bool);
+    fn is_writable(&self) -> bool;
+    fn set_writable(&mut self, is_writable: bool);
+}
+
+/// A fixture whose inputs can be mutated.
+pub trait MutableFixture {
+    type Account: FixtureAccount;
+    type InstructionAccount: FixtureInstructionAccount;
+
+    fn program_id(&self) -> &[u8];
+    fn instruction_data(&self) -> &[u8];
+    fn instruction_data_mut(&mut self) -> &mut Vec<u8>;
+    fn accounts(&self) -> &[Self::Account];
+    fn accounts_mut(&mut self) -> &mut Vec<Self::Account>;
+    fn instruction_accounts(&self) -> &[Self::InstructionAccount];
+    fn instruction_accounts_mut(&mut self) -> &mut Vec<Self::InstructionAccount>;
+}
+
+/// Implement the mutation traits for a fixture layout. Both layouts share
+/// the same field names for instruction inputs.
+macro_rules! impl_mutable_fixture {
+    ($proto:path) => {
+        use $proto as proto;
+
+        impl FixtureAccount for proto::AcctState {
+            fn address(&self) -> &[u8] {
+                &self.address
+            }
+
+            fn owner(&self) -> &[u8] {
+                &self.owner
+            }
+
+            fn set_owner(&mut self, owner: Vec<u8>) {
+                self.owner = owner;
+            }
+
+            fn lamports(&self) -> u64 {
+                self.lamports
+            }
+
+            fn set_lamports(&mut self, lamports: u64) {
+                self.lamports = lamports;
+            }
+
+            fn data(&self) -> &[u8] {
+                &self.data
+            }
+
+            fn data_mut(&mut self) -> &mut Vec<u8> {
+                &mut self.data
+            }
+        }
+
+        impl FixtureInstructionAccount for proto::InstrAcct {
+            fn index(&self) -> u32 {
+                self.index
+            }
+
+            fn set_index(&mut self, index: u32) {
+                self.index = index;
+            }
+
+            fn is_signer(&self) -> bool {
+                self.is_signer
+            }
+
+            fn set_signer(&mut self, is_signer: bool) {
+                self.is_signer = is_signer;
+            }
+
+            fn is_writable(&self) -> bool {
+                self.is_writable
+            }
+
+            fn set_writable(&mut self, is_writable: bool) {
+                self.is_writable = is_writable;
+            }
+        }
+
+        impl MutableFixture for proto::InstrFixture {
+            type Account = proto::AcctState;
+            type InstructionAccount = proto::InstrAcct;
+
+            fn program_id(&self) -> &[u8] {
+                self.input
+                    .as_ref()
+                    .map(|input| input.program_id.as_slice())
+                    .unwrap_or_default()
+            }
+
+            fn instruction_data(&self) -> &[u8] {
+                self.input
+                    .as_ref()
+                    .map(|input| input.data.as_slice())
+                    .unwrap_or_default()
+            }
+
+            fn instruction_data_mut(&mut self) -> &mut Vec<u8> {
+                &mut self.input.get_or_insert_with(Default::default).data
+            }
+
+            fn accounts(&self) -> &[Self::Account] {
+                self.input
+                    .as_ref()
+                    .map(|input| input.accounts.as_slice())
+                    .unwrap_or_default()
+            }
+
+            fn accounts_mut(&mut self) -> &mut Vec<Self::Account> {
+                &mut self.input.get_or_insert_with(Default::default).accounts
+            }
+
+            fn instruction_accounts(&self) -> &[Self::InstructionAccount] {
+                self.input
+                    .as_ref()
+                    .map(|input| input.instr_accounts.as_slice())
+                    .unwrap_or_default()
+            }
+
+            fn instruction_accounts_mut(&mut self) -> &mut Vec<Self::InstructionAccount> {
+                &mut self
+                    .input
+                    .get_or_insert_with(Default::default)
+                    .instr_accounts
+            }
+        }
+    };
+}
+
+mod mollusk {
+    use super::*;
+    impl_mutable_fixture!(mollusk_svm_fuzz_fixture::proto);
+}
+
+mod firedancer {
+    use super::*;
+    impl_mutable_fixture!(mollusk_svm_fuzz_fixture_firedancer::proto);
+}
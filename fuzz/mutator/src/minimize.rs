@@ -0,0 +1,226 @@
+//! Minimization of fixtures reproducing a failure or divergence.
+//!
+//! Fixtures ejected from tests or found by a fuzzer often carry accounts and
+//! bytes irrelevant to the behavior they reproduce. The minimizer repeatedly
+//! removes accounts and instruction accounts, truncates account data and
+//! shrinks instruction data, keeping each reduction only while a predicate
+//! still holds, until no further reduction does.
+
+use {
+    crate::{FixtureAccount, FixtureInstructionAccount, MutableFixture},
+    std::ops::Range,
+};
+
+/// The total size of a fixture's inputs. Every reduction strictly decreases
+/// it, so minimization always terminates.
+fn size<F: MutableFixture>(fixture: &F) -> usize {
+    fixture.accounts().len()
+        + fixture.instruction_accounts().len()
+        + fixture.instruction_data().len()
+        + fixture
+            .accounts()
+            .iter()
+            .map(|account| account.data().len())
+            .sum::<usize>()
+}
+
+/// Remove a range of accounts, along with the instruction accounts
+/// referencing them. Instruction accounts referencing later accounts are
+/// re-indexed.
+fn remove_accounts<F: MutableFixture>(fixture: &mut F, range: Range<usize>) {
+    fixture.accounts_mut().drain(range.clone());
+    fixture.instruction_accounts_mut().retain_mut(|meta| {
+        let index = meta.index() as usize;
+        if range.contains(&index) {
+            return false;
+        }
+        if index >= range.end {
+            meta.set_index((index - range.len()) as u32);
+        }
+        true
+    });
+}
+
+/// Remove chunks of items, from all of them down to single items, keeping
+/// each removal for which the predicate holds.
+fn remove_chunks<F: Clone>(
+    fixture: &mut F,
+    predicate: &mut impl FnMut(&F) -> bool,
+    len: impl Fn(&F) -> usize,
+    remove: impl Fn(&mut F, Range<usize>),
+) {
+    let mut chunk = len(fixture);
+    while chunk > 0 {
+        let mut start = 0;
+        while start < len(fixture) {
+            let end = (start + chunk).min(len(fixture));
+            let mut candidate = fixture.clone();
+            remove(&mut candidate, start..end);
+            if predicate(&candidate) {
+                *fixture = candidate;
+            } else {
+                start = end;
+            }
+        }
+        chunk /= 2;
+    }
+}
+
+/// Truncate an account's data as far as the predicate holds.
+fn truncate_account_data<F: MutableFixture + Clone>(
+    fixture: &mut F,
+    predicate: &mut impl FnMut(&F) -> bool,
+    account: usize,
+) {
+    let mut chunk = fixture.accounts()[account].data().len();
+    while chunk > 0 {
+        let len = fixture.accounts()[account].data().len();
+        if len < chunk {
+            chunk = len;
+            continue;
+        }
+        let mut candidate = fixture.clone();
+        candidate.accounts_mut()[account]
+            .data_mut()
+            .truncate(len - chunk);
+        if predicate(&candidate) {
+            *fixture = candidate;
+        } else {
+            chunk /= 2;
+        }
+    }
+}
+
+/// Minimize a fixture's inputs while the predicate holds, ie. the minimized
+/// fixture still reproduces the same error or divergence.
+///
+/// The predicate should hold for the given fixture. If it doesn't, the
+/// fixture is returned as is.
+///
+/// Note: The fixture's effects are left as is, since they can only be known
+/// by executing the minimized fixture.
+pub fn minimize<F: MutableFixture + Clone>(
+    fixture: &F,
+    mut predicate: impl FnMut(&F) -> bool,
+) -> F {
+    let mut fixture = fixture.clone();
+    if !predicate(&fixture) {
+        return fixture;
+    }
+
+    loop {
+        let before = size(&fixture);
+
+        remove_chunks(
+            &mut fixture,
+            &mut predicate,
+            |fixture| fixture.accounts().len(),
+            remove_accounts,
+        );
+        remove_chunks(
+            &mut fixture,
+            &mut predicate,
+            |fixture| fixture.instruction_accounts().len(),
+            |fixture, range| {
+                fixture.instruction_accounts_mut().drain(range);
+            },
+        );
+        for account in 0..fixture.accounts().len() {
+            truncate_account_data(&mut fixture, &mut predicate, account);
+        }
+        remove_chunks(
+            &mut fixture,
+            &mut predicate,
+            |fixture| fixture.instruction_data().len(),
+            |fixture, range| {
+                fixture.instruction_data_mut().drain(range);
+            },
+        );
+
+        if size(&fixture) == before {
+            return fixture;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, mollusk_svm_fuzz_fixture::proto as mollusk};
+
+    fn fixture() -> mollusk::InstrFixture {
+        let account = |address: u8| mollusk::AcctState {
+            address: vec![address; 32],
+            owner: vec![9; 32],
+            lamports: 1_000,
+            data: vec![address; 64],
+            ..Default::default()
+        };
+        let meta = |index: u32| mollusk::InstrAcct {
+            index,
+            is_signer: index == 3,
+            is_writable: true,
+        };
+        mollusk::InstrFixture {
+            input: Some(mollusk::InstrContext {
+                program_id: vec![9; 32],
+                accounts: (1..=6).map(account).collect(),
+                instr_accounts: (0..6).map(meta).collect(),
+                data: (0..32).collect(),
+                ..Default::default()
+            }),
+            output: Some(Default::default()),
+            programs: vec![],
+        }
+    }
+
+    // Reproduces when account 4 is a signer with at least 10 bytes of data,
+    // and the instruction data contains bytes 7 and 20.
+    fn reproduces(fixture: &mollusk::InstrFixture) -> bool {
+        let accounts = fixture.accounts();
+        fixture.instruction_accounts().iter().any(|meta| {
+            let account = &accounts[meta.index() as usize];
+            account.address() == [4; 32] && meta.is_signer() && account.data().len() >= 10
+        }) && fixture.instruction_data().contains(&7)
+            && fixture.instruction_data().contains(&20)
+    }
+
+    #[test]
+    fn test_minimize() {
+        let fixture = fixture();
+        let minimized = minimize(&fixture, reproduces);
+
+        let input = minimized.input.as_ref().unwrap();
+        assert_eq!(input.accounts.len(), 1);
+        assert_eq!(input.accounts[0].address, vec![4; 32]);
+        assert_eq!(input.accounts[0].data, vec![4; 10]);
+        assert_eq!(
+            input.instr_accounts,
+            vec![mollusk::InstrAcct {
+                index: 0,
+                is_signer: true,
+                is_writable: true,
+            }],
+        );
+        assert_eq!(input.data, vec![7, 20]);
+        assert_eq!(input.program_id, vec![9; 32]);
+        assert!(reproduces(&minimized));
+
+        // Fixtures which don't reproduce are left as is.
+        assert_eq!(minimize(&fixture, |_| false), fixture);
+
+        // Removing an account re-indexes the instruction accounts after it.
+        let mut fixture = fixture;
+        remove_accounts(&mut fixture, 1..3);
+        assert_eq!(fixture.accounts().len(), 4);
+        assert_eq!(
+            fixture
+                .instruction_accounts()
+                .iter()
+                .map(|meta| meta.index())
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2, 3],
+        );
+        assert_eq!(fixture.accounts()[1].address(), [4; 32]);
+        assert!(fixture.instruction_accounts()[1].is_signer());
+    }
+}
@@ -0,0 +1,127 @@
+//! Mutations of fixture inputs.
+
+use {
+    crate::{FixtureAccount, FixtureInstructionAccount, MutableFixture},
+    std::fmt,
+};
+
+/// A mutation of a fixture's inputs.
+///
+/// Accounts are referenced by their index in the fixture's accounts, and
+/// instruction accounts by their index in the fixture's instruction
+/// accounts. Mutations referencing an index out of range have no effect.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mutation {
+    /// XOR a byte of the instruction data with a mask.
+    FlipInstructionDataByte { offset: usize, mask: u8 },
+    /// Truncate or zero-extend an account's data.
+    ResizeAccountData { account: usize, len: usize },
+    /// Replace an account's owner.
+    SetOwner { account: usize, owner: Vec<u8> },
+    /// Replace an account's lamports.
+    SetLamports { account: usize, lamports: u64 },
+    /// Toggle the signer flag of an instruction account.
+    ToggleSigner { instruction_account: usize },
+    /// Toggle the writable flag of an instruction account.
+    ToggleWritable { instruction_account: usize },
+    /// Swap the positions of two instruction accounts.
+    SwapInstructionAccounts { a: usize, b: usize },
+}
+
+impl Mutation {
+    /// Apply the mutation to a fixture.
+    pub fn apply<F: MutableFixture>(&self, fixture: &mut F) {
+        match self {
+            Self::FlipInstructionDataByte { offset, mask } => {
+                if let Some(byte) = fixture.instruction_data_mut().get_mut(*offset) {
+                    *byte ^= mask;
+                }
+            }
+            Self::ResizeAccountData { account, len } => {
+                if let Some(account) = fixture.accounts_mut().get_mut(*account) {
+                    account.data_mut().resize(*len, 0);
+                }
+            }
+            Self::SetOwner { account, owner } => {
+                if let Some(account) = fixture.accounts_mut().get_mut(*account) {
+                    account.set_owner(owner.clone());
+                }
+            }
+            Self::SetLamports { account, lamports } => {
+                if let Some(account) = fixture.accounts_mut().get_mut(*account) {
+                    account.set_lamports(*lamports);
+                }
+            }
+            Self::ToggleSigner {
+                instruction_account,
+            } => {
+                if let Some(meta) = fixture
+                    .instruction_accounts_mut()
+                    .get_mut(*instruction_account)
+                {
+                    meta.set_signer(!meta.is_signer());
+                }
+            }
+            Self::ToggleWritable {
+                instruction_account,
+            } => {
+                if let Some(meta) = fixture
+                    .instruction_accounts_mut()
+                    .get_mut(*instruction_account)
+                {
+                    meta.set_writable(!meta.is_writable());
+                }
+            }
+            Self::SwapInstructionAccounts { a, b } => {
+                let metas = fixture.instruction_accounts_mut();
+                if *a < metas.len() && *b < metas.len() {
+                    metas.swap(*a, *b);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FlipInstructionDataByte { offset, mask } => write!(
+                f,
+                "Flipped instruction data byte {} with mask {:#04x}",
+                offset, mask
+            ),
+            Self::ResizeAccountData { account, len } => {
+                write!(f, "Resized data of account {} to {} bytes", account, len)
+            }
+            Self::SetOwner { account, owner } => write!(
+                f,
+                "Set owner of account {} to {}",
+                account,
+                owner
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<String>()
+            ),
+            Self::SetLamports { account, lamports } => {
+                write!(f, "Set lamports of account {} to {}", account, lamports)
+            }
+            Self::ToggleSigner {
+                instruction_account,
+            } => write!(
+                f,
+                "Toggled signer flag of instruction account {}",
+                instruction_account
+            ),
+            Self::ToggleWritable {
+                instruction_account,
+            } => write!(
+                f,
+                "Toggled writable flag of instruction account {}",
+                instruction_account
+            ),
+            Self::SwapInstructionAccounts { a, b } => {
+                write!(f, "Swapped instruction accounts {} and {}", a, b)
+            }
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! Randomized generation of mutations.
+
+use {
+    crate::{FixtureAccount, MutableFixture, Mutation},
+    mollusk_svm_fuzz_fs::SerializableFixture,
+};
+
+/// The maximum length of account data, matching the runtime's limit.
+const MAX_DATA_LEN: usize = 10 * 1024 * 1024;
+/// The maximum number of mutations stacked onto a single mutant.
+const MAX_STACKED_MUTATIONS: usize = 4;
+
+/// The kinds of mutations applicable to a fixture.
+#[derive(Clone, Copy)]
+enum Kind {
+    FlipInstructionDataByte,
+    ResizeAccountData,
+    SetOwner,
+    SetLamports,
+    ToggleSigner,
+    ToggleWritable,
+    SwapInstructionAccounts,
+}
+
+/// Exclude the current value from the options, so mutations always change
+/// the fixture.
+fn without<T: PartialEq, const N: usize>(options: [T; N], current: &T) -> Vec<T> {
+    options
+        .into_iter()
+        .filter(|option| option != current)
+        .collect()
+}
+
+/// A seeded generator of random mutations.
+///
+/// The same seed always produces the same mutations for the same fixture.
+pub struct Mutator {
+    state: u64,
+}
+
+impl Mutator {
+    /// Create a mutator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // SplitMix64.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    fn choose<T: Clone>(&mut self, options: &[T]) -> T {
+        options[self.below(options.len())].clone()
+    }
+
+    /// Generate a random mutation applicable to a fixture, or `None` if the
+    /// fixture has no instruction data, accounts, or instruction accounts to
+    /// mutate.
+    pub fn generate<F: MutableFixture>(&mut self, fixture: &F) -> Option<Mutation> {
+        let data_len = fixture.instruction_data().len();
+        let accounts = fixture.accounts();
+        let metas_len = fixture.instruction_accounts().len();
+
+        let mut kinds = Vec::new();
+        if data_len > 0 {
+            kinds.push(Kind::FlipInstructionDataByte);
+        }
+        if !accounts.is_empty() {
+            kinds.extend([Kind::ResizeAccountData, Kind::SetOwner, Kind::SetLamports]);
+        }
+        if metas_len > 0 {
+            kinds.extend([Kind::ToggleSigner, Kind::ToggleWritable]);
+        }
+        if metas_len > 1 {
+            kinds.push(Kind::SwapInstructionAccounts);
+        }
+        if kinds.is_empty() {
+            return None;
+        }
+
+        let mutation =
+            match self.choose(&kinds) {
+                Kind::FlipInstructionDataByte => Mutation::FlipInstructionDataByte {
+                    offset: self.below(data_len),
+                    mask: self.below(u8::MAX as usize) as u8 + 1,
+                },
+                Kind::ResizeAccountData => {
+                    let account = self.below(accounts.len());
+                    let len = accounts[account].data().len();
+                    let random = self.below(len + 65);
+                    let lens = without(
+                        [0, len.saturating_sub(1), len + 1, len * 2, random]
+                            .map(|new_len| new_len.min(MAX_DATA_LEN)),
+                        &len,
+                    );
+                    Mutation::ResizeAccountData {
+                        account,
+                        len: self.choose(&lens),
+                    }
+                }
+                Kind::SetOwner => {
+                    let account = self.below(accounts.len());
+                    let random = (0..4)
+                        .flat_map(|_| self.next_u64().to_le_bytes())
+                        .collect::<Vec<u8>>();
+                    let mut owners = vec![vec![0; 32], fixture.program_id().to_vec(), random];
+                    owners.extend(accounts.iter().flat_map(|account| {
+                        [account.address().to_vec(), account.owner().to_vec()]
+                    }));
+                    owners.retain(|owner| owner.len() == 32 && owner != accounts[account].owner());
+                    Mutation::SetOwner {
+                        account,
+                        owner: self.choose(&owners),
+                    }
+                }
+                Kind::SetLamports => {
+                    let account = self.below(accounts.len());
+                    let lamports = accounts[account].lamports();
+                    let random = self.next_u64();
+                    let options = without(
+                        [
+                            0,
+                            1,
+                            lamports.saturating_sub(1),
+                            lamports.saturating_add(1),
+                            lamports / 2,
+                            u64::MAX,
+                            random,
+                        ],
+                        &lamports,
+                    );
+                    Mutation::SetLamports {
+                        account,
+                        lamports: self.choose(&options),
+                    }
+                }
+                Kind::ToggleSigner => Mutation::ToggleSigner {
+                    instruction_account: self.below(metas_len),
+                },
+                Kind::ToggleWritable => Mutation::ToggleWritable {
+                    instruction_account: self.below(metas_len),
+                },
+                Kind::SwapInstructionAccounts => {
+                    let a = self.below(metas_len);
+                    let b = (a + 1 + self.below(metas_len - 1)) % metas_len;
+                    Mutation::SwapInstructionAccounts { a, b }
+                }
+            };
+        Some(mutation)
+    }
+
+    /// Apply a random mutation to a fixture, returning the mutation applied.
+    pub fn mutate<F: MutableFixture>(&mut self, fixture: &mut F) -> Option<Mutation> {
+        let mutation = self.generate(fixture)?;
+        mutation.apply(fixture);
+        Some(mutation)
+    }
+
+    /// Produce mutated variants of a fixture, each with up to
+    /// `MAX_STACKED_MUTATIONS` mutations stacked onto it. Useful for
+    /// expanding a corpus.
+    pub fn mutants<F: MutableFixture + Clone>(&mut self, fixture: &F, count: usize) -> Vec<F> {
+        (0..count)
+            .map(|_| {
+                let mut mutant = fixture.clone();
+                for _ in 0..=self.below(MAX_STACKED_MUTATIONS) {
+                    self.mutate(&mut mutant);
+                }
+                mutant
+            })
+            .collect()
+    }
+}
+
+/// Mutate a fixture blob in place, for use as a libFuzzer custom mutator.
+/// `size` is the length of the blob within `data`, and `max_size` the
+/// maximum length of the mutated blob. Returns the length of the mutated
+/// blob, or `None` if the blob isn't a fixture, has nothing to mutate, or the
+/// mutated blob doesn't fit, in which case the caller should fall back to
+/// libFuzzer's own mutations.
+///
+/// ```rust,ignore
+/// fuzz_mutator!(|data: &mut [u8], size: usize, max_size: usize, seed: u32| {
+///     mutate_blob::<InstrFixture>(data, size, max_size, seed as u64)
+///         .unwrap_or_else(|| fuzzer_mutate(data, size, max_size))
+/// });
+/// ```
+pub fn mutate_blob<F: MutableFixture + SerializableFixture>(
+    data: &mut [u8],
+    size: usize,
+    max_size: usize,
+    seed: u64,
+) -> Option<usize> {
+    let mut fixture = F::try_decode(&data[..size]).ok()?;
+    Mutator::new(seed).mutate(&mut fixture)?;
+    let blob = SerializableFixture::encode(&fixture);
+    if blob.len() > max_size.min(data.len()) {
+        return None;
+    }
+    data[..blob.len()].copy_from_slice(&blob);
+    Some(blob.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{FixtureAccount, FixtureInstructionAccount},
+        mollusk_svm_fuzz_fixture::proto as mollusk,
+        mollusk_svm_fuzz_fixture_firedancer::proto as firedancer,
+    };
+
+    fn mollusk_fixture() -> mollusk::InstrFixture {
+        let account = |address: u8| mollusk::AcctState {
+            address: vec![address; 32],
+            owner: vec![9; 32],
+            lamports: 1_000,
+            data: vec![address; 8],
+            ..Default::default()
+        };
+        mollusk::InstrFixture {
+            input: Some(mollusk::InstrContext {
+                program_id: vec![9; 32],
+                accounts: vec![account(1), account(2)],
+                instr_accounts: vec![
+                    mollusk::InstrAcct {
+                        index: 0,
+                        is_signer: true,
+                        is_writable: true,
+                    },
+                    mollusk::InstrAcct {
+                        index: 1,
+                        is_signer: false,
+                        is_writable: false,
+                    },
+                ],
+                data: vec![0, 1, 2, 3],
+                ..Default::default()
+            }),
+            output: Some(Default::default()),
+            programs: vec![],
+        }
+    }
+
+    fn firedancer_fixture() -> firedancer::InstrFixture {
+        firedancer::InstrFixture {
+            input: Some(firedancer::InstrContext {
+                program_id: vec![9; 32],
+                accounts: vec![firedancer::AcctState {
+                    address: vec![1; 32],
+                    owner: vec![9; 32],
+                    lamports: 1_000,
+                    data: vec![1; 8],
+                    ..Default::default()
+                }],
+                instr_accounts: vec![firedancer::InstrAcct {
+                    index: 0,
+                    is_signer: true,
+                    is_writable: true,
+                }],
+                data: vec![0, 1, 2, 3],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut fixture = mollusk_fixture();
+        let mutations = [
+            Mutation::FlipInstructionDataByte {
+                offset: 1,
+                mask: 0xff,
+            },
+            Mutation::ResizeAccountData { account: 0, len: 2 },
+            Mutation::ResizeAccountData {
+                account: 1,
+                len: 10,
+            },
+            Mutation::SetOwner {
+                account: 1,
+                owner: vec![0; 32],
+            },
+            Mutation::SetLamports {
+                account: 0,
+                lamports: 0,
+            },
+            Mutation::ToggleSigner {
+                instruction_account: 0,
+            },
+            Mutation::ToggleWritable {
+                instruction_account: 1,
+            },
+            Mutation::SwapInstructionAccounts { a: 0, b: 1 },
+            // Out of range, so no effect.
+            Mutation::SetLamports {
+                account: 5,
+                lamports: 0,
+            },
+        ];
+        mutations
+            .iter()
+            .for_each(|mutation| mutation.apply(&mut fixture));
+
+        assert_eq!(fixture.instruction_data(), &[0, 0xfe, 2, 3]);
+        let accounts = fixture.accounts();
+        assert_eq!(accounts[0].data(), &[1, 1]);
+        assert_eq!(accounts[0].lamports(), 0);
+        assert_eq!(accounts[1].data(), &[2, 2, 2, 2, 2, 2, 2, 2, 0, 0]);
+        assert_eq!(accounts[1].owner(), &[0; 32]);
+        let metas = fixture.instruction_accounts();
+        assert_eq!(metas[0].index, 1);
+        assert!(metas[0].is_writable() && !metas[0].is_signer());
+        assert_eq!(metas[1].index, 0);
+        assert!(metas[1].is_writable() && !metas[1].is_signer());
+    }
+
+    #[test]
+    fn test_mutator() {
+        let fixture = mollusk_fixture();
+
+        // Mutations are deterministic for a seed, and always applicable.
+        let generate = |seed| {
+            let mut mutator = Mutator::new(seed);
+            (0..256)
+                .map(|_| mutator.generate(&fixture).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let mutations = generate(42);
+        assert_eq!(mutations, generate(42));
+        assert_ne!(mutations, generate(43));
+        for mutation in &mutations {
+            let mut mutant = fixture.clone();
+            mutation.apply(&mut mutant);
+            assert_ne!(mutant, fixture, "{}", mutation);
+        }
+
+        // Both layouts can be mutated.
+        let fixture = firedancer_fixture();
+        let mutants = Mutator::new(42).mutants(&fixture, 16);
+        assert_eq!(mutants.len(), 16);
+        assert!(mutants.iter().all(|mutant| mutant != &fixture));
+        assert!(mutants.iter().all(|mutant| mutant.output == fixture.output));
+
+        // Fixtures without inputs can't be mutated.
+        assert_eq!(
+            Mutator::new(42).generate(&mollusk::InstrFixture::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_mutate_blob() {
+        let fixture = mollusk_fixture();
+        let blob = fixture.encode();
+        let mut data = blob.clone();
+        let max_size = blob.len() * 2;
+        data.resize(max_size, 0);
+
+        let size =
+            mutate_blob::<mollusk::InstrFixture>(&mut data, blob.len(), max_size, 7).unwrap();
+        let mutant = mollusk::InstrFixture::try_decode(&data[..size]).unwrap();
+        assert_ne!(mutant, fixture);
+
+        // Not a fixture.
+        assert_eq!(
+            mutate_blob::<mollusk::InstrFixture>(&mut [0xff; 8], 8, 8, 7),
+            None
+        );
+        // Too large once mutated.
+        let mut data = blob.clone();
+        let max_size = blob.len() / 2;
+        assert_eq!(
+            mutate_blob::<mollusk::InstrFixture>(&mut data, blob.len(), max_size, 7),
+            None
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! Module for debugging programs over the GDB remote protocol.
+//!
+//! When a debug target is configured, Mollusk replaces the BPF loader with a
+//! thin shim. Invocations of every other program are forwarded to the BPF
+//! loader untouched, but when the target program is invoked, it's executed
+//! in the interpreter with a gdbstub server listening on the configured TCP
+//! port. Execution blocks until a debugger (`gdb`, `lldb`) connects, and
+//! resumes once it disconnects.
+//!
+//! A target can be configured on the `Mollusk` instance itself, or through
+//! the environment when running tests:
+//!
+//! ```ignore
+//! MOLLUSK_DEBUG_PROGRAM_ID=<program_id> MOLLUSK_DEBUG_PORT=9001 cargo test ...
+//! ```
+//!
+//! Since tests usually run in parallel, consider passing `--test-threads=1`
+//! (or running a single test), otherwise multiple invocations will race for
+//! the same port.
+//!
+//! Note: requires the `debugger` feature.
+
+use {
+    solana_bpf_loader_program::{create_vm, serialization, MEMORY_POOL},
+    solana_log_collector::ic_logger_msg,
+    solana_program_runtime::{
+        invoke_context::InvokeContext, loaded_programs::ProgramCacheEntryType, stable_log,
+    },
+    solana_rbpf::{
+        declare_builtin_function,
+        elf::Executable,
+        error::{EbpfError, ProgramResult},
+        memory_region::{AccessType, MemoryMapping},
+        vm::ContextObject,
+    },
+    solana_sdk::{
+        bpf_loader_deprecated,
+        entrypoint::{MAX_PERMITTED_DATA_INCREASE, SUCCESS},
+        feature_set::{bpf_account_data_direct_mapping, deplete_cu_meter_on_vm_failure},
+        instruction::InstructionError,
+        native_loader,
+        pubkey::Pubkey,
+        transaction_context::IndexOfAccount,
+    },
+    std::{cell::Cell, mem, str::FromStr},
+};
+
+/// The environment variable used to configure the program to debug.
+pub const DEBUG_PROGRAM_ID_ENV: &str = "MOLLUSK_DEBUG_PROGRAM_ID";
+/// The environment variable used to configure the debugger's TCP port.
+pub const DEBUG_PORT_ENV: &str = "MOLLUSK_DEBUG_PORT";
+/// The TCP port the debugger listens on, unless configured otherwise.
+pub const DEFAULT_DEBUG_PORT: u16 = 9001;
+
+/// A program to debug, and the local TCP port on which to serve the GDB
+/// remote protocol when it's invoked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Debugger {
+    pub program_id: Pubkey,
+    pub port: u16,
+}
+
+impl Debugger {
+    /// Read the debug target from the `MOLLUSK_DEBUG_PROGRAM_ID` and
+    /// `MOLLUSK_DEBUG_PORT` environment variables, if set.
+    ///
+    /// Malformed values are ignored with a warning, rather than failing
+    /// every `Mollusk` constructed by default.
+    pub fn from_env() -> Option<Self> {
+        let program_id = std::env::var(DEBUG_PROGRAM_ID_ENV).ok()?;
+        let Ok(program_id) = Pubkey::from_str(program_id.trim()) else {
+            eprintln!(
+                "Ignoring {}: invalid program ID `{}`",
+                DEBUG_PROGRAM_ID_ENV, program_id
+            );
+            return None;
+        };
+        let port = match std::env::var(DEBUG_PORT_ENV) {
+            Ok(port) => match u16::from_str(port.trim()) {
+                Ok(port) => port,
+                Err(_) => {
+                    eprintln!(
+                        "Ignoring {} and {}: invalid port `{}`",
+                        DEBUG_PROGRAM_ID_ENV, DEBUG_PORT_ENV, port
+                    );
+                    return None;
+                }
+            },
+            Err(_) => DEFAULT_DEBUG_PORT,
+        };
+        Some(Self { program_id, port })
+    }
+}
+
+thread_local! {
+    static TARGET: Cell<Option<Debugger>> = const { Cell::new(None) };
+}
+
+/// Put the debug target in effect for every instruction processed on the
+/// current thread, until the returned guard is dropped.
+pub(crate) fn attach(target: Option<Debugger>) -> AttachGuard {
+    AttachGuard {
+        previous: TARGET.replace(target),
+    }
+}
+
+pub(crate) struct AttachGuard {
+    previous: Option<Debugger>,
+}
+
+impl Drop for AttachGuard {
+    fn drop(&mut self) {
+        TARGET.set(self.previous.take());
+    }
+}
+
+declare_builtin_function!(
+    /// BPF loader entrypoint, which forwards to the BPF loader unless the
+    /// invoked program is the debug target.
+    Entrypoint,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        _arg0: u64,
+        _arg1: u64,
+        _arg2: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        if let Some(target) = TARGET.get() {
+            let transaction_context = &invoke_context.transaction_context;
+            let instruction_context = transaction_context.get_current_instruction_context()?;
+            let program_account =
+                instruction_context.try_borrow_last_program_account(transaction_context)?;
+            let is_target = *program_account.get_key() == target.program_id
+                && !native_loader::check_id(program_account.get_owner())
+                && program_account.is_executable();
+            drop(program_account);
+            if is_target {
+                if let Some(entry) = invoke_context
+                    .program_cache_for_tx_batch
+                    .find(&target.program_id)
+                {
+                    if let ProgramCacheEntryType::Loaded(executable) = &entry.program {
+                        return execute(executable, invoke_context, target.port).map(|_| 0);
+                    }
+                }
+            }
+        }
+        solana_bpf_loader_program::process_instruction_inner(invoke_context)
+    }
+);
+
+/// Execute a program in the interpreter, with the VM's debugger attached to
+/// the provided port.
+///
+/// Mirrors `solana_bpf_loader_program::execute`, minus the timings. Since
+/// `solana_rbpf` is pinned alongside the loader, the `debugger` tests run
+/// programs through both paths and compare the results to catch any drift.
+fn execute<'a, 'b: 'a>(
+    executable: &'a Executable<InvokeContext<'static>>,
+    invoke_context: &'a mut InvokeContext<'b>,
+    port: u16,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // SAFETY: The program cache stores executables with the `InvokeContext`
+    // lifetime erased to `'static`, since it outlives any single transaction.
+    // The lifetime is only a marker for the context object the VM is created
+    // with, which here is `invoke_context`, so narrowing it to `'b` matches
+    // the context actually passed in. The executable itself is borrowed from
+    // the transaction's program cache for `'a`, which the VM doesn't outlive.
+    // This is the same cast `solana_bpf_loader_program::execute` performs.
+    let executable = unsafe {
+        mem::transmute::<&'a Executable<InvokeContext<'static>>, &'a Executable<InvokeContext<'b>>>(
+            executable,
+        )
+    };
+    let log_collector = invoke_context.get_log_collector();
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let (program_id, is_loader_deprecated) = {
+        let program_account =
+            instruction_context.try_borrow_last_program_account(transaction_context)?;
+        (
+            *program_account.get_key(),
+            *program_account.get_owner() == bpf_loader_deprecated::id(),
+        )
+    };
+    let direct_mapping = invoke_context
+        .get_feature_set()
+        .is_active(&bpf_account_data_direct_mapping::id());
+
+    let (parameter_bytes, regions, accounts_metadata) = serialization::serialize_parameters(
+        invoke_context.transaction_context,
+        instruction_context,
+        !direct_mapping,
+    )?;
+
+    let account_region_addrs = accounts_metadata
+        .iter()
+        .map(|m| {
+            let vm_end = m
+                .vm_data_addr
+                .saturating_add(m.original_data_len as u64)
+                .saturating_add(if !is_loader_deprecated {
+                    MAX_PERMITTED_DATA_INCREASE as u64
+                } else {
+                    0
+                });
+            m.vm_data_addr..vm_end
+        })
+        .collect::<Vec<_>>();
+
+    let execution_result = {
+        let compute_meter_prev = invoke_context.get_remaining();
+        create_vm!(vm, executable, regions, accounts_metadata, invoke_context);
+        let (mut vm, stack, heap) = match vm {
+            Ok(info) => info,
+            Err(e) => {
+                ic_logger_msg!(log_collector, "Failed to create SBF VM: {}", e);
+                return Err(Box::new(InstructionError::ProgramEnvironmentSetupFailure));
+            }
+        };
+
+        // The debugger is only served by the interpreter.
+        vm.debug_port = Some(port);
+        let (compute_units_consumed, result) = vm.execute_program(executable, true);
+        MEMORY_POOL.with_borrow_mut(|memory_pool| {
+            memory_pool.put_stack(stack);
+            memory_pool.put_heap(heap);
+        });
+        drop(vm);
+
+        ic_logger_msg!(
+            log_collector,
+            "Program {} consumed {} of {} compute units",
+            &program_id,
+            compute_units_consumed,
+            compute_meter_prev
+        );
+        let (_returned_from_program_id, return_data) =
+            invoke_context.transaction_context.get_return_data();
+        if !return_data.is_empty() {
+            stable_log::program_return(&log_collector, &program_id, return_data);
+        }
+        match result {
+            ProgramResult::Ok(status) if status != SUCCESS => {
+                let error: InstructionError = status.into();
+                Err(Box::new(error) as Box<dyn std::error::Error>)
+            }
+            ProgramResult::Err(mut error) => {
+                if invoke_context
+                    .get_feature_set()
+                    .is_active(&deplete_cu_meter_on_vm_failure::id())
+                    && !matches!(error, EbpfError::SyscallError(_))
+                {
+                    invoke_context.consume(invoke_context.get_remaining());
+                }
+                if direct_mapping {
+                    if let EbpfError::AccessViolation(AccessType::Store, address, _, _) = error {
+                        if let Some((instruction_account_index, _)) = account_region_addrs
+                            .iter()
+                            .enumerate()
+                            .find(|(_, vm_region)| vm_region.contains(&address))
+                        {
+                            let transaction_context = &invoke_context.transaction_context;
+                            let instruction_context =
+                                transaction_context.get_current_instruction_context()?;
+                            let account = instruction_context.try_borrow_instruction_account(
+                                transaction_context,
+                                instruction_account_index as IndexOfAccount,
+                            )?;
+                            error = EbpfError::SyscallError(Box::new(if account.is_executable() {
+                                InstructionError::ExecutableDataModified
+                            } else if account.is_writable() {
+                                InstructionError::ExternalAccountDataModified
+                            } else {
+                                InstructionError::ReadonlyDataModified
+                            }));
+                        }
+                    }
+                }
+                Err(if let EbpfError::SyscallError(err) = error {
+                    err
+                } else {
+                    error.into()
+                })
+            }
+            _ => Ok(()),
+        }
+    };
+
+    execution_result.and_then(|_| {
+        serialization::deserialize_parameters(
+            invoke_context.transaction_context,
+            invoke_context
+                .transaction_context
+                .get_current_instruction_context()?,
+            !direct_mapping,
+            parameter_bytes.as_slice(),
+            &invoke_context.get_syscall_context()?.accounts_metadata,
+        )
+        .map_err(|error| Box::new(error) as Box<dyn std::error::Error>)
+    })
+}
@@ -0,0 +1,614 @@
+//! Module for structured, human-readable diffs of accounts.
+//!
+//! Rather than printing entire account data buffers, diffs capture only what
+//! changed: lamport deltas, owner and executable changes, resizes, and the
+//! byte ranges of data which differ, rendered as hex dumps.
+//!
+//! Diffs implement `Display` for plain output, and can be rendered with ANSI
+//! colors through `colored()`.
+
+use {
+    solana_sdk::{
+        account::{Account, ReadableAccount},
+        pubkey::Pubkey,
+    },
+    std::{
+        fmt,
+        io::{self, IsTerminal},
+    },
+};
+
+/// Differing byte ranges separated by fewer than this many equal bytes are
+/// merged into a single range, to keep hex dumps compact.
+const MERGE_GAP: usize = 8;
+/// The number of bytes per hex dump line.
+const BYTES_PER_LINE: usize = 16;
+/// The maximum size of the table used to diff the lines of two states. Past
+/// it, the differing lines are listed in full rather than interleaved.
+const MAX_STATE_DIFF_CELLS: usize = 1 << 20;
+
+/// A contiguous range of account data which differs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ByteRangeDiff {
+    /// The offset of the range within the account data.
+    pub offset: usize,
+    /// The old bytes in the range. Shorter than `new` if the data grew.
+    pub old: Vec<u8>,
+    /// The new bytes in the range. Shorter than `old` if the data shrank.
+    pub new: Vec<u8>,
+}
+
+/// The differences between two account data buffers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataDiff {
+    /// The length of the old data.
+    pub old_len: usize,
+    /// The length of the new data.
+    pub new_len: usize,
+    /// The byte ranges which differ.
+    pub ranges: Vec<ByteRangeDiff>,
+}
+
+impl DataDiff {
+    /// Diff two account data buffers, returning `None` if they're equal.
+    pub fn new(old: &[u8], new: &[u8]) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+        let max_len = old.len().max(new.len());
+        let differs = |i: usize| old.get(i) != new.get(i);
+
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        let mut i = 0;
+        while i < max_len {
+            if !differs(i) {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < max_len && differs(i) {
+                i += 1;
+            }
+            match ranges.last_mut() {
+                Some((_, end)) if start - *end < MERGE_GAP => *end = i,
+                _ => ranges.push((start, i)),
+            }
+        }
+
+        let slice = |data: &[u8], start: usize, end: usize| {
+            data.get(start.min(data.len())..end.min(data.len()))
+                .unwrap_or_default()
+                .to_vec()
+        };
+        Some(Self {
+            old_len: old.len(),
+            new_len: new.len(),
+            ranges: ranges
+                .into_iter()
+                .map(|(start, end)| ByteRangeDiff {
+                    offset: start,
+                    old: slice(old, start, end),
+                    new: slice(new, start, end),
+                })
+                .collect(),
+        })
+    }
+}
+
+/// The differences between two states of an account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountDiff {
+    /// The account's address.
+    pub pubkey: Pubkey,
+    /// The old and new lamports, if changed.
+    pub lamports: Option<(u64, u64)>,
+    /// The old and new owner, if changed.
+    pub owner: Option<(Pubkey, Pubkey)>,
+    /// The old and new executable flag, if changed.
+    pub executable: Option<(bool, bool)>,
+    /// The old and new rent epoch, if changed.
+    pub rent_epoch: Option<(u64, u64)>,
+    /// The data differences, if changed.
+    pub data: Option<DataDiff>,
+}
+
+impl AccountDiff {
+    /// Diff two states of an account, returning `None` if they're equal.
+    pub fn new(pubkey: &Pubkey, old: &Account, new: &Account) -> Option<Self> {
+        fn changed<T: PartialEq>(old: T, new: T) -> Option<(T, T)> {
+            (old != new).then_some((old, new))
+        }
+        let diff = Self {
+            pubkey: *pubkey,
+            lamports: changed(old.lamports(), new.lamports()),
+            owner: changed(*old.owner(), *new.owner()),
+            executable: changed(old.executable(), new.executable()),
+            rent_epoch: changed(old.rent_epoch(), new.rent_epoch()),
+            data: DataDiff::new(old.data(), new.data()),
+        };
+        let unchanged = diff.lamports.is_none()
+            && diff.owner.is_none()
+            && diff.executable.is_none()
+            && diff.rent_epoch.is_none()
+            && diff.data.is_none();
+        (!unchanged).then_some(diff)
+    }
+}
+
+/// The differences between two sets of accounts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diff {
+    /// The accounts which differ, in order of appearance.
+    pub accounts: Vec<AccountDiff>,
+}
+
+impl Diff {
+    /// Diff two sets of accounts, matched by address.
+    ///
+    /// An account missing from one of the sets is diffed against a default
+    /// (empty) account.
+    pub fn new(old: &[(Pubkey, Account)], new: &[(Pubkey, Account)]) -> Self {
+        let default = Account::default();
+        fn find<'a>(accounts: &'a [(Pubkey, Account)], pubkey: &Pubkey) -> Option<&'a Account> {
+            accounts
+                .iter()
+                .find(|(key, _)| key == pubkey)
+                .map(|(_, account)| account)
+        }
+        let mut accounts: Vec<AccountDiff> = old
+            .iter()
+            .filter_map(|(pubkey, old_account)| {
+                let new_account = find(new, pubkey).unwrap_or(&default);
+                AccountDiff::new(pubkey, old_account, new_account)
+            })
+            .collect();
+        accounts.extend(
+            new.iter()
+                .filter(|(pubkey, _)| find(old, pubkey).is_none())
+                .filter_map(|(pubkey, new_account)| {
+                    AccountDiff::new(pubkey, &default, new_account)
+                }),
+        );
+        Self { accounts }
+    }
+
+    /// Whether the two sets of accounts were identical.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+}
+
+/// A line of a `StateDiff`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateDiffLine {
+    /// A line present in both states.
+    Unchanged(String),
+    /// A line only present in the old state.
+    Removed(String),
+    /// A line only present in the new state.
+    Added(String),
+}
+
+/// The field-level differences between two deserialized account states,
+/// computed over their pretty-printed `Debug` representations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateDiff {
+    /// Every line of the old and new states, in order.
+    pub lines: Vec<StateDiffLine>,
+}
+
+impl StateDiff {
+    /// Diff two account states, returning `None` if they're equal.
+    pub fn new<T: fmt::Debug + PartialEq>(old: &T, new: &T) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+        let old = format!("{:#?}", old);
+        let new = format!("{:#?}", new);
+        let old = old.lines().collect::<Vec<_>>();
+        let new = new.lines().collect::<Vec<_>>();
+
+        // Only the lines between the common prefix and suffix are diffed,
+        // which is usually a handful, even for large states.
+        let prefix = old
+            .iter()
+            .zip(new.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let (old_middle, new_middle) = (
+            &old[prefix..old.len() - suffix],
+            &new[prefix..new.len() - suffix],
+        );
+
+        let mut lines = old[..prefix]
+            .iter()
+            .map(|line| StateDiffLine::Unchanged(line.to_string()))
+            .collect::<Vec<_>>();
+        if (old_middle.len() + 1).saturating_mul(new_middle.len() + 1) > MAX_STATE_DIFF_CELLS {
+            lines.extend(
+                old_middle
+                    .iter()
+                    .map(|line| StateDiffLine::Removed(line.to_string())),
+            );
+            lines.extend(
+                new_middle
+                    .iter()
+                    .map(|line| StateDiffLine::Added(line.to_string())),
+            );
+        } else {
+            diff_lines(old_middle, new_middle, &mut lines);
+        }
+        lines.extend(
+            old[old.len() - suffix..]
+                .iter()
+                .map(|line| StateDiffLine::Unchanged(line.to_string())),
+        );
+        Some(Self { lines })
+    }
+}
+
+/// Diff two sequences of lines by their longest common subsequence.
+fn diff_lines(old: &[&str], new: &[&str], lines: &mut Vec<StateDiffLine>) {
+    // Longest common subsequence of lines, filled back to front.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(StateDiffLine::Unchanged(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(StateDiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(StateDiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+}
+
+/// A diff rendered with ANSI colors.
+pub struct Colored<'a, T>(&'a T);
+
+/// Whether diffs printed to stderr should be colored, ie. when stderr is a
+/// terminal and `NO_COLOR` is not set.
+pub(crate) fn use_colors() -> bool {
+    std::env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal()
+}
+
+/// Render a diff to be printed to stderr, colored if `use_colors`.
+pub(crate) fn render_for_stderr<T>(diff: &T) -> String
+where
+    T: fmt::Display,
+    for<'a> Colored<'a, T>: fmt::Display,
+{
+    if use_colors() {
+        Colored(diff).to_string()
+    } else {
+        diff.to_string()
+    }
+}
+
+struct Palette {
+    header: &'static str,
+    removed: &'static str,
+    added: &'static str,
+    reset: &'static str,
+}
+
+const PLAIN: Palette = Palette {
+    header: "",
+    removed: "",
+    added: "",
+    reset: "",
+};
+
+const COLORS: Palette = Palette {
+    header: "\x1b[1m",
+    removed: "\x1b[31m",
+    added: "\x1b[32m",
+    reset: "\x1b[0m",
+};
+
+trait Render {
+    fn render(&self, f: &mut fmt::Formatter<'_>, p: &Palette) -> fmt::Result;
+}
+
+fn render_hex(
+    f: &mut fmt::Formatter<'_>,
+    offset: usize,
+    bytes: &[u8],
+    sign: char,
+    color: &str,
+    p: &Palette,
+) -> fmt::Result {
+    if bytes.is_empty() {
+        return writeln!(f, "    {}{} (none){}", color, sign, p.reset);
+    }
+    for (i, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let hex = line
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            f,
+            "    {}{} {:#06x}: {}{}",
+            color,
+            sign,
+            offset + i * BYTES_PER_LINE,
+            hex,
+            p.reset
+        )?;
+    }
+    Ok(())
+}
+
+impl Render for DataDiff {
+    fn render(&self, f: &mut fmt::Formatter<'_>, p: &Palette) -> fmt::Result {
+        if self.old_len != self.new_len {
+            writeln!(
+                f,
+                "  data: resized {} -> {} bytes",
+                self.old_len, self.new_len
+            )?;
+        }
+        for range in &self.ranges {
+            let len = range.old.len().max(range.new.len());
+            writeln!(
+                f,
+                "  data [{:#06x}..{:#06x}]:",
+                range.offset,
+                range.offset + len
+            )?;
+            render_hex(f, range.offset, &range.old, '-', p.removed, p)?;
+            render_hex(f, range.offset, &range.new, '+', p.added, p)?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for AccountDiff {
+    fn render(&self, f: &mut fmt::Formatter<'_>, p: &Palette) -> fmt::Result {
+        writeln!(f, "{}Account {}:{}", p.header, self.pubkey, p.reset)?;
+        if let Some((old, new)) = self.lamports {
+            let delta = new as i128 - old as i128;
+            let color = if delta < 0 { p.removed } else { p.added };
+            writeln!(
+                f,
+                "  lamports: {} -> {} ({}{:+}{})",
+                old, new, color, delta, p.reset
+            )?;
+        }
+        if let Some((old, new)) = &self.owner {
+            writeln!(
+                f,
+                "  owner: {}{}{} -> {}{}{}",
+                p.removed, old, p.reset, p.added, new, p.reset
+            )?;
+        }
+        if let Some((old, new)) = self.executable {
+            writeln!(f, "  executable: {} -> {}", old, new)?;
+        }
+        if let Some((old, new)) = self.rent_epoch {
+            writeln!(f, "  rent_epoch: {} -> {}", old, new)?;
+        }
+        if let Some(data) = &self.data {
+            data.render(f, p)?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for Diff {
+    fn render(&self, f: &mut fmt::Formatter<'_>, p: &Palette) -> fmt::Result {
+        if self.accounts.is_empty() {
+            return writeln!(f, "No account changes");
+        }
+        for account in &self.accounts {
+            account.render(f, p)?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for StateDiff {
+    fn render(&self, f: &mut fmt::Formatter<'_>, p: &Palette) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                StateDiffLine::Unchanged(line) => writeln!(f, "      {}", line)?,
+                StateDiffLine::Removed(line) => {
+                    writeln!(f, "    {}- {}{}", p.removed, line, p.reset)?
+                }
+                StateDiffLine::Added(line) => writeln!(f, "    {}+ {}{}", p.added, line, p.reset)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+macro_rules! impl_display {
+    ($($ty:ty),*) => {
+        $(
+            impl $ty {
+                /// Render the diff with ANSI colors.
+                pub fn colored(&self) -> Colored<'_, Self> {
+                    Colored(self)
+                }
+            }
+
+            impl fmt::Display for $ty {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    self.render(f, &PLAIN)
+                }
+            }
+
+            impl fmt::Display for Colored<'_, $ty> {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    self.0.render(f, &COLORS)
+                }
+            }
+        )*
+    };
+}
+
+impl_display!(DataDiff, AccountDiff, Diff, StateDiff);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_diff() {
+        assert_eq!(DataDiff::new(&[1, 2, 3], &[1, 2, 3]), None);
+
+        // Nearby changes are merged, distant ones are not.
+        let mut old = vec![0; 64];
+        let mut new = old.clone();
+        new[2] = 1;
+        new[5] = 1;
+        new[40] = 1;
+        assert_eq!(
+            DataDiff::new(&old, &new),
+            Some(DataDiff {
+                old_len: 64,
+                new_len: 64,
+                ranges: vec![
+                    ByteRangeDiff {
+                        offset: 2,
+                        old: vec![0, 0, 0, 0],
+                        new: vec![1, 0, 0, 1],
+                    },
+                    ByteRangeDiff {
+                        offset: 40,
+                        old: vec![0],
+                        new: vec![1],
+                    },
+                ],
+            }),
+        );
+
+        // Resizes.
+        old.truncate(4);
+        new.truncate(2);
+        assert_eq!(
+            DataDiff::new(&old, &new),
+            Some(DataDiff {
+                old_len: 4,
+                new_len: 2,
+                ranges: vec![ByteRangeDiff {
+                    offset: 2,
+                    old: vec![0, 0],
+                    new: vec![],
+                }],
+            }),
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let owner = Pubkey::new_unique();
+        let unchanged = (Pubkey::new_unique(), Account::new(1, 0, &owner));
+        let changed = Pubkey::new_unique();
+        let created = Pubkey::new_unique();
+
+        let old = vec![unchanged.clone(), (changed, Account::new(100, 2, &owner))];
+        let new = vec![
+            unchanged,
+            (changed, {
+                let mut account = Account::new(90, 2, &owner);
+                account.data = vec![0, 7];
+                account
+            }),
+            (created, Account::new(10, 0, &owner)),
+        ];
+
+        let diff = Diff::new(&old, &new);
+        assert_eq!(diff.accounts.len(), 2);
+        assert_eq!(diff.accounts[0].pubkey, changed);
+        assert_eq!(diff.accounts[0].lamports, Some((100, 90)));
+        assert_eq!(diff.accounts[0].owner, None);
+        assert_eq!(diff.accounts[1].pubkey, created);
+        assert_eq!(diff.accounts[1].owner, Some((Pubkey::default(), owner)));
+
+        let rendered = diff.to_string();
+        assert!(rendered.contains("lamports: 100 -> 90 (-10)"));
+        assert!(rendered.contains("    - 0x0001: 00\n    + 0x0001: 07\n"));
+    }
+
+    #[test]
+    fn test_state_diff() {
+        #[derive(Debug, PartialEq)]
+        struct State {
+            authority: u8,
+            amount: u64,
+        }
+
+        let old = State {
+            authority: 1,
+            amount: 5,
+        };
+        let new = State {
+            authority: 1,
+            amount: 6,
+        };
+        assert_eq!(StateDiff::new(&old, &old), None);
+        assert_eq!(
+            StateDiff::new(&old, &new).unwrap().to_string(),
+            concat!(
+                "      State {\n",
+                "          authority: 1,\n",
+                "    -     amount: 5,\n",
+                "    +     amount: 6,\n",
+                "      }\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn test_large_state_diff() {
+        // Pretty-printed, each byte is a line of its own.
+        let old = vec![0u8; 10_000];
+        let mut new = old.clone();
+        new[5_000] = 1;
+        let diff = StateDiff::new(&old, &new).unwrap();
+        let changed = diff
+            .lines
+            .iter()
+            .filter(|line| !matches!(line, StateDiffLine::Unchanged(_)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changed,
+            vec![
+                &StateDiffLine::Removed("    0,".to_string()),
+                &StateDiffLine::Added("    1,".to_string()),
+            ]
+        );
+
+        // States differing throughout are listed in full, rather than diffed.
+        let new = vec![1u8; 10_001];
+        let diff = StateDiff::new(&old, &new).unwrap();
+        assert_eq!(diff.lines.len(), 1 + 10_000 + 10_001 + 1);
+        assert_eq!(diff.lines[1], StateDiffLine::Removed("    0,".to_string()));
+        assert_eq!(
+            diff.lines[10_001],
+            StateDiffLine::Added("    1,".to_string())
+        );
+    }
+}
@@ -82,3 +82,17 @@ pub fn load_program_elf(program_name: &str) -> Vec<u8> {
     let program_file = find_file(&file_name).or_panic_with(MolluskError::FileNotFound(&file_name));
     read_file(program_file)
 }
+
+/// Load a program's unstripped ELF file, containing its debug info, from the
+/// local filesystem by program name.
+///
+/// The file is searched for in the same default search paths as
+/// `load_program_elf`, and is expected to be named `{program_name}.debug`,
+/// as produced by `cargo build-sbf --debug`.
+///
+/// Unlike most functions in this module, this one does not panic if the file
+/// is not found, since debug info is optional.
+pub fn load_program_debug_elf(program_name: &str) -> Option<Vec<u8>> {
+    let file_name = format!("{program_name}.debug");
+    find_file(&file_name).map(read_file)
+}
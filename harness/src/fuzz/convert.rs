@@ -0,0 +1,149 @@
+//! Module for converting fixtures between the Mollusk and Firedancer
+//! layouts, by way of Mollusk types.
+//!
+//! Each layout records context the other can't represent, such as sysvars,
+//! which only Mollusk fixtures record, or seed addresses, which only
+//! Firedancer fixtures record. Conversions keep as much context as the
+//! target layout supports, and report the fields they drop.
+//!
+//! Only available when both the `fuzz` and `fuzz-fd` features are enabled.
+
+use {
+    super::{firedancer, mollusk},
+    crate::{result::ProgramResult, sysvar::Sysvars},
+    mollusk_svm_fuzz_fixture::{
+        effects::Effects as MolluskEffects, sysvars::Sysvars as MolluskSysvars,
+        Fixture as MolluskFixture,
+    },
+    mollusk_svm_fuzz_fixture_firedancer::Fixture as FiredancerFixture,
+    solana_compute_budget::compute_budget::ComputeBudget,
+    solana_sdk::pubkey::Pubkey,
+};
+
+/// A fixture converted to another layout.
+pub struct Conversion<F> {
+    /// The converted fixture.
+    pub fixture: F,
+    /// The fields of the original fixture which the target layout can't
+    /// represent, and were dropped.
+    pub dropped: Vec<&'static str>,
+}
+
+// Firedancer fixtures only record the slot, so the sysvars they're replayed
+// with are Mollusk's defaults, warped to that slot.
+fn sysvars_at_slot(slot: u64) -> Sysvars {
+    let mut sysvars = Sysvars::default();
+    if slot != 0 {
+        sysvars.warp_to_slot(slot);
+    }
+    sysvars
+}
+
+/// Convert a Mollusk fixture to the Firedancer layout.
+///
+/// Firedancer fixtures only record the compute unit limit of the compute
+/// budget, and the slot of the sysvars. Their effects don't record execution
+/// time, logs, inner instructions or the program which set the return data,
+/// and there is no room for embedded programs.
+pub fn mollusk_to_firedancer(fixture: &MolluskFixture) -> Conversion<FiredancerFixture> {
+    let (context, result) = mollusk::load_fixture(fixture);
+    let slot = context.sysvars.clock.slot;
+
+    let mut dropped = vec![];
+    let compute_budget = ComputeBudget {
+        compute_unit_limit: context.compute_budget.compute_unit_limit,
+        ..Default::default()
+    };
+    if context.compute_budget != compute_budget {
+        dropped.push("compute_budget");
+    }
+    if MolluskSysvars::from(&sysvars_at_slot(slot)) != fixture.input.sysvars {
+        dropped.push("sysvars");
+    }
+    if !fixture.programs.is_empty() {
+        dropped.push("programs");
+    }
+    let effects = &fixture.output;
+    if effects.execution_time != 0 {
+        dropped.push("effects.execution_time");
+    }
+    if !effects.logs.is_empty() {
+        dropped.push("effects.logs");
+    }
+    if !effects.inner_instructions.is_empty() {
+        dropped.push("effects.inner_instructions");
+    }
+    if !effects.return_data.is_empty() && effects.return_data_program_id != Pubkey::default() {
+        dropped.push("effects.return_data_program_id");
+    }
+
+    let input = firedancer::build_fixture_context(
+        &context.accounts,
+        &context.compute_budget,
+        &context.feature_set,
+        &context.instruction,
+        slot,
+    );
+    // Results loaded from Mollusk fixture effects only hold instruction
+    // errors.
+    let output = firedancer::build_fixture_effects(&input, &result)
+        .expect("Fixture effects don't record transaction errors");
+
+    Conversion {
+        fixture: FiredancerFixture {
+            metadata: Some(firedancer::instruction_metadata()),
+            input,
+            output,
+        },
+        dropped,
+    }
+}
+
+/// Convert a Firedancer fixture to the Mollusk layout.
+///
+/// Mollusk fixtures don't record seed addresses, and only record program
+/// errors which convert to a program error code. The slot is recorded in
+/// the sysvars, and the effects are written with version 0, since Firedancer
+/// fixtures don't record logs, inner instructions or the program which set
+/// the return data.
+pub fn firedancer_to_mollusk(fixture: &FiredancerFixture) -> Conversion<MolluskFixture> {
+    let (context, result) = firedancer::load_firedancer_fixture(fixture);
+
+    let mut dropped = vec![];
+    if fixture
+        .input
+        .accounts
+        .iter()
+        .chain(&fixture.output.modified_accounts)
+        .any(|(_, _, seed_address)| seed_address.is_some())
+    {
+        dropped.push("seed_addresses");
+    }
+    if matches!(result.program_result, ProgramResult::UnknownError(_)) {
+        dropped.push("effects.result");
+    }
+
+    let input = mollusk::build_fixture_context(
+        &context.accounts,
+        &context.compute_budget,
+        &context.feature_set,
+        &context.instruction,
+        &sysvars_at_slot(context.slot),
+    );
+    // Results loaded from Firedancer fixture effects only hold instruction
+    // errors.
+    let output = MolluskEffects {
+        version: 0,
+        ..MolluskEffects::try_from(&result)
+            .expect("Fixture effects don't record transaction errors")
+    };
+
+    Conversion {
+        fixture: MolluskFixture {
+            input,
+            output,
+            programs: vec![],
+        },
+        dropped,
+    }
+}
@@ -7,7 +7,7 @@
 use {
     crate::{
         accounts::{compile_accounts, CompiledAccounts},
-        result::InstructionResult,
+        result::{InstructionResult, ProgramResult},
         Mollusk, DEFAULT_LOADER_KEY,
     },
     mollusk_svm_fuzz_fixture_firedancer::{
@@ -25,6 +25,7 @@ use {
         feature_set::FeatureSet,
         instruction::{AccountMeta, Instruction, InstructionError},
         pubkey::Pubkey,
+        transaction::TransactionError,
     },
 };
 
@@ -66,7 +67,7 @@ fn num_to_instr_err(num: i32, custom_code: u32) -> InstructionError {
     deser
 }
 
-fn build_fixture_context(
+pub(crate) fn build_fixture_context(
     accounts: &[(Pubkey, Account)],
     compute_budget: &ComputeBudget,
     feature_set: &FeatureSet,
@@ -158,7 +159,17 @@ pub(crate) fn parse_fixture_context(context: &FuzzContext) -> ParsedFixtureConte
     }
 }
 
-fn build_fixture_effects(context: &FuzzContext, result: &InstructionResult) -> FuzzEffects {
+/// Fixture effects only record instruction errors, so a result rejected with
+/// a transaction error, such as an invalid rent state transition, can't be
+/// recorded, just like the Mollusk layout.
+pub(crate) fn build_fixture_effects(
+    context: &FuzzContext,
+    result: &InstructionResult,
+) -> Result<FuzzEffects, TransactionError> {
+    if let ProgramResult::TransactionError(err) = &result.program_result {
+        return Err(err.clone());
+    }
+
     let mut program_custom_code = 0;
     let program_result = match &result.raw_result {
         Ok(()) => 0,
@@ -187,7 +198,7 @@ fn build_fixture_effects(context: &FuzzContext, result: &InstructionResult) -> F
         })
         .collect();
 
-    FuzzEffects {
+    Ok(FuzzEffects {
         program_result,
         program_custom_code,
         modified_accounts,
@@ -195,7 +206,7 @@ fn build_fixture_effects(context: &FuzzContext, result: &InstructionResult) -> F
             .compute_units_available
             .saturating_sub(result.compute_units_consumed),
         return_data,
-    }
+    })
 }
 
 pub(crate) fn parse_fixture_effects(
@@ -231,26 +242,37 @@ pub(crate) fn parse_fixture_effects(
     InstructionResult {
         program_result,
         raw_result,
+        error_location: None,
         execution_time: 0, // TODO: Omitted for now.
         compute_units_consumed: compute_unit_limit.saturating_sub(effects.compute_units_available),
+        invariant_violations: vec![],
         return_data,
+        // Not recorded by Firedancer fixtures.
+        return_data_program_id: Pubkey::default(),
+        logs: vec![],
+        inner_instructions: vec![],
         resulting_accounts,
     }
 }
 
-fn instruction_metadata() -> FuzzMetadata {
+pub(crate) fn instruction_metadata() -> FuzzMetadata {
     FuzzMetadata {
         // Mollusk is always an instruction harness.
         entrypoint: String::from("sol_compat_instr_execute_v1"),
     }
 }
 
+/// Build a fixture from an instruction, the accounts before it, and the
+/// result of processing it.
+///
+/// Returns the transaction error if the result was rejected with one, since
+/// fixture effects can't record it.
 pub fn build_fixture_from_mollusk_test(
     mollusk: &Mollusk,
     instruction: &Instruction,
     accounts: &[(Pubkey, Account)],
     result: &InstructionResult,
-) -> FuzzFixture {
+) -> Result<FuzzFixture, TransactionError> {
     let input = build_fixture_context(
         accounts,
         &mollusk.compute_budget,
@@ -260,12 +282,12 @@ pub fn build_fixture_from_mollusk_test(
     );
     // This should probably be built from the checks, but there's currently no
     // mechanism to enforce full check coverage on a result.
-    let output = build_fixture_effects(&input, result);
-    FuzzFixture {
+    let output = build_fixture_effects(&input, result)?;
+    Ok(FuzzFixture {
         metadata: Some(instruction_metadata()),
         input,
         output,
-    }
+    })
 }
 
 pub fn load_firedancer_fixture(
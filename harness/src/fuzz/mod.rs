@@ -1,14 +1,30 @@
+#[cfg(all(feature = "fuzz", feature = "fuzz-fd"))]
+pub mod convert;
 #[cfg(feature = "fuzz-fd")]
 pub mod firedancer;
 #[cfg(feature = "fuzz")]
 pub mod mollusk;
+#[cfg(feature = "fuzz")]
+pub mod target;
 
 use {
     crate::{result::InstructionResult, Mollusk},
     mollusk_svm_fuzz_fs::FsHandler,
-    solana_sdk::{account::Account, instruction::Instruction, pubkey::Pubkey},
+    solana_sdk::{
+        account::Account, instruction::Instruction, pubkey::Pubkey, transaction::TransactionError,
+    },
 };
 
+/// Fixture effects only record instruction errors, so a result rejected by
+/// post-execution checks, such as rent state transitions, can't be ejected.
+fn skip_ejection(err: &TransactionError) {
+    eprintln!(
+        "Skipping fixture ejection: the transaction error `{:?}` can't be recorded in fixture \
+         effects",
+        err
+    );
+}
+
 pub fn generate_fixtures_from_mollusk_test(
     mollusk: &Mollusk,
     instruction: &Instruction,
@@ -20,8 +36,18 @@ pub fn generate_fixtures_from_mollusk_test(
         if std::env::var("EJECT_FUZZ_FIXTURES").is_ok()
             || std::env::var("EJECT_FUZZ_FIXTURES_JSON").is_ok()
         {
-            let fixture =
-                mollusk::build_fixture_from_mollusk_test(mollusk, instruction, accounts, result);
+            let mut fixture = match mollusk::build_fixture_from_mollusk_test(
+                mollusk,
+                instruction,
+                accounts,
+                result,
+            ) {
+                Ok(fixture) => fixture,
+                Err(err) => return skip_ejection(&err),
+            };
+            if std::env::var("EJECT_FUZZ_FIXTURES_PROGRAMS").is_ok() {
+                fixture.programs = mollusk::build_fixture_programs(mollusk);
+            }
             let handler = FsHandler::new(fixture);
             if let Ok(blob_dir) = std::env::var("EJECT_FUZZ_FIXTURES") {
                 handler.dump_to_blob_file(&blob_dir);
@@ -37,8 +63,15 @@ pub fn generate_fixtures_from_mollusk_test(
         if std::env::var("EJECT_FUZZ_FIXTURES_FD").is_ok()
             || std::env::var("EJECT_FUZZ_FIXTURES_JSON_FD").is_ok()
         {
-            let fixture =
-                firedancer::build_fixture_from_mollusk_test(mollusk, instruction, accounts, result);
+            let fixture = match firedancer::build_fixture_from_mollusk_test(
+                mollusk,
+                instruction,
+                accounts,
+                result,
+            ) {
+                Ok(fixture) => fixture,
+                Err(err) => return skip_ejection(&err),
+            };
             let handler = FsHandler::new(fixture);
             if let Ok(blob_dir) = std::env::var("EJECT_FUZZ_FIXTURES_FD") {
                 handler.dump_to_blob_file(&blob_dir);
@@ -50,3 +83,47 @@ pub fn generate_fixtures_from_mollusk_test(
         }
     }
 }
+
+/// Eject a chain of instructions as a single chain fixture, preserving the
+/// order of its instructions and the effects of each one.
+///
+/// Chain fixtures are written when the `EJECT_FUZZ_CHAIN_FIXTURES` or
+/// `EJECT_FUZZ_CHAIN_FIXTURES_JSON` environment variables are set. They only
+/// exist in Mollusk's protobuf layouts. Like instruction fixtures, they embed
+/// the ELFs of the cached programs when `EJECT_FUZZ_FIXTURES_PROGRAMS` is
+/// set.
+///
+/// Chains rejected by a transaction error, such as an invalid rent state
+/// transition, aren't ejected.
+#[cfg(feature = "fuzz")]
+pub fn generate_chain_fixture_from_mollusk_test(
+    mollusk: &Mollusk,
+    instructions: &[Instruction],
+    accounts: &[(Pubkey, Account)],
+    results: &[InstructionResult],
+) {
+    if std::env::var("EJECT_FUZZ_CHAIN_FIXTURES").is_ok()
+        || std::env::var("EJECT_FUZZ_CHAIN_FIXTURES_JSON").is_ok()
+    {
+        let mut fixture = match mollusk::build_chain_fixture_from_mollusk_test(
+            mollusk,
+            instructions,
+            accounts,
+            results,
+        ) {
+            Ok(fixture) => fixture,
+            Err(err) => return skip_ejection(&err),
+        };
+        if std::env::var("EJECT_FUZZ_FIXTURES_PROGRAMS").is_ok() {
+            fixture.programs = mollusk::build_fixture_programs(mollusk);
+        }
+        let handler = FsHandler::new(fixture);
+        if let Ok(blob_dir) = std::env::var("EJECT_FUZZ_CHAIN_FIXTURES") {
+            handler.dump_to_blob_file(&blob_dir);
+        }
+
+        if let Ok(json_dir) = std::env::var("EJECT_FUZZ_CHAIN_FIXTURES_JSON") {
+            handler.dump_to_json_file(&json_dir);
+        }
+    }
+}
@@ -6,13 +6,19 @@
 
 use {
     crate::{
-        result::{InstructionResult, ProgramResult},
+        result::{InnerInstruction, InstructionResult, ProgramResult},
         sysvar::Sysvars,
         Mollusk,
     },
     mollusk_svm_fuzz_fixture::{
-        context::Context as FuzzContext, effects::Effects as FuzzEffects,
-        sysvars::Sysvars as FuzzSysvars, Fixture as FuzzFixture,
+        chain::{ChainContext as FuzzChainContext, ChainFixture as FuzzChainFixture},
+        context::Context as FuzzContext,
+        effects::{
+            Effects as FuzzEffects, InnerInstruction as FuzzInnerInstruction, EFFECTS_VERSION,
+        },
+        program::ProgramElf as FuzzProgramElf,
+        sysvars::Sysvars as FuzzSysvars,
+        Fixture as FuzzFixture,
     },
     solana_compute_budget::compute_budget::ComputeBudget,
     solana_sdk::{
@@ -22,6 +28,7 @@ use {
         pubkey::Pubkey,
         slot_hashes::SlotHashes,
         sysvar::last_restart_slot::LastRestartSlot,
+        transaction::TransactionError,
     },
 };
 
@@ -54,8 +61,13 @@ impl From<&FuzzSysvars> for Sysvars {
     }
 }
 
-impl From<&InstructionResult> for FuzzEffects {
-    fn from(input: &InstructionResult) -> Self {
+/// Fixture effects only record instruction errors, so a result rejected with
+/// a transaction error, such as an invalid rent state transition, can't be
+/// converted.
+impl TryFrom<&InstructionResult> for FuzzEffects {
+    type Error = TransactionError;
+
+    fn try_from(input: &InstructionResult) -> Result<Self, Self::Error> {
         let compute_units_consumed = input.compute_units_consumed;
         let execution_time = input.execution_time;
         let return_data = input.return_data.clone();
@@ -64,17 +76,31 @@ impl From<&InstructionResult> for FuzzEffects {
             ProgramResult::Success => 0,
             ProgramResult::Failure(e) => u64::from(e.clone()),
             ProgramResult::UnknownError(_) => u64::MAX, //TODO
+            ProgramResult::TransactionError(err) => return Err(err.clone()),
         };
 
         let resulting_accounts = input.resulting_accounts.clone();
 
-        Self {
+        let inner_instructions = input
+            .inner_instructions
+            .iter()
+            .map(|inner| FuzzInnerInstruction {
+                instruction: inner.instruction.clone(),
+                stack_height: inner.stack_height,
+            })
+            .collect();
+
+        Ok(Self {
             compute_units_consumed,
             execution_time,
             program_result,
             return_data,
             resulting_accounts,
-        }
+            version: EFFECTS_VERSION,
+            logs: input.logs.clone(),
+            inner_instructions,
+            return_data_program_id: input.return_data_program_id,
+        })
     }
 }
 
@@ -94,12 +120,26 @@ impl From<&FuzzEffects> for InstructionResult {
 
         let resulting_accounts = input.resulting_accounts.clone();
 
+        let inner_instructions = input
+            .inner_instructions
+            .iter()
+            .map(|inner| InnerInstruction {
+                instruction: inner.instruction.clone(),
+                stack_height: inner.stack_height,
+            })
+            .collect();
+
         Self {
             compute_units_consumed,
+            invariant_violations: vec![],
+            error_location: None,
             execution_time,
             program_result,
             raw_result,
             return_data,
+            return_data_program_id: input.return_data_program_id,
+            logs: input.logs.clone(),
+            inner_instructions,
             resulting_accounts,
         }
     }
@@ -113,7 +153,7 @@ pub struct ParsedFixtureContext {
     pub sysvars: Sysvars,
 }
 
-fn build_fixture_context(
+pub(crate) fn build_fixture_context(
     accounts: &[(Pubkey, Account)],
     compute_budget: &ComputeBudget,
     feature_set: &FeatureSet,
@@ -158,12 +198,17 @@ pub(crate) fn parse_fixture_context(context: &FuzzContext) -> ParsedFixtureConte
     }
 }
 
+/// Build a fixture from an instruction, the accounts before it, and the
+/// result of processing it.
+///
+/// Returns the transaction error if the result was rejected with one, since
+/// fixture effects can't record it.
 pub fn build_fixture_from_mollusk_test(
     mollusk: &Mollusk,
     instruction: &Instruction,
     accounts: &[(Pubkey, Account)],
     result: &InstructionResult,
-) -> FuzzFixture {
+) -> Result<FuzzFixture, TransactionError> {
     let input = build_fixture_context(
         accounts,
         &mollusk.compute_budget,
@@ -173,8 +218,12 @@ pub fn build_fixture_from_mollusk_test(
     );
     // This should probably be built from the checks, but there's currently no
     // mechanism to enforce full check coverage on a result.
-    let output = FuzzEffects::from(result);
-    FuzzFixture { input, output }
+    let output = FuzzEffects::try_from(result)?;
+    Ok(FuzzFixture {
+        input,
+        output,
+        programs: vec![],
+    })
 }
 
 pub fn load_fixture(
@@ -185,3 +234,135 @@ pub fn load_fixture(
         InstructionResult::from(&fixture.output),
     )
 }
+
+pub struct ParsedChainFixtureContext {
+    pub accounts: Vec<(Pubkey, Account)>,
+    pub compute_budget: ComputeBudget,
+    pub feature_set: FeatureSet,
+    pub instructions: Vec<Instruction>,
+    pub sysvars: Sysvars,
+}
+
+pub(crate) fn parse_chain_fixture_context(context: &FuzzChainContext) -> ParsedChainFixtureContext {
+    let FuzzChainContext {
+        compute_budget,
+        feature_set,
+        sysvars,
+        instructions,
+        accounts,
+    } = context;
+
+    ParsedChainFixtureContext {
+        accounts: accounts.clone(),
+        compute_budget: *compute_budget,
+        feature_set: feature_set.clone(),
+        instructions: instructions.clone(),
+        sysvars: sysvars.into(),
+    }
+}
+
+/// Build a chain fixture from the instructions of a chain, the accounts
+/// before the first instruction, and the result of each processed
+/// instruction.
+///
+/// Returns the transaction error if any result was rejected with one, like
+/// `build_fixture_from_mollusk_test`.
+pub fn build_chain_fixture_from_mollusk_test(
+    mollusk: &Mollusk,
+    instructions: &[Instruction],
+    accounts: &[(Pubkey, Account)],
+    results: &[InstructionResult],
+) -> Result<FuzzChainFixture, TransactionError> {
+    let input = FuzzChainContext {
+        compute_budget: mollusk.compute_budget,
+        feature_set: mollusk.feature_set.clone(),
+        sysvars: (&mollusk.sysvars).into(),
+        instructions: instructions.to_vec(),
+        accounts: accounts.to_vec(),
+    };
+    let output = results
+        .iter()
+        .map(FuzzEffects::try_from)
+        .collect::<Result<_, _>>()?;
+    Ok(FuzzChainFixture {
+        input,
+        output,
+        programs: vec![],
+    })
+}
+
+pub fn load_chain_fixture(
+    fixture: &FuzzChainFixture,
+) -> (ParsedChainFixtureContext, Vec<InstructionResult>) {
+    (
+        parse_chain_fixture_context(&fixture.input),
+        fixture.output.iter().map(InstructionResult::from).collect(),
+    )
+}
+
+/// Build the programs section of a fixture from the ELFs of every program
+/// in Mollusk's program cache, so the fixture can be replayed on its own.
+///
+/// Builtins and natively executed programs have no ELF, so they aren't
+/// included.
+pub fn build_fixture_programs(mollusk: &Mollusk) -> Vec<FuzzProgramElf> {
+    mollusk
+        .program_cache
+        .program_elfs()
+        .map(|(program_id, loader_key, elf)| FuzzProgramElf {
+            program_id: *program_id,
+            loader_key: *loader_key,
+            elf: elf.to_vec(),
+        })
+        .collect()
+}
+
+/// Rebuild the programs embedded in a fixture from the ELFs in Mollusk's
+/// program cache, ie. after blessing the fixture with a new version of the
+/// program under test.
+///
+/// Panics if the cache holds no ELF for one of the programs, ie. when ELF
+/// retention is disabled.
+pub(crate) fn rebuild_fixture_programs(
+    mollusk: &Mollusk,
+    programs: &[FuzzProgramElf],
+) -> Vec<FuzzProgramElf> {
+    let rebuilt = build_fixture_programs(mollusk);
+    programs
+        .iter()
+        .map(|program| {
+            rebuilt
+                .iter()
+                .find(|rebuilt| rebuilt.program_id == program.program_id)
+                .cloned()
+                .unwrap_or_else(|| {
+                    panic!(
+                        "No ELF retained for embedded program {}. Enable \
+                         `ProgramCache::set_elf_retention` before adding programs.",
+                        program.program_id
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Add the programs embedded in a fixture to Mollusk's program cache.
+///
+/// Programs already in the cache are left as is, so programs provided by
+/// the developer, ie. a new version of the program under test, take
+/// precedence over the embedded ones.
+pub(crate) fn load_fixture_programs(mollusk: &mut Mollusk, programs: &[FuzzProgramElf]) {
+    for program in programs {
+        if mollusk
+            .program_cache
+            .load_program(&program.program_id)
+            .is_none()
+        {
+            mollusk.add_program_with_elf_and_loader(
+                &program.program_id,
+                &program.elf,
+                &program.loader_key,
+            );
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Module for coverage-guided fuzzing of programs with libFuzzer, ie. through
+//! `cargo fuzz`.
+//!
+//! A `FuzzTarget` decodes each fuzzer input as a Mollusk instruction fixture
+//! (the protobuf blob of a `.fix` file) and processes its instruction against
+//! a program. Since inputs are fixture blobs, fixtures ejected from tests with
+//! `EJECT_FUZZ_FIXTURES` can seed the fuzzer's corpus, for libFuzzer to
+//! mutate.
+//!
+//! Only the instruction data, instruction accounts and input accounts of a
+//! fixture are used. The program ID is replaced with the target's program,
+//! and the compute budget, feature set and sysvars are those of the target's
+//! `Mollusk` instance, so inputs can't configure an environment the runtime
+//! doesn't support.
+//!
+//! The following are reported as findings, by panicking:
+//!
+//! * Panics within Mollusk or a natively executed program.
+//! * Violations of invariants registered on the `Mollusk` instance.
+//! * SBF programs failing to complete, ie. panicking or faulting, rather than
+//!   returning an error.
+//!
+//! For SBF programs executed with instruction tracing (see
+//! `ProgramCache::set_instruction_tracing`), the edges between executed
+//! program counters are recorded in libFuzzer's extra counters, guiding the
+//! fuzzer towards new paths through the program. Natively executed programs
+//! are instrumented by `cargo fuzz` itself.
+//!
+//! ```rust,ignore
+//! #![no_main]
+//!
+//! use {libfuzzer_sys::fuzz_target, mollusk_svm::fuzz::target::FuzzTarget};
+//!
+//! thread_local! {
+//!     static TARGET: FuzzTarget = FuzzTarget::new(&PROGRAM_ID, include_bytes!("my_program.so"));
+//! }
+//!
+//! fuzz_target!(|data: &[u8]| TARGET.with(|target| target.fuzz(data)));
+//! ```
+//!
+//! ```ignore
+//! cargo fuzz run my_target ./fuzz-fixtures
+//! ```
+
+use {
+    crate::{
+        invariant::InvariantViolation, location::ErrorLocation, result::InstructionResult, Mollusk,
+        DEFAULT_LOADER_KEY,
+    },
+    mollusk_svm_fuzz_fixture::{context::Context, proto::InstrFixture as ProtoFixture},
+    mollusk_svm_fuzz_fs::SerializableFixture,
+    solana_sdk::{
+        account::Account,
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+    },
+    std::{
+        fmt,
+        sync::atomic::{AtomicU8, Ordering},
+    },
+};
+
+/// The maximum number of accounts in a fuzzer input, matching the number of
+/// accounts a transaction may lock.
+const MAX_ACCOUNTS: usize = 64;
+/// The number of counters SBF edges are hashed into.
+const COVERAGE_COUNTERS: usize = 1 << 16;
+
+/// SBF edge coverage. On Linux, libFuzzer picks up counters in this section
+/// alongside the coverage instrumented by the compiler.
+#[cfg_attr(target_os = "linux", link_section = "__libfuzzer_extra_counters")]
+#[used]
+static SBF_COVERAGE: [AtomicU8; COVERAGE_COUNTERS] =
+    [const { AtomicU8::new(0) }; COVERAGE_COUNTERS];
+
+fn record_edge(previous_pc: u64, pc: u64) {
+    let hash = ((previous_pc >> 1) ^ pc).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    let index = (hash >> 48) as usize;
+    // Saturate, since libFuzzer treats a zero counter as uncovered.
+    let _ = SBF_COVERAGE[index].fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+        count.checked_add(1)
+    });
+}
+
+/// The number of distinct SBF edges covered by every target so far.
+pub fn covered_edges() -> usize {
+    SBF_COVERAGE
+        .iter()
+        .filter(|counter| counter.load(Ordering::Relaxed) > 0)
+        .count()
+}
+
+/// A finding reported by a fuzz target.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Finding {
+    /// Invariants registered on the `Mollusk` instance were violated.
+    InvariantViolations(Vec<InvariantViolation>),
+    /// An SBF program failed to complete, ie. panicked or faulted.
+    ProgramFailure(Option<ErrorLocation>),
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvariantViolations(violations) => {
+                write!(f, "INVARIANTS VIOLATED:")?;
+                for violation in violations {
+                    write!(f, "\n  {}: {}", violation.invariant, violation.message)?;
+                }
+                Ok(())
+            }
+            Self::ProgramFailure(Some(location)) => write!(f, "PROGRAM FAILED: {}", location),
+            Self::ProgramFailure(None) => write!(f, "PROGRAM FAILED"),
+        }
+    }
+}
+
+/// A libFuzzer-compatible target, processing fuzzer inputs against a
+/// program.
+pub struct FuzzTarget {
+    /// The Mollusk instance inputs are processed with.
+    pub mollusk: Mollusk,
+    /// The program inputs are processed against.
+    pub program_id: Pubkey,
+    /// Whether SBF programs failing to complete, ie. panicking or faulting,
+    /// are findings. Enabled by default.
+    pub program_failures_are_findings: bool,
+}
+
+impl FuzzTarget {
+    /// Create a target for a program from its ELF. The program is executed
+    /// with instruction tracing, so SBF coverage guides the fuzzer.
+    pub fn new(program_id: &Pubkey, elf: &[u8]) -> Self {
+        let mut mollusk = Mollusk::default();
+        mollusk.program_cache.set_instruction_tracing(true);
+        mollusk.add_program_with_elf_and_loader(program_id, elf, &DEFAULT_LOADER_KEY);
+        Self::from_mollusk(mollusk, program_id)
+    }
+
+    /// Create a target for a program already in a Mollusk instance's program
+    /// cache.
+    pub fn from_mollusk(mollusk: Mollusk, program_id: &Pubkey) -> Self {
+        Self {
+            mollusk,
+            program_id: *program_id,
+            program_failures_are_findings: true,
+        }
+    }
+
+    /// Decode a fuzzer input into an instruction for the target's program and
+    /// its input accounts. Returns `None` if the input isn't a valid fixture.
+    pub fn decode(&self, data: &[u8]) -> Option<(Instruction, Vec<(Pubkey, Account)>)> {
+        let mut context = ProtoFixture::try_decode(data).ok()?.input?;
+
+        let is_pubkey = |bytes: &[u8]| bytes.len() == 32;
+        if context.accounts.len() > MAX_ACCOUNTS
+            || !context
+                .accounts
+                .iter()
+                .all(|account| is_pubkey(&account.address) && is_pubkey(&account.owner))
+            || context
+                .instr_accounts
+                .iter()
+                .any(|meta| meta.index as usize >= context.accounts.len())
+        {
+            return None;
+        }
+
+        // Use the target's program and environment.
+        context.program_id = self.program_id.to_bytes().to_vec();
+        context.compute_budget = None;
+        context.feature_set = None;
+        context.sysvars = None;
+
+        let Context {
+            program_id,
+            instruction_accounts,
+            instruction_data,
+            accounts,
+            ..
+        } = context.into();
+        Some((
+            Instruction::new_with_bytes(program_id, &instruction_data, instruction_accounts),
+            accounts,
+        ))
+    }
+
+    /// Process a fuzzer input, recording SBF coverage. Returns `None` if the
+    /// input isn't a valid fixture.
+    pub fn execute(&self, data: &[u8]) -> Option<InstructionResult> {
+        let (instruction, accounts) = self.decode(data)?;
+        let mut previous_pc = u64::MAX;
+        let mut coverage = |pc| {
+            record_edge(previous_pc, pc);
+            previous_pc = pc;
+        };
+        Some(self.mollusk.process_instruction_with_coverage(
+            &instruction,
+            &accounts,
+            Some(&mut coverage),
+        ))
+    }
+
+    /// Evaluate the result of an input, returning a finding if any.
+    pub fn finding(&self, result: &InstructionResult) -> Option<Finding> {
+        if !result.invariant_violations.is_empty() {
+            return Some(Finding::InvariantViolations(
+                result.invariant_violations.clone(),
+            ));
+        }
+        if self.program_failures_are_findings
+            && result.raw_result == Err(InstructionError::ProgramFailedToComplete)
+        {
+            return Some(Finding::ProgramFailure(result.error_location.clone()));
+        }
+        None
+    }
+
+    /// The fuzzing entrypoint. Processes an input, panicking on any finding.
+    pub fn fuzz(&self, data: &[u8]) {
+        if let Some(result) = self.execute(data) {
+            if let Some(finding) = self.finding(&result) {
+                panic!("{}", finding);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{fuzz::mollusk::build_fixture_from_mollusk_test, invariant::Invariant},
+        solana_sdk::{system_instruction, system_program},
+    };
+
+    fn transfer_fixture(mollusk: &Mollusk, sender: Pubkey, recipient: Pubkey) -> Vec<u8> {
+        let instruction = system_instruction::transfer(&sender, &recipient, 42_000);
+        let accounts = [
+            (sender, Account::new(100_000_000, 0, &system_program::id())),
+            (
+                recipient,
+                Account::new(100_000_000, 0, &system_program::id()),
+            ),
+        ];
+        let result = mollusk.process_instruction(&instruction, &accounts);
+        let fixture =
+            build_fixture_from_mollusk_test(mollusk, &instruction, &accounts, &result).unwrap();
+        SerializableFixture::encode(&ProtoFixture::from(fixture))
+    }
+
+    #[test]
+    fn test_fuzz_target() {
+        let sender = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let mut target = FuzzTarget::from_mollusk(Mollusk::default(), &system_program::id());
+        let data = transfer_fixture(&target.mollusk, sender, recipient);
+
+        // Fixtures decode into an instruction for the target's program.
+        let (instruction, accounts) = target.decode(&data).unwrap();
+        assert_eq!(instruction.program_id, system_program::id());
+        assert_eq!(instruction.accounts.len(), 2);
+        assert_eq!(accounts.len(), 2);
+
+        let result = target.execute(&data).unwrap();
+        assert!(!result.program_result.is_err());
+        assert_eq!(target.finding(&result), None);
+        target.fuzz(&data);
+
+        // Invalid inputs are rejected.
+        assert!(target.execute(&[0xff; 16]).is_none());
+        assert!(target.execute(&data[..data.len() / 2]).is_none());
+
+        // Invariant violations are findings.
+        target.mollusk.add_invariant(Invariant::custom(
+            "sender_keeps_lamports",
+            move |ctx| match ctx.result.get_account(&sender) {
+                Some(account) if account.lamports == 100_000_000 => Ok(()),
+                _ => Err("sender lost lamports".to_string()),
+            },
+        ));
+        let result = target.execute(&data).unwrap();
+        assert_eq!(
+            target.finding(&result),
+            Some(Finding::InvariantViolations(vec![InvariantViolation {
+                invariant: "sender_keeps_lamports".to_string(),
+                message: "sender lost lamports".to_string(),
+            }])),
+        );
+        assert!(
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| target.fuzz(&data))).is_err()
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! Module for invariants checked after every instruction.
+//!
+//! Invariants registered on a `Mollusk` instance are evaluated after each
+//! processed instruction, including each step of an instruction chain.
+//! Violations are recorded on the `InstructionResult`, and fail any checks
+//! subsequently run against it.
+//!
+//! ```rust,ignore
+//! let mut mollusk = Mollusk::new(&program_id, "my_program");
+//! mollusk.add_invariants(Invariant::all());
+//! mollusk.add_invariant(Invariant::custom("vault_never_empty", |ctx| {
+//!     match ctx.result.get_account(&vault) {
+//!         Some(vault) if vault.lamports > 0 => Ok(()),
+//!         _ => Err("vault was emptied".to_string()),
+//!     }
+//! }));
+//! ```
+
+use {
+    crate::result::InstructionResult,
+    solana_sdk::{
+        account::{Account, ReadableAccount},
+        instruction::Instruction,
+        pubkey::Pubkey,
+        rent::Rent,
+        system_program,
+    },
+};
+
+/// The context an invariant is evaluated against.
+pub struct InvariantContext<'a> {
+    /// The processed instruction.
+    pub instruction: &'a Instruction,
+    /// The accounts provided as inputs to the instruction.
+    pub accounts: &'a [(Pubkey, Account)],
+    /// The result of the instruction.
+    pub result: &'a InstructionResult,
+    /// The rent sysvar the instruction was processed with.
+    pub rent: &'a Rent,
+}
+
+impl InvariantContext<'_> {
+    /// Iterate over each input account alongside its resulting state.
+    fn changes(&self) -> impl Iterator<Item = (&Pubkey, &Account, &Account)> {
+        self.accounts.iter().filter_map(|(pubkey, input)| {
+            self.result
+                .get_account(pubkey)
+                .map(|resulting| (pubkey, input, resulting))
+        })
+    }
+}
+
+type CustomInvariant = Box<dyn Fn(&InvariantContext) -> Result<(), String>>;
+
+/// An invariant which must hold after every instruction.
+pub enum Invariant {
+    /// The total lamports across all accounts is unchanged.
+    LamportConservation,
+    /// Accounts not marked writable by the instruction are unchanged.
+    ReadonlyUnchanged,
+    /// Every modified account which holds lamports is rent-exempt.
+    RentExempt,
+    /// Only accounts owned by the instruction's program have their data
+    /// changed, aside from newly created system accounts.
+    ///
+    /// Note: Data changed by programs invoked through CPI, other than the
+    /// System program, violates this invariant.
+    OwnerOnlyWrites,
+    /// A custom invariant, returning an error message when violated.
+    Custom {
+        name: String,
+        check: CustomInvariant,
+    },
+}
+
+impl Invariant {
+    /// All built-in invariants, except `OwnerOnlyWrites`, which programs
+    /// writing to accounts through CPI violate. Add it on its own to opt in.
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::LamportConservation,
+            Self::ReadonlyUnchanged,
+            Self::RentExempt,
+        ]
+    }
+
+    /// A custom invariant, returning an error message when violated.
+    pub fn custom(
+        name: &str,
+        check: impl Fn(&InvariantContext) -> Result<(), String> + 'static,
+    ) -> Self {
+        Self::Custom {
+            name: name.to_string(),
+            check: Box::new(check),
+        }
+    }
+
+    /// The name of the invariant.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::LamportConservation => "lamport_conservation",
+            Self::ReadonlyUnchanged => "readonly_unchanged",
+            Self::RentExempt => "rent_exempt",
+            Self::OwnerOnlyWrites => "owner_only_writes",
+            Self::Custom { name, .. } => name,
+        }
+    }
+
+    /// Evaluate the invariant.
+    pub fn check(&self, ctx: &InvariantContext) -> Result<(), String> {
+        match self {
+            Self::LamportConservation => {
+                let sum = |accounts: &[(Pubkey, Account)]| {
+                    accounts
+                        .iter()
+                        .map(|(_, account)| account.lamports() as u128)
+                        .sum::<u128>()
+                };
+                let before = sum(ctx.accounts);
+                let after = sum(&ctx.result.resulting_accounts);
+                if before != after {
+                    return Err(format!(
+                        "Total lamports changed from {} to {}",
+                        before, after
+                    ));
+                }
+            }
+            Self::ReadonlyUnchanged => {
+                for (pubkey, input, resulting) in ctx.changes() {
+                    let writable = ctx
+                        .instruction
+                        .accounts
+                        .iter()
+                        .any(|meta| meta.pubkey == *pubkey && meta.is_writable);
+                    if !writable && input != resulting {
+                        return Err(format!("Read-only account {} was modified", pubkey));
+                    }
+                }
+            }
+            Self::RentExempt => {
+                for (pubkey, input, resulting) in ctx.changes() {
+                    if input != resulting
+                        && resulting.lamports() > 0
+                        && !ctx
+                            .rent
+                            .is_exempt(resulting.lamports(), resulting.data().len())
+                    {
+                        return Err(format!(
+                            "Account {} is not rent-exempt: {} lamports for {} bytes, minimum \
+                             balance is {}",
+                            pubkey,
+                            resulting.lamports(),
+                            resulting.data().len(),
+                            ctx.rent.minimum_balance(resulting.data().len()),
+                        ));
+                    }
+                }
+            }
+            Self::OwnerOnlyWrites => {
+                for (pubkey, input, resulting) in ctx.changes() {
+                    let created =
+                        input.data().is_empty() && system_program::check_id(input.owner());
+                    if input.data() != resulting.data()
+                        && input.owner() != &ctx.instruction.program_id
+                        && !created
+                    {
+                        return Err(format!(
+                            "Data of account {} owned by {} was changed by {}",
+                            pubkey,
+                            input.owner(),
+                            ctx.instruction.program_id,
+                        ));
+                    }
+                }
+            }
+            Self::Custom { check, .. } => check(ctx)?,
+        }
+        Ok(())
+    }
+}
+
+/// A violated invariant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct InvariantViolation {
+    /// The name of the violated invariant.
+    pub invariant: String,
+    /// A description of the violation.
+    pub message: String,
+}
+
+/// Evaluate every invariant, returning the violations.
+pub(crate) fn check_all(
+    invariants: &[Invariant],
+    ctx: &InvariantContext,
+) -> Vec<InvariantViolation> {
+    invariants
+        .iter()
+        .filter_map(|invariant| {
+            invariant
+                .check(ctx)
+                .err()
+                .map(|message| InvariantViolation {
+                    invariant: invariant.name().to_string(),
+                    message,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::instruction::AccountMeta};
+
+    fn violations(
+        invariants: &[Invariant],
+        instruction: &Instruction,
+        accounts: &[(Pubkey, Account)],
+        resulting_accounts: Vec<(Pubkey, Account)>,
+    ) -> Vec<String> {
+        let result = InstructionResult {
+            resulting_accounts,
+            ..Default::default()
+        };
+        let ctx = InvariantContext {
+            instruction,
+            accounts,
+            result: &result,
+            rent: &Rent::default(),
+        };
+        check_all(invariants, &ctx)
+            .into_iter()
+            .map(|violation| violation.invariant)
+            .collect()
+    }
+
+    #[test]
+    fn test_invariants() {
+        let program_id = Pubkey::new_unique();
+        let owned = Pubkey::new_unique();
+        let foreign = Pubkey::new_unique();
+        let readonly = Pubkey::new_unique();
+
+        let rent = Rent::default();
+        let exempt = rent.minimum_balance(8);
+        let accounts = vec![
+            (owned, Account::new(exempt, 8, &program_id)),
+            (foreign, Account::new(exempt, 8, &Pubkey::new_unique())),
+            (readonly, Account::new(exempt, 8, &program_id)),
+        ];
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![
+                AccountMeta::new(owned, false),
+                AccountMeta::new(foreign, false),
+                AccountMeta::new_readonly(readonly, false),
+            ],
+        );
+        let mut invariants = Invariant::all();
+        invariants.push(Invariant::OwnerOnlyWrites);
+
+        // No changes.
+        assert!(violations(&invariants, &instruction, &accounts, accounts.clone()).is_empty());
+
+        // Writing to an owned account.
+        let mut resulting = accounts.clone();
+        resulting[0].1.data[0] = 1;
+        assert!(violations(&invariants, &instruction, &accounts, resulting).is_empty());
+
+        // Writing to a foreign account, which only violates the opt-in
+        // invariant.
+        let mut resulting = accounts.clone();
+        resulting[1].1.data[0] = 1;
+        assert_eq!(
+            violations(&invariants, &instruction, &accounts, resulting.clone()),
+            vec!["owner_only_writes"],
+        );
+        assert!(violations(&Invariant::all(), &instruction, &accounts, resulting).is_empty());
+
+        // Writing to a read-only account.
+        let mut resulting = accounts.clone();
+        resulting[2].1.data[0] = 1;
+        assert_eq!(
+            violations(&invariants, &instruction, &accounts, resulting),
+            vec!["readonly_unchanged"],
+        );
+
+        // Minting lamports, leaving an account below the rent-exempt minimum.
+        let mut resulting = accounts.clone();
+        resulting[0].1.data.push(0);
+        resulting[0].1.lamports += 1;
+        assert_eq!(
+            violations(&invariants, &instruction, &accounts, resulting),
+            vec!["lamport_conservation", "rent_exempt"],
+        );
+    }
+}
@@ -276,6 +276,98 @@
 //! Developers should recognize that instruction chains are primarily used for
 //! testing program execution.
 //!
+//! ## Invariants
+//!
+//! Invariants registered on `Mollusk` are checked after every processed
+//! instruction, including each step of an instruction chain. Built-in
+//! invariants cover lamport conservation, read-only accounts, rent-exemption
+//! of modified accounts, and writes to accounts not owned by the program.
+//! `Invariant::all()` leaves out the last, `Invariant::OwnerOnlyWrites`, since
+//! programs writing to accounts through CPI violate it, so it must be added on
+//! its own. Custom invariants are closures. Violations are recorded in
+//! `InstructionResult::invariant_violations` and fail any checks run against
+//! the result.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::invariant::Invariant;
+//!
+//! let mut mollusk = Mollusk::new(&program_id, "my_program");
+//! mollusk.add_invariants(Invariant::all());
+//! mollusk.add_invariant(Invariant::custom("supply_capped", |ctx| {
+//!     // Inspect `ctx.instruction`, `ctx.accounts` and `ctx.result`.
+//!     Ok(())
+//! }));
+//! ```
+//!
+//! ## Snapshots
+//!
+//! With the `serde` feature enabled, results can be compared against snapshots
+//! stored as readable JSON under `tests/snapshots`, rather than hand-written
+//! expected values. Snapshots are only written, or rewritten, when
+//! `MOLLUSK_UPDATE_SNAPSHOTS=1` is set, so a missing snapshot fails.
+//!
+//! ```rust,ignore
+//! mollusk
+//!     .process_instruction(&instruction, &accounts)
+//!     .assert_snapshot("my_instruction");
+//! ```
+//!
+//! ## Property Testing
+//!
+//! With the `proptest` feature enabled, the `proptest` module provides
+//! strategies for pubkeys, accounts (including rent-exempt accounts of a given
+//! size), account meta permutations, and instruction data built from user
+//! types. `Mollusk::test_property` processes generated instructions against a
+//! property and shrinks any failure to a minimal failing case. With the `fuzz`
+//! features also enabled, that case is ejected as a fixture using the same
+//! `EJECT_FUZZ_FIXTURES` environment variables as
+//! `process_and_validate_instruction`.
+//!
+//! ```rust,ignore
+//! use {mollusk_svm::proptest::ProptestConfig, proptest::prelude::*};
+//!
+//! let strategy = (0..=1_000_000u64).prop_map(|amount| {
+//!     let instruction = system_instruction::transfer(&sender, &recipient, amount);
+//!     (instruction, accounts.clone())
+//! });
+//!
+//! let failure = mollusk
+//!     .test_property(ProptestConfig::default(), strategy, |result, _, _| {
+//!         // Return `Err` with a reason when the property doesn't hold.
+//!         Ok(())
+//!     })
+//!     .unwrap_err();
+//! ```
+//!
+//! ## Debugging
+//!
+//! With the `debugger` feature enabled, Mollusk can serve a program to `gdb` or
+//! `lldb` over the GDB remote protocol. Whenever the program is invoked, it's
+//! executed in the interpreter and blocks until a debugger connects on the
+//! configured local TCP port.
+//!
+//! The program to debug can be set with `Mollusk::debug_program`, or through
+//! the `MOLLUSK_DEBUG_PROGRAM_ID` and `MOLLUSK_DEBUG_PORT` (default `9001`)
+//! environment variables.
+//!
+//! ```ignore
+//! MOLLUSK_DEBUG_PROGRAM_ID=<program_id> cargo test --features debugger -- my_test
+//! ```
+//!
+//! ## Native Execution
+//!
+//! Programs can also be executed natively, compiled for the host, by
+//! registering their `process_instruction` function with
+//! `Mollusk::add_native_program`. Accounts are serialized and deserialized
+//! exactly like the BPF loader does, so the same `InstructionResult` is
+//! returned, but the program can be stepped through with a native debugger or
+//! built with sanitizers. Compute units are not metered for native programs.
+//!
+//! ```rust,ignore
+//! let mut mollusk = Mollusk::default();
+//! mollusk.add_native_program(&program_id, my_program::process_instruction);
+//! ```
+//!
 //! ## Fixtures
 //!
 //! Mollusk also supports working with multiple kinds of fixtures, which can
@@ -372,21 +464,255 @@
 //!
 //! Fixtures can be loaded from files or decoded from raw blobs. These
 //! capabilities are provided by the respective fixture crates.
+//!
+//! Mollusk fixtures also record the program logs, the instructions invoked
+//! through CPI and the program that set the return data, which are validated
+//! with `Compare::Logs`, `Compare::InnerInstructions` and
+//! `Compare::ReturnDataProgramId`. `Compare::all_effects()` adds these to
+//! `Compare::everything()`, and is what validating and blessing Mollusk
+//! fixtures use, so replaying fixtures also catches changes in logging and CPI
+//! behavior across program versions. Effects are versioned: fixtures recorded
+//! before these fields existed skip those checks, as do Firedancer fixtures,
+//! which don't record them. Requesting one of these checks explicitly, ie.
+//! through `process_and_partially_validate_fixture` or a CLI config file, fails
+//! it instead.
+//!
+//! The CLI's `execute-fixture`, `replay`, `run-test` and `bundle run` commands
+//! print the checks which failed for each failing fixture. With `--json`, they
+//! instead print each fixture's report, listing every check along with its
+//! expected and actual values, as a line of JSON for CI tooling to aggregate.
+//!
+//! ```ignore
+//! mollusk execute-fixture ./program.so ./fixtures <PROGRAM_ID> --json
+//! ```
+//!
+//! ### Chain Fixtures
+//!
+//! `process_and_validate_instruction_chain` ejects one fixture per instruction,
+//! which loses the order of the chain. Setting `EJECT_FUZZ_CHAIN_FIXTURES` (or
+//! `EJECT_FUZZ_CHAIN_FIXTURES_JSON`) also captures the whole chain as a single
+//! chain fixture, holding the shared context, the ordered instructions and the
+//! effects of each processed instruction. Chain fixtures are only available in
+//! Mollusk's layout.
+//!
+//! ```ignore
+//! EJECT_FUZZ_CHAIN_FIXTURES="./chain-fixtures" cargo test-sbf ...
+//! ```
+//!
+//! `process_chain_fixture` replays a chain fixture, returning the result of
+//! each instruction, and `process_and_validate_chain_fixture` compares each
+//! result against its effects. The CLI executes chain fixtures with the
+//! `--chain` flag:
+//!
+//! ```ignore
+//! mollusk execute-fixture ./program.so ./chain-fixtures <PROGRAM_ID> --chain
+//! ```
+//!
+//! ### Self-Contained Fixtures
+//!
+//! Replaying a fixture requires the programs it invokes, including any invoked
+//! through CPI. Setting `EJECT_FUZZ_FIXTURES_PROGRAMS` alongside
+//! `EJECT_FUZZ_FIXTURES` (or `EJECT_FUZZ_CHAIN_FIXTURES`) embeds the ELF and
+//! loader of every program in the program cache into each ejected Mollusk
+//! fixture, at the cost of larger fixtures. The program cache only keeps a
+//! copy of each ELF while this is set, or once enabled with
+//! `ProgramCache::set_elf_retention`.
+//!
+//! ```ignore
+//! EJECT_FUZZ_FIXTURES="./fuzz-fixtures" EJECT_FUZZ_FIXTURES_PROGRAMS=1 cargo test-sbf ...
+//! ```
+//!
+//! `process_fixture` and `process_chain_fixture` load embedded programs
+//! missing from the program cache, so programs added by the developer, ie. a
+//! new version of the program under test, take precedence. The CLI replays
+//! self-contained fixtures without any ELF or program ID:
+//!
+//! ```ignore
+//! mollusk replay ./fuzz-fixtures
+//! ```
+//!
+//! ### Readable Fixtures
+//!
+//! JSON fixtures (`EJECT_FUZZ_FIXTURES_JSON`) and YAML fixtures
+//! (`FsHandler::dump_to_yaml_file`) are written in a readable form: addresses
+//! and hashes in base58, other bytes in hex, and features by address and name.
+//! Readable fixtures round-trip losslessly with their protobuf form, so
+//! regression fixtures can be written by hand. Missing fields take their
+//! protobuf defaults, or Mollusk's defaults for the compute budget and sysvars,
+//! and features can also be written as just their address. JSON fixtures
+//! written before this form, with bytes as arrays of numbers, are still read.
+//!
+//! ```ignore
+//! input:
+//!   program_id: MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr
+//!   data: 68656c6c6f
+//!   feature_set:
+//!     features:
+//!     - address: 6RvdSWHh8oh72Dp7wMTS2DBkf3fRPtChfNrAo3cZZoXJ
+//!       name: secp256k1_recover syscall
+//! output:
+//!   program_result: 0
+//! ```
+//!
+//! ```rust,ignore
+//! let fixture = Fixture::load_from_yaml_file("./regressions/hello.yaml");
+//! mollusk.process_and_partially_validate_fixture(&fixture, &[Compare::ProgramResult]);
+//! ```
+//!
+//! ### Inspecting Fixtures
+//!
+//! The CLI decodes a fixture in either layout and prints its context and
+//! effects as YAML, or JSON with `--json`. Addresses are shown in base58, data
+//! in hex (and as text, if printable), and features by name.
+//!
+//! ```ignore
+//! mollusk inspect ./fuzz-fixtures/instr-<HASH>.fix
+//! mollusk inspect ./fd-fixtures/instr-<HASH>.fix --proto firedancer --json
+//! ```
+//!
+//! ### Blessing Fixtures
+//!
+//! When a program change intentionally alters its behavior, the effects of
+//! every stored fixture go stale. `Mollusk::bless_fixture` (and
+//! `bless_chain_fixture` and `bless_firedancer_fixture`) re-executes a
+//! fixture's input and rewrites its effects with the result, returning a
+//! `CheckReport` whose failures are the effects which changed, or the
+//! transaction error if the input is now rejected with one. Embedded
+//! programs are rewritten with the ones in the program cache, which must retain
+//! their ELFs. The CLI blesses fixtures in place, printing what changed in each
+//! one, much like updating snapshots:
+//!
+//! ```ignore
+//! mollusk bless ./program.so ./fuzz-fixtures <PROGRAM_ID> --dry-run
+//! mollusk bless ./program.so ./fuzz-fixtures <PROGRAM_ID>
+//! ```
+//!
+//! ### Converting Fixtures
+//!
+//! With both the `fuzz` and `fuzz-fd` features enabled, the `fuzz::convert`
+//! module converts fixtures between the Mollusk and Firedancer layouts,
+//! keeping as much context as the target layout supports. Each conversion
+//! reports the fields the target layout can't represent, such as sysvars other
+//! than the slot in the Firedancer layout, or seed addresses in the Mollusk
+//! layout.
+//!
+//! ```rust,ignore
+//! use mollusk_svm::fuzz::convert::mollusk_to_firedancer;
+//!
+//! let conversion = mollusk_to_firedancer(&fixture);
+//! println!("Dropped: {:?}", conversion.dropped);
+//! ```
+//!
+//! The CLI converts a fixture, or a directory of them:
+//!
+//! ```ignore
+//! mollusk convert ./fuzz-fixtures --from mollusk --to firedancer -o ./fd-fixtures
+//! ```
+//!
+//! ### Fixture Bundles
+//!
+//! Large corpora make for many small `.fix` files. The `bundle` module of
+//! `mollusk-svm-fuzz-fs` packs fixtures into a single compressed bundle, with
+//! an index of each fixture's kind, protobuf layout, hash, program ID,
+//! instruction discriminator and result. `BundleWriter` and `BundleReader`
+//! write and read bundles one fixture at a time, and `BundleFilter` selects
+//! fixtures from the index without decoding them. Fixtures are only read as
+//! the kind and layout they were bundled as. The CLI creates, lists and runs
+//! bundles:
+//!
+//! ```ignore
+//! mollusk bundle create ./fuzz-fixtures -o ./corpus.bundle
+//! mollusk bundle list ./corpus.bundle --failed
+//! mollusk bundle run ./program.so ./corpus.bundle <PROGRAM_ID> --discriminator 02
+//! ```
+//!
+//! ### Curating Fixtures
+//!
+//! Ejected corpora grow with every test run. The CLI's `corpus` commands
+//! curate directories of fixtures: `dedupe` removes fixtures with the same
+//! hash, `stats` counts fixtures per program, result and instruction
+//! discriminator, `filter` copies fixtures matching a program, result,
+//! discriminator or account to another directory, and `split` divides
+//! fixtures into a training set and a regression set by hash, so each fixture
+//! stays in the same set as the corpus grows.
+//!
+//! ```ignore
+//! mollusk corpus dedupe ./fuzz-fixtures
+//! mollusk corpus stats ./fuzz-fixtures
+//! mollusk corpus filter ./fuzz-fixtures -o ./failures --failed
+//! mollusk corpus split ./fuzz-fixtures --train ./train --regression ./regression
+//! ```
+//!
+//! ### Fuzzing
+//!
+//! With the `fuzz` feature, `fuzz::target::FuzzTarget` provides a
+//! libFuzzer-compatible entrypoint for `cargo fuzz`. Each input is decoded as a
+//! Mollusk fixture blob and its instruction is processed against the target's
+//! program, so a directory of `.fix` files ejected from tests makes a seed
+//! corpus. Panics, violations of registered invariants and SBF programs
+//! failing to complete are reported as findings. For SBF programs, executed
+//! program counters are fed back to libFuzzer as coverage.
+//!
+//! ```rust,ignore
+//! #![no_main]
+//!
+//! use {libfuzzer_sys::fuzz_target, mollusk_svm::fuzz::target::FuzzTarget};
+//!
+//! thread_local! {
+//!     static TARGET: FuzzTarget = FuzzTarget::new(&PROGRAM_ID, include_bytes!("my_program.so"));
+//! }
+//!
+//! fuzz_target!(|data: &[u8]| TARGET.with(|target| target.fuzz(data)));
+//! ```
+//!
+//! ```ignore
+//! cargo fuzz run my_target ./fuzz-fixtures
+//! ```
+//!
+//! The `mollusk-svm-fuzz-mutator` crate provides structure-aware mutation of
+//! fixtures in either layout, ie. flipping instruction data bytes, resizing
+//! account data, changing owners and lamports, toggling signer and writable
+//! flags, and reordering instruction accounts. Its `mutate_blob` function can
+//! be used as a libFuzzer custom mutator, and `Mutator::mutants` can expand a
+//! corpus of fixtures.
+//!
+//! When a fixture reproduces a failure or divergence, `minimize` from the same
+//! crate reduces it to the smallest inputs for which a predicate still holds,
+//! removing accounts, truncating account data and shrinking instruction data.
+//! The CLI minimizes a fixture while a program fails with the same error, or,
+//! given a test program, while both programs diverge on the same checks:
+//!
+//! ```ignore
+//! mollusk minimize ./program.so ./failure.fix <PROGRAM_ID> --target ./program-v2.so
+//! ```
 
 mod accounts;
+#[cfg(feature = "debugger")]
+pub mod debugger;
+pub mod diff;
 pub mod file;
 #[cfg(any(feature = "fuzz", feature = "fuzz-fd"))]
 pub mod fuzz;
+pub mod invariant;
+pub mod location;
+pub mod mutation;
+pub mod native;
 pub mod program;
+#[cfg(feature = "proptest")]
+pub mod proptest;
+mod rent_state;
+pub mod report;
 pub mod result;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+pub mod state;
 pub mod sysvar;
 
-#[cfg(any(feature = "fuzz", feature = "fuzz-fd"))]
-use result::Compare;
 use {
     crate::{
+        invariant::{Invariant, InvariantContext},
         program::ProgramCache,
-        result::{Check, InstructionResult},
+        result::{Check, InnerInstruction, InstructionResult, ProgramResult},
         sysvar::Sysvars,
     },
     accounts::CompiledAccounts,
@@ -395,13 +721,21 @@ use {
     solana_compute_budget::compute_budget::ComputeBudget,
     solana_program_runtime::invoke_context::{EnvironmentConfig, InvokeContext},
     solana_sdk::{
-        account::Account, bpf_loader_upgradeable, feature_set::FeatureSet, fee::FeeStructure,
-        hash::Hash, instruction::Instruction, precompiles::get_precompile, pubkey::Pubkey,
+        account::Account,
+        bpf_loader_upgradeable,
+        feature_set::FeatureSet,
+        fee::FeeStructure,
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        precompiles::get_precompile,
+        pubkey::Pubkey,
         transaction_context::TransactionContext,
     },
     solana_timings::ExecuteTimings,
     std::{cell::RefCell, rc::Rc, sync::Arc},
 };
+#[cfg(any(feature = "fuzz", feature = "fuzz-fd"))]
+use {result::Compare, solana_sdk::transaction::TransactionError};
 
 pub(crate) const DEFAULT_LOADER_KEY: Pubkey = bpf_loader_upgradeable::id();
 
@@ -412,11 +746,19 @@ pub(crate) const DEFAULT_LOADER_KEY: Pubkey = bpf_loader_upgradeable::id();
 pub struct Mollusk {
     pub config: Config,
     pub compute_budget: ComputeBudget,
+    /// Whether to reject rent state transitions the runtime would reject
+    /// after the transaction, ie. an account left rent-paying. A chain of
+    /// instructions is checked once, after its last instruction, like a
+    /// single transaction.
+    pub enforce_rent_state: bool,
     pub feature_set: FeatureSet,
     pub fee_structure: FeeStructure,
+    pub invariants: Vec<Invariant>,
     pub logger: Option<Rc<RefCell<solana_log_collector::LogCollector>>>,
     pub program_cache: ProgramCache,
     pub sysvars: Sysvars,
+    #[cfg(feature = "debugger")]
+    pub debugger: Option<debugger::Debugger>,
     #[cfg(feature = "fuzz-fd")]
     pub slot: u64,
 }
@@ -444,11 +786,15 @@ impl Default for Mollusk {
         Self {
             config: Config::default(),
             compute_budget: ComputeBudget::default(),
+            enforce_rent_state: false,
             feature_set,
             fee_structure: FeeStructure::default(),
+            invariants: Vec::new(),
             program_cache: ProgramCache::default(),
             sysvars: Sysvars::default(),
             logger: None,
+            #[cfg(feature = "debugger")]
+            debugger: debugger::Debugger::from_env(),
             #[cfg(feature = "fuzz-fd")]
             slot: 0,
         }
@@ -470,7 +816,13 @@ impl Mollusk {
     /// Add a program to the test environment.
     ///
     /// If you intend to CPI to a program, this is likely what you want to use.
+    ///
+    /// If a `{program_name}.debug` file is found alongside the program's ELF,
+    /// its debug info is used to resolve the source locations of failures.
     pub fn add_program(&mut self, program_id: &Pubkey, program_name: &str, loader_key: &Pubkey) {
+        if let Some(debug_elf) = file::load_program_debug_elf(program_name) {
+            self.program_cache.add_debug_info(program_id, &debug_elf);
+        }
         let elf = file::load_program_elf(program_name);
         self.add_program_with_elf_and_loader(program_id, &elf, loader_key);
     }
@@ -494,6 +846,44 @@ impl Mollusk {
         );
     }
 
+    /// Add a program to the test environment, executed natively through its
+    /// `process_instruction` function, compiled for the host.
+    ///
+    /// Useful for stepping through program logic with a native debugger, or
+    /// for cross-checking results against the program's ELF.
+    pub fn add_native_program(
+        &mut self,
+        program_id: &Pubkey,
+        process_instruction: solana_sdk::entrypoint::ProcessInstruction,
+    ) {
+        self.program_cache
+            .add_native_program(program_id, process_instruction);
+    }
+
+    /// Serve a program to a debugger over the GDB remote protocol whenever it's
+    /// invoked, listening on the provided local TCP port.
+    ///
+    /// Execution blocks until a debugger connects, ie.
+    /// `gdb -ex "target remote 127.0.0.1:<port>"`.
+    #[cfg(feature = "debugger")]
+    pub fn debug_program(&mut self, program_id: &Pubkey, port: u16) {
+        self.debugger = Some(debugger::Debugger {
+            program_id: *program_id,
+            port,
+        });
+    }
+
+    /// Add an invariant to check after every processed instruction.
+    pub fn add_invariant(&mut self, invariant: Invariant) {
+        self.invariants.push(invariant);
+    }
+
+    /// Add invariants to check after every processed instruction, ie.
+    /// `Invariant::all()`.
+    pub fn add_invariants(&mut self, invariants: Vec<Invariant>) {
+        self.invariants.extend(invariants);
+    }
+
     /// Warp the test environment to a slot by updating sysvars.
     pub fn warp_to_slot(&mut self, slot: u64) {
         self.sysvars.warp_to_slot(slot)
@@ -505,6 +895,30 @@ impl Mollusk {
         &self,
         instruction: &Instruction,
         accounts: &[(Pubkey, Account)],
+    ) -> InstructionResult {
+        self.process_instruction_with_coverage(instruction, accounts, None)
+    }
+
+    /// Process an instruction, passing the program counter of every SBF
+    /// instruction executed by a traced program to `coverage`, in order.
+    pub(crate) fn process_instruction_with_coverage(
+        &self,
+        instruction: &Instruction,
+        accounts: &[(Pubkey, Account)],
+        coverage: Option<&mut dyn FnMut(u64)>,
+    ) -> InstructionResult {
+        self.execute_instruction(instruction, accounts, coverage, true)
+    }
+
+    /// Process an instruction, checking its rent state transitions if
+    /// `check_rent_state` is set. Chains process their instructions without
+    /// the check, and check the whole chain once instead.
+    fn execute_instruction(
+        &self,
+        instruction: &Instruction,
+        accounts: &[(Pubkey, Account)],
+        coverage: Option<&mut dyn FnMut(u64)>,
+        check_rent_state: bool,
     ) -> InstructionResult {
         let mut compute_units_consumed = 0;
         let mut timings = ExecuteTimings::default();
@@ -532,7 +946,19 @@ impl Mollusk {
             self.compute_budget.max_instruction_trace_length,
         );
 
-        let invoke_result = {
+        // Program logs are always collected, since they're used to locate
+        // errors. If the user didn't provide a logger, a throwaway one is used.
+        let logger = self
+            .logger
+            .clone()
+            .unwrap_or_else(solana_log_collector::LogCollector::new_ref);
+        let logs_start = logger.borrow().messages.len();
+
+        let _native_programs = native::attach(self.program_cache.native_programs());
+        #[cfg(feature = "debugger")]
+        let _debugger = debugger::attach(self.debugger);
+
+        let (invoke_result, traces) = {
             let mut program_cache = self.program_cache.cache().write().unwrap();
             let sysvar_cache = self.sysvars.setup_sysvar_cache(accounts);
             let mut invoke_context = InvokeContext::new(
@@ -546,12 +972,13 @@ impl Mollusk {
                     self.fee_structure.lamports_per_signature,
                     &sysvar_cache,
                 ),
-                self.logger.clone(),
+                Some(logger.clone()),
                 self.compute_budget,
             );
-            if let Some(precompile) = get_precompile(&instruction.program_id, |feature_id| {
-                invoke_context.get_feature_set().is_active(feature_id)
-            }) {
+            let invoke_result = if let Some(precompile) =
+                get_precompile(&instruction.program_id, |feature_id| {
+                    invoke_context.get_feature_set().is_active(feature_id)
+                }) {
                 invoke_context.process_precompile(
                     precompile,
                     &instruction.data,
@@ -567,10 +994,43 @@ impl Mollusk {
                     &mut compute_units_consumed,
                     &mut timings,
                 )
+            };
+            if let Some(coverage) = coverage {
+                invoke_context
+                    .get_traces()
+                    .iter()
+                    .flatten()
+                    .for_each(|state| coverage(state[location::PC_REGISTER]));
             }
+            // Keep only the final register state of each SBF frame, which is
+            // all that's needed to locate an error.
+            let traces = if invoke_result.is_err() {
+                invoke_context
+                    .get_traces()
+                    .iter()
+                    .map(|trace| trace.last().copied())
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            (invoke_result, traces)
         };
 
-        let return_data = transaction_context.get_return_data().1.to_vec();
+        let error_location = invoke_result.is_err().then(|| {
+            location::locate_error(
+                &instruction.program_id,
+                &logger.borrow().messages[logs_start..],
+                &traces,
+                &transaction_context,
+                &self.program_cache,
+            )
+        });
+
+        let (return_data_program_id, return_data) = transaction_context.get_return_data();
+        let return_data_program_id = *return_data_program_id;
+        let return_data = return_data.to_vec();
+        let logs = logger.borrow().messages[logs_start..].to_vec();
+        let inner_instructions = inner_instructions(&transaction_context);
 
         let resulting_accounts: Vec<(Pubkey, Account)> = if invoke_result.is_ok() {
             accounts
@@ -594,13 +1054,58 @@ impl Mollusk {
             accounts.to_vec()
         };
 
-        InstructionResult {
+        let mut result = InstructionResult {
             compute_units_consumed,
+            invariant_violations: vec![],
+            error_location,
             execution_time: timings.details.execute_us,
             program_result: invoke_result.clone().into(),
             raw_result: invoke_result,
             return_data,
+            return_data_program_id,
+            logs,
+            inner_instructions,
             resulting_accounts,
+        };
+
+        if check_rent_state {
+            self.check_rent_state(std::iter::once(instruction), accounts, &mut result);
+        }
+
+        result.invariant_violations = invariant::check_all(
+            &self.invariants,
+            &InvariantContext {
+                instruction,
+                accounts,
+                result: &result,
+                rent: &self.sysvars.rent,
+            },
+        );
+
+        result
+    }
+
+    /// Fail the result of a transaction's last instruction if the runtime
+    /// would reject any of the transaction's rent state transitions, when
+    /// `enforce_rent_state` is set. Like a failed transaction, the accounts
+    /// are reverted to their state before the transaction.
+    fn check_rent_state<'a>(
+        &self,
+        instructions: impl IntoIterator<Item = &'a Instruction>,
+        accounts: &[(Pubkey, Account)],
+        result: &mut InstructionResult,
+    ) {
+        if !self.enforce_rent_state || result.program_result.is_err() {
+            return;
+        }
+        if let Err(err) = rent_state::check_transitions(
+            instructions,
+            accounts,
+            &result.resulting_accounts,
+            &self.sysvars.rent,
+        ) {
+            result.program_result = ProgramResult::TransactionError(err);
+            result.resulting_accounts = accounts.to_vec();
         }
     }
 
@@ -624,8 +1129,14 @@ impl Mollusk {
             ..Default::default()
         };
 
-        for instruction in instructions {
-            let this_result = self.process_instruction(instruction, &result.resulting_accounts);
+        for (index, instruction) in instructions.iter().enumerate() {
+            let mut this_result =
+                self.execute_instruction(instruction, &result.resulting_accounts, None, false);
+            if index == instructions.len() - 1 {
+                // Rent state transitions are checked once, for the whole
+                // chain, like the runtime does for a transaction.
+                self.check_rent_state(instructions, accounts, &mut this_result);
+            }
 
             result.absorb(this_result);
 
@@ -637,6 +1148,100 @@ impl Mollusk {
         result
     }
 
+    /// Find the smallest compute unit limit at which an instruction still
+    /// succeeds with identical effects, by binary searching
+    /// `compute_budget.compute_unit_limit` between the compute units the
+    /// instruction consumed and its current value.
+    ///
+    /// This can differ from `compute_units_consumed`, since CPIs check the
+    /// remaining budget before invoking. Returns `None` if the instruction
+    /// doesn't succeed at the current limit.
+    pub fn find_min_compute_units(
+        &mut self,
+        instruction: &Instruction,
+        accounts: &[(Pubkey, Account)],
+    ) -> Option<u64> {
+        let max_limit = self.compute_budget.compute_unit_limit;
+        let baseline = self.process_instruction(instruction, accounts);
+        if baseline.program_result.is_err() {
+            return None;
+        }
+
+        // Restores the limit once the search is over, even if processing an
+        // instruction panics.
+        let guard = ComputeUnitLimitGuard {
+            mollusk: self,
+            limit: max_limit,
+        };
+        let mut succeeds_with_limit = |limit: u64| {
+            guard.mollusk.compute_budget.compute_unit_limit = limit;
+            let result = guard.mollusk.process_instruction(instruction, accounts);
+            result.program_result == baseline.program_result
+                && result.return_data == baseline.return_data
+                && result.resulting_accounts == baseline.resulting_accounts
+        };
+
+        // No instruction succeeds with less than it consumed, and most need
+        // exactly that.
+        let consumed = baseline.compute_units_consumed;
+        if consumed >= max_limit || succeeds_with_limit(consumed) {
+            return Some(consumed.min(max_limit));
+        }
+
+        // The limit at which the instruction fails, and the limit at which it
+        // succeeds.
+        let mut low = consumed;
+        let mut high = max_limit;
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if succeeds_with_limit(mid) {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+
+        Some(high)
+    }
+
+    /// Test an instruction which succeeds against every mutation of its
+    /// account privileges, ie. dropped signers or writable flags, swapped
+    /// owners, fake PDAs and duplicated accounts. Returns a report of every
+    /// mutation which still succeeded.
+    ///
+    /// Panics if the unmutated instruction fails.
+    pub fn test_privilege_mutations(
+        &self,
+        instruction: &Instruction,
+        accounts: &[(Pubkey, Account)],
+    ) -> mutation::MutationReport {
+        mutation::run(instruction, accounts, |instruction, accounts| {
+            self.process_instruction(instruction, accounts)
+        })
+    }
+
+    /// Test a property against instructions and accounts generated by a
+    /// `proptest` strategy. The first failing case is shrunk to a minimal
+    /// failing case, which is returned.
+    ///
+    /// For `fuzz` and `fuzz-fd` features only:
+    ///
+    /// The minimal failing case is ejected as a fixture when the
+    /// `EJECT_FUZZ_FIXTURES` environment variables are set, just like
+    /// `process_and_validate_instruction`.
+    #[cfg(feature = "proptest")]
+    pub fn test_property<S>(
+        &self,
+        config: crate::proptest::ProptestConfig,
+        strategy: S,
+        property: impl Fn(&InstructionResult, &Instruction, &[(Pubkey, Account)]) -> Result<(), String>,
+    ) -> Result<(), Box<crate::proptest::PropertyFailure>>
+    where
+        S: ::proptest::strategy::Strategy<Value = crate::proptest::InstructionCase>,
+    {
+        crate::proptest::run(self, config, strategy, property)
+    }
+
     /// Process an instruction using the minified Solana Virtual Machine (SVM)
     /// environment, then perform checks on the result. Panics if any checks
     /// fail.
@@ -670,7 +1275,7 @@ impl Mollusk {
         #[cfg(any(feature = "fuzz", feature = "fuzz-fd"))]
         fuzz::generate_fixtures_from_mollusk_test(self, instruction, accounts, &result);
 
-        result.run_checks_with_config(checks, &self.config);
+        result.run_checks_with_inputs(checks, accounts, &self.config);
         result
     }
 
@@ -698,6 +1303,11 @@ impl Mollusk {
     /// (ie. `EJECT_FUZZ_FIXTURES_FD`). This will generate Firedancer fuzzing
     /// fixtures, which are structured a bit differently than Mollusk's own
     /// protobuf layouts.
+    ///
+    /// To capture the whole chain as a single fixture instead, preserving the
+    /// order of its instructions and the effects of each one, provide
+    /// `EJECT_FUZZ_CHAIN_FIXTURES` (or `EJECT_FUZZ_CHAIN_FIXTURES_JSON`).
+    /// Chain fixtures can be replayed with `process_chain_fixture`.
     pub fn process_and_validate_instruction_chain(
         &self,
         instructions: &[(&Instruction, &[Check])],
@@ -707,14 +1317,35 @@ impl Mollusk {
             resulting_accounts: accounts.to_vec(),
             ..Default::default()
         };
+        #[cfg(feature = "fuzz")]
+        let mut results = vec![];
+
+        for (index, (instruction, checks)) in instructions.iter().enumerate() {
+            let mut this_result =
+                self.execute_instruction(instruction, &result.resulting_accounts, None, false);
+            if index == instructions.len() - 1 {
+                // Rent state transitions are checked once, for the whole
+                // chain, like the runtime does for a transaction.
+                self.check_rent_state(
+                    instructions.iter().map(|(instruction, _)| *instruction),
+                    accounts,
+                    &mut this_result,
+                );
+            }
 
-        for (instruction, checks) in instructions.iter() {
-            let this_result = self.process_and_validate_instruction(
+            #[cfg(any(feature = "fuzz", feature = "fuzz-fd"))]
+            fuzz::generate_fixtures_from_mollusk_test(
+                self,
                 instruction,
                 &result.resulting_accounts,
-                checks,
+                &this_result,
             );
 
+            this_result.run_checks_with_inputs(checks, &result.resulting_accounts, &self.config);
+
+            #[cfg(feature = "fuzz")]
+            results.push(this_result.clone());
+
             result.absorb(this_result);
 
             if result.program_result.is_err() {
@@ -722,6 +1353,17 @@ impl Mollusk {
             }
         }
 
+        #[cfg(feature = "fuzz")]
+        fuzz::generate_chain_fixture_from_mollusk_test(
+            self,
+            &instructions
+                .iter()
+                .map(|(instruction, _)| (*instruction).clone())
+                .collect::<Vec<_>>(),
+            accounts,
+            &results,
+        );
+
         result
     }
 
@@ -735,7 +1377,8 @@ impl Mollusk {
     ///
     /// Note: This is a mutable method on `Mollusk`, since loading a fixture
     /// into the test environment will alter `Mollusk` values, such as compute
-    /// budget and sysvars. However, the program cache remains unchanged.
+    /// budget and sysvars. The program cache only gains the programs embedded
+    /// in the fixture which it doesn't already hold, if any.
     ///
     /// Therefore, developers can provision a `Mollusk` instance, set up their
     /// desired program cache, and then run a series of fixtures against that
@@ -754,6 +1397,7 @@ impl Mollusk {
         self.compute_budget = compute_budget;
         self.feature_set = feature_set;
         self.sysvars = sysvars;
+        fuzz::mollusk::load_fixture_programs(self, &fixture.programs);
         self.process_instruction(&instruction, &accounts)
     }
 
@@ -768,7 +1412,8 @@ impl Mollusk {
     ///
     /// Note: This is a mutable method on `Mollusk`, since loading a fixture
     /// into the test environment will alter `Mollusk` values, such as compute
-    /// budget and sysvars. However, the program cache remains unchanged.
+    /// budget and sysvars. The program cache only gains the programs embedded
+    /// in the fixture which it doesn't already hold, if any.
     ///
     /// Therefore, developers can provision a `Mollusk` instance, set up their
     /// desired program cache, and then run a series of fixtures against that
@@ -783,7 +1428,7 @@ impl Mollusk {
         let result = self.process_fixture(fixture);
         InstructionResult::from(&fixture.output).compare_with_config(
             &result,
-            &Compare::everything(),
+            &Compare::for_effects_version(&Compare::all_effects(), fixture.output.version),
             &self.config,
         );
         result
@@ -808,12 +1453,16 @@ impl Mollusk {
     ///
     /// Note: This is a mutable method on `Mollusk`, since loading a fixture
     /// into the test environment will alter `Mollusk` values, such as compute
-    /// budget and sysvars. However, the program cache remains unchanged.
+    /// budget and sysvars. The program cache only gains the programs embedded
+    /// in the fixture which it doesn't already hold, if any.
     ///
     /// Therefore, developers can provision a `Mollusk` instance, set up their
     /// desired program cache, and then run a series of fixtures against that
     /// `Mollusk` instance (and cache).
     ///
+    /// Checks of fields the fixture's effects don't record, such as logs in
+    /// fixtures recorded before them, fail rather than pass unchecked.
+    ///
     /// Note: To compare the result against the entire fixture effects, pass
     /// `&[FixtureCheck::All]` for `checks`.
     pub fn process_and_partially_validate_fixture(
@@ -823,10 +1472,176 @@ impl Mollusk {
     ) -> InstructionResult {
         let result = self.process_fixture(fixture);
         let expected = InstructionResult::from(&fixture.output);
-        result.compare_with_config(&expected, checks, &self.config);
+        result.compare_with_effects_report(&expected, checks, fixture.output.version, &self.config);
         result
     }
 
+    #[cfg(feature = "fuzz")]
+    /// Re-execute a fuzz fixture's input using the minified Solana Virtual
+    /// Machine (SVM) environment and rewrite its effects with the result, for
+    /// when a program change intentionally alters its behavior.
+    ///
+    /// Returns a report comparing the fixture's previous effects against the
+    /// new ones, whose failures are the effects which changed. The effects
+    /// are rewritten with the current version, so they also record any
+    /// fields their previous version didn't.
+    ///
+    /// Embedded programs are rewritten with the ELFs in the program cache,
+    /// so the fixture embeds the programs it was blessed with, which requires
+    /// ELF retention (see `ProgramCache::set_elf_retention`).
+    ///
+    /// Returns the transaction error, leaving the fixture untouched, if the
+    /// input is now rejected with one, such as an invalid rent state
+    /// transition, which effects can't record.
+    ///
+    /// Note: This is a mutable method on `Mollusk`, since loading a fixture
+    /// into the test environment will alter `Mollusk` values, such as compute
+    /// budget and sysvars. The program cache only gains the programs embedded
+    /// in the fixture which it doesn't already hold, if any.
+    pub fn bless_fixture(
+        &mut self,
+        fixture: &mut mollusk_svm_fuzz_fixture::Fixture,
+    ) -> Result<report::CheckReport, TransactionError> {
+        let result = self.process_fixture(fixture);
+        let output = mollusk_svm_fuzz_fixture::effects::Effects::try_from(&result)?;
+        let report = InstructionResult::from(&fixture.output).compare_with_report(
+            &result,
+            &Compare::for_effects_version(&Compare::all_effects(), fixture.output.version),
+            &Config::default(),
+        );
+        fixture.output = output;
+        fixture.programs = fuzz::mollusk::rebuild_fixture_programs(self, &fixture.programs);
+        Ok(report)
+    }
+
+    #[cfg(feature = "fuzz")]
+    /// Process a chain fuzz fixture using the minified Solana Virtual Machine
+    /// (SVM) environment. Each instruction is processed against the accounts
+    /// resulting from the previous one, stopping at the first failing
+    /// instruction, just like `process_instruction_chain`.
+    ///
+    /// Returns the result of each processed instruction, in order.
+    ///
+    /// Note: This is a mutable method on `Mollusk`, since loading a fixture
+    /// into the test environment will alter `Mollusk` values, such as compute
+    /// budget and sysvars. The program cache only gains the programs embedded
+    /// in the fixture which it doesn't already hold, if any.
+    pub fn process_chain_fixture(
+        &mut self,
+        fixture: &mollusk_svm_fuzz_fixture::chain::ChainFixture,
+    ) -> Vec<InstructionResult> {
+        let fuzz::mollusk::ParsedChainFixtureContext {
+            accounts,
+            compute_budget,
+            feature_set,
+            instructions,
+            sysvars,
+        } = fuzz::mollusk::parse_chain_fixture_context(&fixture.input);
+        self.compute_budget = compute_budget;
+        self.feature_set = feature_set;
+        self.sysvars = sysvars;
+        fuzz::mollusk::load_fixture_programs(self, &fixture.programs);
+
+        let mut results: Vec<InstructionResult> = Vec::with_capacity(instructions.len());
+        for (index, instruction) in instructions.iter().enumerate() {
+            let input_accounts = results
+                .last()
+                .map_or(&accounts, |result| &result.resulting_accounts);
+            let mut result = self.execute_instruction(instruction, input_accounts, None, false);
+            if index == instructions.len() - 1 {
+                // Rent state transitions are checked once, for the whole
+                // chain, like the runtime does for a transaction.
+                self.check_rent_state(&instructions, &accounts, &mut result);
+            }
+            let failed = result.program_result.is_err();
+            results.push(result);
+            if failed {
+                break;
+            }
+        }
+        results
+    }
+
+    #[cfg(feature = "fuzz")]
+    /// Process a chain fuzz fixture using the minified Solana Virtual Machine
+    /// (SVM) environment and compare the result of each instruction against
+    /// its effects in the fixture.
+    ///
+    /// The number of processed instructions is compared first, since the
+    /// chain stops at the first failing instruction, and a fixture missing
+    /// effects for some instructions can't validate them.
+    ///
+    /// Note: This is a mutable method on `Mollusk`, since loading a fixture
+    /// into the test environment will alter `Mollusk` values, such as compute
+    /// budget and sysvars. The program cache only gains the programs embedded
+    /// in the fixture which it doesn't already hold, if any.
+    pub fn process_and_validate_chain_fixture(
+        &mut self,
+        fixture: &mollusk_svm_fuzz_fixture::chain::ChainFixture,
+    ) -> Vec<InstructionResult> {
+        let results = self.process_chain_fixture(fixture);
+        Self::compare_chain_fixture(fixture, &results, &self.config);
+        results
+    }
+
+    #[cfg(feature = "fuzz")]
+    /// Compare the result of each instruction of a chain against its effects
+    /// in the fixture, prefixing each check with the instruction's index.
+    fn compare_chain_fixture(
+        fixture: &mollusk_svm_fuzz_fixture::chain::ChainFixture,
+        results: &[InstructionResult],
+        config: &Config,
+    ) -> report::CheckReport {
+        let mut r = report::Reporter::new(config);
+        r.compare(
+            "instruction_count",
+            None,
+            fixture.output.len(),
+            results.len(),
+        );
+        let mut report = r.finish();
+        for (index, (result, effects)) in results.iter().zip(fixture.output.iter()).enumerate() {
+            let mut instruction_report = InstructionResult::from(effects).compare_with_report(
+                result,
+                &Compare::for_effects_version(&Compare::all_effects(), effects.version),
+                config,
+            );
+            instruction_report.outcomes.iter_mut().for_each(|outcome| {
+                outcome.check = format!("instructions[{}].{}", index, outcome.check)
+            });
+            report.extend(instruction_report);
+        }
+        report
+    }
+
+    #[cfg(feature = "fuzz")]
+    /// Re-execute a chain fuzz fixture's instructions using the minified
+    /// Solana Virtual Machine (SVM) environment and rewrite the effects of
+    /// each one with its result, like `bless_fixture`.
+    ///
+    /// The checks in the returned report are prefixed with the index of the
+    /// instruction they compare, ie. `instructions[1].program_result`. A
+    /// chain which now stops at a different instruction fails the
+    /// `instruction_count` check, and records effects for the instructions
+    /// it processed. Embedded programs are rewritten like `bless_fixture`.
+    ///
+    /// Returns the transaction error, leaving the fixture untouched, if the
+    /// chain is now rejected with one, like `bless_fixture`.
+    pub fn bless_chain_fixture(
+        &mut self,
+        fixture: &mut mollusk_svm_fuzz_fixture::chain::ChainFixture,
+    ) -> Result<report::CheckReport, TransactionError> {
+        let results = self.process_chain_fixture(fixture);
+        let output = results
+            .iter()
+            .map(mollusk_svm_fuzz_fixture::effects::Effects::try_from)
+            .collect::<Result<_, _>>()?;
+        let report = Self::compare_chain_fixture(fixture, &results, &Config::default());
+        fixture.output = output;
+        fixture.programs = fuzz::mollusk::rebuild_fixture_programs(self, &fixture.programs);
+        Ok(report)
+    }
+
     #[cfg(feature = "fuzz-fd")]
     /// Process a Firedancer fuzz fixture using the minified Solana Virtual
     /// Machine (SVM) environment.
@@ -901,6 +1716,7 @@ impl Mollusk {
             &fixture.output,
         );
 
+        // Firedancer fixtures only record the original effects.
         expected_result.compare_with_config(&result, &Compare::everything(), &self.config);
         result
     }
@@ -926,6 +1742,10 @@ impl Mollusk {
     /// desired program cache, and then run a series of fixtures against that
     /// `Mollusk` instance (and cache).
     ///
+    /// Firedancer fixtures don't record logs, inner instructions or the
+    /// program that set the return data, so checks of those fail rather than
+    /// pass unchecked.
+    ///
     /// Note: To compare the result against the entire fixture effects, pass
     /// `&[FixtureCheck::All]` for `checks`.
     pub fn process_and_partially_validate_firedancer_fixture(
@@ -951,7 +1771,89 @@ impl Mollusk {
             &fixture.output,
         );
 
-        result.compare_with_config(&expected, checks, &self.config);
+        // Firedancer fixtures only record the original effects.
+        result.compare_with_effects_report(&expected, checks, 0, &self.config);
         result
     }
+
+    #[cfg(feature = "fuzz-fd")]
+    /// Re-execute a Firedancer fuzz fixture's input using the minified Solana
+    /// Virtual Machine (SVM) environment and rewrite its effects with the
+    /// result, like `bless_fixture`.
+    ///
+    /// Firedancer fixtures only record the original effects, so only those
+    /// are compared in the returned report.
+    ///
+    /// Returns the transaction error, leaving the fixture untouched, if the
+    /// input is now rejected with one, like `bless_fixture`.
+    pub fn bless_firedancer_fixture(
+        &mut self,
+        fixture: &mut mollusk_svm_fuzz_fixture_firedancer::Fixture,
+    ) -> Result<report::CheckReport, TransactionError> {
+        let (_, expected) = fuzz::firedancer::load_firedancer_fixture(fixture);
+        let result = self.process_firedancer_fixture(fixture);
+        let output = fuzz::firedancer::build_fixture_effects(&fixture.input, &result)?;
+        let report =
+            expected.compare_with_report(&result, &Compare::everything(), &Config::default());
+        fixture.output = output;
+        Ok(report)
+    }
+}
+
+/// Restores a `Mollusk` instance's compute unit limit when dropped.
+struct ComputeUnitLimitGuard<'a> {
+    mollusk: &'a mut Mollusk,
+    limit: u64,
+}
+
+impl Drop for ComputeUnitLimitGuard<'_> {
+    fn drop(&mut self) {
+        self.mollusk.compute_budget.compute_unit_limit = self.limit;
+    }
+}
+
+/// Collect the instructions invoked through CPI from the instruction trace,
+/// in the order they were invoked.
+fn inner_instructions(transaction_context: &TransactionContext) -> Vec<InnerInstruction> {
+    (0..transaction_context.get_instruction_trace_length())
+        .filter_map(|index| {
+            let instruction_context = transaction_context
+                .get_instruction_context_at_index_in_trace(index)
+                .ok()?;
+            let stack_height = instruction_context.get_stack_height();
+            // The top-level instruction is at stack height 1.
+            if stack_height <= 1 {
+                return None;
+            }
+            let program_id = *instruction_context
+                .get_last_program_key(transaction_context)
+                .ok()?;
+            let accounts = (0..instruction_context.get_number_of_instruction_accounts())
+                .map(|account_index| {
+                    let index_in_transaction = instruction_context
+                        .get_index_of_instruction_account_in_transaction(account_index)
+                        .ok()?;
+                    Some(AccountMeta {
+                        pubkey: *transaction_context
+                            .get_key_of_account_at_index(index_in_transaction)
+                            .ok()?,
+                        is_signer: instruction_context
+                            .is_instruction_account_signer(account_index)
+                            .ok()?,
+                        is_writable: instruction_context
+                            .is_instruction_account_writable(account_index)
+                            .ok()?,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(InnerInstruction {
+                instruction: Instruction {
+                    program_id,
+                    accounts,
+                    data: instruction_context.get_instruction_data().to_vec(),
+                },
+                stack_height: stack_height as u8,
+            })
+        })
+        .collect()
 }
@@ -0,0 +1,325 @@
+//! Module for resolving source-level locations of program failures.
+//!
+//! When a program fails, Mollusk attempts to pinpoint where in the program's
+//! source the failure occurred. Two sources of information are used:
+//!
+//! * Panic messages emitted to the program logs, such as `panicked at
+//!   src/lib.rs:42:5:`.
+//! * The program counter at which the program faulted, resolved into a
+//!   `file:line` through the DWARF debug info of an unstripped ELF.
+//!
+//! Faulting program counters are only recorded for programs with debug info,
+//! since instruction tracing must be enabled in their runtime environment.
+//! Debug info is picked up automatically if the ELF provided to Mollusk is
+//! unstripped, or when a `{program_name}.debug` file (as produced by
+//! `cargo build-sbf --debug`) is found next to `{program_name}.so`.
+
+use {
+    crate::program::ProgramCache,
+    addr2line::gimli,
+    object::{Object, ObjectSection},
+    solana_sdk::{pubkey::Pubkey, transaction_context::TransactionContext},
+    std::{borrow::Cow, fmt, str::FromStr},
+};
+
+/// The index of the program counter within a traced register state.
+pub(crate) const PC_REGISTER: usize = 11;
+/// The size of a single SBF instruction, in bytes.
+const INSN_SIZE: u64 = 8;
+
+/// A location within a program's source code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The source file.
+    pub file: String,
+    /// The line within the source file.
+    pub line: u32,
+    /// The column within the line, if known.
+    pub column: Option<u32>,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a failing program stopped executing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorLocation {
+    /// The program in which the failure originated.
+    pub program_id: Pubkey,
+    /// The program counter (instruction index) at which the program stopped,
+    /// if the program was traced.
+    pub pc: Option<u64>,
+    /// The source location of the program counter, resolved from DWARF.
+    pub fault: Option<SourceLocation>,
+    /// The source location of a panic, parsed from the program logs.
+    pub panic: Option<SourceLocation>,
+}
+
+impl ErrorLocation {
+    /// The most precise source location available, preferring the panic
+    /// location over the resolved program counter.
+    pub fn source(&self) -> Option<&SourceLocation> {
+        self.panic.as_ref().or(self.fault.as_ref())
+    }
+}
+
+impl fmt::Display for ErrorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "program {}", self.program_id)?;
+        if let Some(panic) = &self.panic {
+            write!(f, " panicked at {}", panic)?;
+        }
+        if let Some(pc) = self.pc {
+            write!(f, " stopped at pc {}", pc)?;
+            if let Some(fault) = &self.fault {
+                write!(f, " ({})", fault)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// DWARF debug info for a program, stored as the raw (unstripped) ELF.
+///
+/// The DWARF sections are only parsed when a failure needs to be resolved,
+/// which keeps the cost of successful executions at zero.
+#[derive(Clone, Debug)]
+pub struct DebugInfo {
+    elf: Vec<u8>,
+}
+
+impl DebugInfo {
+    /// Create debug info from an unstripped ELF, if it contains DWARF line
+    /// information.
+    pub fn from_elf(elf: &[u8]) -> Option<Self> {
+        let file = object::File::parse(elf).ok()?;
+        file.section_by_name(".debug_line")?;
+        Some(Self { elf: elf.to_vec() })
+    }
+
+    /// Resolve a program counter (instruction index into the `.text` section)
+    /// into a source location.
+    pub fn resolve(&self, pc: u64) -> Option<SourceLocation> {
+        let file = object::File::parse(self.elf.as_slice()).ok()?;
+        let text_address = file.section_by_name(".text")?.address();
+
+        let load_section = |id: gimli::SectionId| -> Result<Cow<[u8]>, gimli::Error> {
+            Ok(file
+                .section_by_name(id.name())
+                .and_then(|section| section.uncompressed_data().ok())
+                .unwrap_or(Cow::Borrowed(&[])))
+        };
+        let sections = gimli::DwarfSections::load(load_section).ok()?;
+        let dwarf =
+            sections.borrow(|section| gimli::EndianSlice::new(section, gimli::LittleEndian));
+        let context = addr2line::Context::from_dwarf(dwarf).ok()?;
+
+        let address = text_address.saturating_add(pc.saturating_mul(INSN_SIZE));
+        let location = context.find_location(address).ok()??;
+        Some(SourceLocation {
+            file: location.file?.to_string(),
+            line: location.line?,
+            column: location.column,
+        })
+    }
+}
+
+/// Parse a panic location from a single log message, ie.
+/// `Program log: panicked at src/lib.rs:42:5:`.
+///
+/// Both the current (`panicked at <location>:`) and the legacy
+/// (`panicked at '<message>', <location>`) panic formats are supported.
+pub(crate) fn parse_panic_location(message: &str) -> Option<SourceLocation> {
+    let (_, rest) = message.split_once("panicked at ")?;
+    let rest = if let Some(quoted) = rest.strip_prefix('\'') {
+        // Legacy format: the location follows the quoted message.
+        let (_, location) = quoted.rsplit_once("', ")?;
+        location
+    } else {
+        rest
+    };
+    let location = rest.lines().next()?.trim_end().trim_end_matches(':');
+
+    let mut parts = location.rsplitn(3, ':');
+    let last = parts.next()?;
+    let second_last = parts.next()?;
+    match parts.next() {
+        Some(file) => Some(SourceLocation {
+            file: file.to_string(),
+            line: u32::from_str(second_last).ok()?,
+            column: u32::from_str(last).ok(),
+        }),
+        None => Some(SourceLocation {
+            file: second_last.to_string(),
+            line: u32::from_str(last).ok()?,
+            column: None,
+        }),
+    }
+}
+
+/// Find the innermost program that failed, along with the location of any
+/// panic it emitted, by walking the program logs of a single instruction.
+fn parse_failure_from_logs(logs: &[String]) -> Option<(Pubkey, Option<SourceLocation>)> {
+    let mut invoke_stack: Vec<Pubkey> = Vec::new();
+    let mut panic = None;
+    for message in logs {
+        let Some(rest) = message.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(log) = rest.strip_prefix("log: ") {
+            if let Some(location) = parse_panic_location(log) {
+                panic = invoke_stack.last().map(|id| (*id, location));
+            }
+            continue;
+        }
+        let mut words = rest.split_whitespace();
+        let Some(program_id) = words.next().and_then(|w| Pubkey::from_str(w).ok()) else {
+            continue;
+        };
+        match words.next() {
+            Some("invoke") => invoke_stack.push(program_id),
+            Some("success") => {
+                invoke_stack.pop();
+            }
+            Some("failed:") => {
+                let panic = panic
+                    .filter(|(id, _)| *id == program_id)
+                    .map(|(_, location)| location);
+                return Some((program_id, panic));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The program IDs of every frame in the instruction trace, in the order in
+/// which the frames were popped off of the invoke stack.
+fn frames_in_pop_order(transaction_context: &TransactionContext) -> Vec<Pubkey> {
+    let mut frames = Vec::new();
+    let mut stack: Vec<(usize, Pubkey)> = Vec::new();
+    for index in 0..transaction_context.get_instruction_trace_length() {
+        let Ok(instruction_context) =
+            transaction_context.get_instruction_context_at_index_in_trace(index)
+        else {
+            continue;
+        };
+        let Ok(program_id) = instruction_context.get_last_program_key(transaction_context) else {
+            continue;
+        };
+        let height = instruction_context.get_stack_height();
+        while stack.last().is_some_and(|(h, _)| *h >= height) {
+            frames.push(stack.pop().unwrap().1);
+        }
+        stack.push((height, *program_id));
+    }
+    while let Some((_, program_id)) = stack.pop() {
+        frames.push(program_id);
+    }
+    frames
+}
+
+/// Locate the failure of an instruction.
+///
+/// `logs` are the program logs emitted by the instruction and `traces` are
+/// the final register states of each SBF frame, in pop order.
+pub(crate) fn locate_error(
+    program_id: &Pubkey,
+    logs: &[String],
+    traces: &[Option<[u64; 12]>],
+    transaction_context: &TransactionContext,
+    program_cache: &ProgramCache,
+) -> ErrorLocation {
+    let (program_id, panic) = parse_failure_from_logs(logs).unwrap_or((*program_id, None));
+
+    // Traces are only recorded for frames executed by the SBF VM, in the
+    // order the frames were popped. The failing frame is the last one popped
+    // for the failing program, since nothing is invoked after the failure.
+    let pc = frames_in_pop_order(transaction_context)
+        .into_iter()
+        .filter(|id| program_cache.is_sbf_program(id))
+        .zip(traces.iter())
+        .filter(|(id, _)| *id == program_id)
+        .last()
+        .and_then(|(_, state)| state.map(|state| state[PC_REGISTER]));
+
+    let fault = pc.and_then(|pc| {
+        program_cache
+            .debug_info(&program_id)
+            .and_then(|debug_info| debug_info.resolve(pc))
+    });
+
+    ErrorLocation {
+        program_id,
+        pc,
+        fault,
+        panic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_panic_location() {
+        assert_eq!(
+            parse_panic_location("panicked at src/lib.rs:42:5:\nattempt to divide by zero"),
+            Some(SourceLocation {
+                file: "src/lib.rs".to_string(),
+                line: 42,
+                column: Some(5),
+            }),
+        );
+        assert_eq!(
+            parse_panic_location("panicked at 'attempt to divide by zero', src/lib.rs:42:5"),
+            Some(SourceLocation {
+                file: "src/lib.rs".to_string(),
+                line: 42,
+                column: Some(5),
+            }),
+        );
+        assert_eq!(
+            parse_panic_location("panicked at src/processor.rs:7"),
+            Some(SourceLocation {
+                file: "src/processor.rs".to_string(),
+                line: 7,
+                column: None,
+            }),
+        );
+        assert_eq!(parse_panic_location("Hello, world!"), None);
+    }
+
+    #[test]
+    fn test_parse_failure_from_logs() {
+        let caller = Pubkey::new_unique();
+        let callee = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {} invoke [1]", caller),
+            format!("Program {} invoke [2]", callee),
+            "Program log: panicked at src/lib.rs:10:9:\nboom".to_string(),
+            format!("Program {} consumed 100 of 199000 compute units", callee),
+            format!("Program {} failed: SBF program panicked", callee),
+            format!("Program {} consumed 1000 of 200000 compute units", caller),
+            format!("Program {} failed: SBF program panicked", caller),
+        ];
+        assert_eq!(
+            parse_failure_from_logs(&logs),
+            Some((
+                callee,
+                Some(SourceLocation {
+                    file: "src/lib.rs".to_string(),
+                    line: 10,
+                    column: Some(9),
+                }),
+            )),
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Module for privilege mutation testing.
+//!
+//! Given an instruction which succeeds, mutation testing systematically
+//! weakens its accounts, ie. dropping signer or writable flags, swapping
+//! account owners, substituting fake PDAs, and duplicating accounts. Each
+//! variant is processed, and any which still succeeds is reported as a
+//! surviving mutation, since it likely points to a missing signer, owner, or
+//! address check in the program.
+//!
+//! ```rust,ignore
+//! let report = mollusk.test_privilege_mutations(&instruction, &accounts);
+//! assert!(report.survivors.is_empty(), "{}", report);
+//! ```
+
+use {
+    crate::result::{InstructionResult, ProgramResult},
+    solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    },
+    std::fmt,
+};
+
+/// A mutation of an instruction and its accounts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mutation {
+    /// Remove the signer flag from every meta for an account.
+    DropSigner { pubkey: Pubkey },
+    /// Remove the writable flag from every meta for an account.
+    DropWritable { pubkey: Pubkey },
+    /// Replace an account's owner.
+    SwapOwner { pubkey: Pubkey, owner: Pubkey },
+    /// Replace a program-derived address with a different off-curve address,
+    /// holding an identical account.
+    FakePda { pda: Pubkey, fake: Pubkey },
+    /// Replace the account at an index in the instruction's accounts with
+    /// the account at another index, keeping only the flags both metas
+    /// share.
+    DuplicateAccount { index: usize, duplicate_of: usize },
+}
+
+impl Mutation {
+    /// Generate every mutation of an instruction and its accounts.
+    pub fn generate(instruction: &Instruction, accounts: &[(Pubkey, Account)]) -> Vec<Self> {
+        let mut keys: Vec<Pubkey> = Vec::new();
+        for meta in &instruction.accounts {
+            if !keys.contains(&meta.pubkey) {
+                keys.push(meta.pubkey);
+            }
+        }
+        let find = |pubkey: &Pubkey| {
+            accounts
+                .iter()
+                .find(|(key, _)| key == pubkey)
+                .map(|(_, account)| account)
+        };
+        let has = |pubkey: &Pubkey, flag: fn(&AccountMeta) -> bool| {
+            instruction
+                .accounts
+                .iter()
+                .any(|meta| meta.pubkey == *pubkey && flag(meta))
+        };
+
+        let mut mutations = Vec::new();
+        for pubkey in &keys {
+            if has(pubkey, |meta| meta.is_signer) {
+                mutations.push(Self::DropSigner { pubkey: *pubkey });
+            }
+        }
+        for pubkey in &keys {
+            if has(pubkey, |meta| meta.is_writable) {
+                mutations.push(Self::DropWritable { pubkey: *pubkey });
+            }
+        }
+        for pubkey in &keys {
+            if find(pubkey).is_some_and(|account| !account.executable) {
+                mutations.push(Self::SwapOwner {
+                    pubkey: *pubkey,
+                    owner: Pubkey::new_unique(),
+                });
+            }
+        }
+        for pubkey in &keys {
+            if !pubkey.is_on_curve() {
+                let (fake, _) =
+                    Pubkey::find_program_address(&[pubkey.as_ref()], &Pubkey::new_unique());
+                mutations.push(Self::FakePda { pda: *pubkey, fake });
+            }
+        }
+        for (index, meta) in instruction.accounts.iter().enumerate() {
+            for (duplicate_of, other) in instruction.accounts.iter().enumerate() {
+                if other.pubkey != meta.pubkey {
+                    mutations.push(Self::DuplicateAccount {
+                        index,
+                        duplicate_of,
+                    });
+                }
+            }
+        }
+        mutations
+    }
+
+    /// Apply the mutation, returning the mutated instruction and accounts.
+    pub fn apply(
+        &self,
+        instruction: &Instruction,
+        accounts: &[(Pubkey, Account)],
+    ) -> (Instruction, Vec<(Pubkey, Account)>) {
+        let mut instruction = instruction.clone();
+        let mut accounts = accounts.to_vec();
+        match self {
+            Self::DropSigner { pubkey } => instruction
+                .accounts
+                .iter_mut()
+                .filter(|meta| meta.pubkey == *pubkey)
+                .for_each(|meta| meta.is_signer = false),
+            Self::DropWritable { pubkey } => instruction
+                .accounts
+                .iter_mut()
+                .filter(|meta| meta.pubkey == *pubkey)
+                .for_each(|meta| meta.is_writable = false),
+            Self::SwapOwner { pubkey, owner } => accounts
+                .iter_mut()
+                .filter(|(key, _)| key == pubkey)
+                .for_each(|(_, account)| account.owner = *owner),
+            Self::FakePda { pda, fake } => {
+                instruction
+                    .accounts
+                    .iter_mut()
+                    .filter(|meta| meta.pubkey == *pda)
+                    .for_each(|meta| meta.pubkey = *fake);
+                accounts
+                    .iter_mut()
+                    .filter(|(key, _)| key == pda)
+                    .for_each(|(key, _)| *key = *fake);
+            }
+            Self::DuplicateAccount {
+                index,
+                duplicate_of,
+            } => {
+                // The runtime merges the flags of duplicate metas, so keep
+                // only the flags both metas share, otherwise the duplicate
+                // would gain privileges rather than lose them.
+                let original = instruction.accounts[*duplicate_of].clone();
+                let meta = &mut instruction.accounts[*index];
+                meta.pubkey = original.pubkey;
+                meta.is_signer &= original.is_signer;
+                meta.is_writable &= original.is_writable;
+            }
+        }
+        (instruction, accounts)
+    }
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DropSigner { pubkey } => write!(f, "Dropped signer flag of {}", pubkey),
+            Self::DropWritable { pubkey } => write!(f, "Dropped writable flag of {}", pubkey),
+            Self::SwapOwner { pubkey, owner } => {
+                write!(f, "Swapped owner of {} to {}", pubkey, owner)
+            }
+            Self::FakePda { pda, fake } => write!(f, "Substituted PDA {} with {}", pda, fake),
+            Self::DuplicateAccount {
+                index,
+                duplicate_of,
+            } => write!(
+                f,
+                "Replaced account {} with duplicate of account {}",
+                index, duplicate_of
+            ),
+        }
+    }
+}
+
+/// A mutation which still succeeded.
+#[derive(Debug)]
+pub struct SurvivingMutation {
+    /// The mutation applied.
+    pub mutation: Mutation,
+    /// The result of processing the mutated instruction.
+    pub result: InstructionResult,
+}
+
+/// The outcome of privilege mutation testing.
+#[derive(Debug, Default)]
+pub struct MutationReport {
+    /// The number of mutations tested.
+    pub tested: usize,
+    /// The mutations which still succeeded.
+    pub survivors: Vec<SurvivingMutation>,
+}
+
+impl MutationReport {
+    /// The mutations which still succeeded.
+    pub fn surviving_mutations(&self) -> impl Iterator<Item = &Mutation> {
+        self.survivors.iter().map(|survivor| &survivor.mutation)
+    }
+}
+
+impl fmt::Display for MutationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} mutations survived",
+            self.survivors.len(),
+            self.tested
+        )?;
+        for survivor in &self.survivors {
+            writeln!(f, "  {}", survivor.mutation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Process every mutation of an instruction, collecting those which still
+/// succeed.
+pub(crate) fn run(
+    instruction: &Instruction,
+    accounts: &[(Pubkey, Account)],
+    process: impl Fn(&Instruction, &[(Pubkey, Account)]) -> InstructionResult,
+) -> MutationReport {
+    let baseline = process(instruction, accounts);
+    if baseline.program_result != ProgramResult::Success {
+        panic!(
+            "Mutation testing requires an instruction which succeeds, got: {:?}",
+            baseline.program_result
+        );
+    }
+
+    let mut report = MutationReport::default();
+    for mutation in Mutation::generate(instruction, accounts) {
+        let (instruction, accounts) = mutation.apply(instruction, accounts);
+        let result = process(&instruction, &accounts);
+        report.tested += 1;
+        if result.program_result == ProgramResult::Success {
+            report
+                .survivors
+                .push(SurvivingMutation { mutation, result });
+        }
+    }
+    report
+}
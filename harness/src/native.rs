@@ -0,0 +1,416 @@
+//! Module for executing programs natively, compiled for the host.
+//!
+//! A program's `process_instruction` function can be registered with Mollusk
+//! in place of its ELF. Natively executed programs are invoked through a
+//! builtin shim, which serializes the instruction's accounts exactly like the
+//! BPF loader does, hands them to the program, and deserializes them back
+//! into the transaction context. As a result, any changes made by the program
+//! are subject to the same runtime checks as they would be on-chain, and the
+//! same `InstructionResult` is returned.
+//!
+//! Since the program runs on the host, it can be stepped through with a
+//! native debugger, or built with sanitizers.
+//!
+//! Syscalls are routed to the running instruction through `solana_program`'s
+//! syscall stubs, so logging, cross-program invocations, return data, and
+//! sysvars work as expected. For example, `msg!` output is recorded in the
+//! program logs, just like on-chain. However, there are a few differences from
+//! executing the ELF:
+//!
+//! * Compute units are not metered for natively executed programs. Each
+//!   invocation consumes a flat `DEFAULT_COMPUTE_UNITS`.
+//! * A panic fails the instruction with `ProgramFailedToComplete`, but its
+//!   location isn't recorded in the program logs.
+
+use {
+    solana_bpf_loader_program::serialization,
+    solana_program_runtime::{
+        declare_process_instruction, invoke_context::InvokeContext, stable_log,
+    },
+    solana_sdk::{
+        account_info::AccountInfo,
+        entrypoint::{self, ProcessInstruction, ProgramResult},
+        instruction::{Instruction, InstructionError},
+        program_error::UNSUPPORTED_SYSVAR,
+        program_stubs::{self, SyscallStubs},
+        pubkey::Pubkey,
+        stable_layout::stable_instruction::StableInstruction,
+        sysvar::Sysvar,
+    },
+    solana_timings::ExecuteTimings,
+    std::{
+        cell::{Cell, RefCell},
+        collections::HashMap,
+        panic::{self, AssertUnwindSafe},
+        sync::{Arc, Once},
+    },
+};
+
+/// Install the syscall stubs which route syscalls to natively executing
+/// programs.
+pub(crate) fn install_syscall_stubs() {
+    static INSTALL_SYSCALL_STUBS: Once = Once::new();
+    INSTALL_SYSCALL_STUBS.call_once(|| {
+        let fallback = program_stubs::set_syscall_stubs(Box::new(DefaultSyscallStubs));
+        program_stubs::set_syscall_stubs(Box::new(NativeSyscallStubs { fallback }));
+    });
+}
+
+thread_local! {
+    static PROGRAMS: RefCell<Arc<HashMap<Pubkey, ProcessInstruction>>> = RefCell::default();
+}
+
+/// Put a program cache's natively executed programs in effect for every
+/// instruction processed on the current thread, until the returned guard is
+/// dropped.
+pub(crate) fn attach(programs: &Arc<HashMap<Pubkey, ProcessInstruction>>) -> AttachGuard {
+    AttachGuard {
+        previous: PROGRAMS.replace(Arc::clone(programs)),
+    }
+}
+
+pub(crate) struct AttachGuard {
+    previous: Arc<HashMap<Pubkey, ProcessInstruction>>,
+}
+
+impl Drop for AttachGuard {
+    fn drop(&mut self) {
+        PROGRAMS.set(std::mem::take(&mut self.previous));
+    }
+}
+
+thread_local! {
+    static INVOKE_CONTEXT: Cell<Option<*mut InvokeContext<'static>>> = const { Cell::new(None) };
+}
+
+/// Make the invoke context available to syscall stubs for the duration of a
+/// native program's execution.
+struct InvokeContextGuard {
+    previous: Option<*mut InvokeContext<'static>>,
+}
+
+impl InvokeContextGuard {
+    fn new(invoke_context: &mut InvokeContext) -> Self {
+        // The lifetime is erased so the invoke context can be stashed in a
+        // thread local. It's only dereferenced by `with_invoke_context` while
+        // the guard is alive.
+        let pointer = (invoke_context as *mut InvokeContext).cast::<InvokeContext<'static>>();
+        Self {
+            previous: INVOKE_CONTEXT.replace(Some(pointer)),
+        }
+    }
+}
+
+impl Drop for InvokeContextGuard {
+    fn drop(&mut self) {
+        INVOKE_CONTEXT.set(self.previous.take());
+    }
+}
+
+/// Call `f` with the invoke context of the natively executing program on the
+/// current thread, if any.
+///
+/// The invoke context is taken out of the thread local for the duration of
+/// the call, so a reentrant syscall can't borrow it a second time. A
+/// cross-program invocation to another native program installs its own
+/// invoke context, which is derived from the borrow passed to `f`.
+fn with_invoke_context<R>(f: impl FnOnce(&mut InvokeContext<'static>) -> R) -> Option<R> {
+    let pointer = INVOKE_CONTEXT.take()?;
+    // Put the invoke context back when done, even if `f` unwinds.
+    let _guard = InvokeContextGuard {
+        previous: Some(pointer),
+    };
+    // SAFETY: The pointer was stashed by the `InvokeContextGuard` of the
+    // running entrypoint, whose invoke context outlives the program's
+    // execution and isn't used by the entrypoint until the program returns.
+    // It was taken out of the thread local above, so this is the only live
+    // borrow, and it doesn't outlive the call.
+    Some(f(unsafe { &mut *pointer }))
+}
+
+/// The compute units consumed by each invocation of a natively executed
+/// program, since the program itself isn't metered. The runtime requires
+/// builtins to consume at least some compute units.
+pub const DEFAULT_COMPUTE_UNITS: u64 = 1;
+
+/// Unwinds a natively executed program when a cross-program invocation fails.
+struct InvokeFailure(InstructionError);
+
+declare_process_instruction!(Entrypoint, DEFAULT_COMPUTE_UNITS, |invoke_context| {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let program_id = *instruction_context.get_last_program_key(transaction_context)?;
+    let process_instruction = PROGRAMS
+        .with_borrow(|programs| programs.get(&program_id).copied())
+        .ok_or(InstructionError::UnsupportedProgramId)?;
+
+    let (mut parameter_bytes, _regions, accounts_metadata) =
+        serialization::serialize_parameters(transaction_context, instruction_context, true)?;
+
+    let result = {
+        let _guard = InvokeContextGuard::new(invoke_context);
+        // SAFETY: The parameters were just serialized with the aligned layout
+        // `deserialize` expects, and the buffer outlives the account infos,
+        // which are dropped at the end of this block, before it's read back.
+        let (program_id, account_infos, instruction_data) =
+            unsafe { entrypoint::deserialize(parameter_bytes.as_slice_mut().as_mut_ptr()) };
+        // A panicking program fails the instruction, rather than the test.
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            process_instruction(program_id, &account_infos, instruction_data)
+        }))
+    };
+
+    let (_returned_from_program_id, return_data) =
+        invoke_context.transaction_context.get_return_data();
+    if !return_data.is_empty() {
+        stable_log::program_return(
+            &invoke_context.get_log_collector(),
+            &program_id,
+            return_data,
+        );
+    }
+
+    result
+        .map_err(|payload| match payload.downcast::<InvokeFailure>() {
+            Ok(failure) => failure.0,
+            Err(_) => InstructionError::ProgramFailedToComplete,
+        })?
+        .map_err(|error| InstructionError::from(u64::from(error)))?;
+
+    serialization::deserialize_parameters(
+        invoke_context.transaction_context,
+        invoke_context
+            .transaction_context
+            .get_current_instruction_context()?,
+        true,
+        parameter_bytes.as_slice(),
+        &accounts_metadata,
+    )
+});
+
+/// Perform a cross-program invocation from a natively executing program.
+///
+/// Changes to the caller's account infos are written to the transaction
+/// context before the invocation, and the callee's changes are written back
+/// afterwards, just like the `sol_invoke_signed` syscall does.
+fn invoke_signed(
+    invoke_context: &mut InvokeContext,
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let caller = *instruction_context.get_last_program_key(transaction_context)?;
+    let signers = signers_seeds
+        .iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let instruction = StableInstruction::from(instruction.clone());
+    let (instruction_accounts, program_indices) =
+        invoke_context.prepare_instruction(&instruction, &signers)?;
+
+    // Write the caller's changes to the transaction context.
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let mut writable_accounts = Vec::with_capacity(instruction_accounts.len());
+    for instruction_account in instruction_accounts.iter() {
+        let key = transaction_context
+            .get_key_of_account_at_index(instruction_account.index_in_transaction)?;
+        let account_info_index = account_infos
+            .iter()
+            .position(|account_info| account_info.key == key)
+            .ok_or(InstructionError::MissingAccount)?;
+        let account_info = &account_infos[account_info_index];
+        let mut account = instruction_context.try_borrow_instruction_account(
+            transaction_context,
+            instruction_account.index_in_caller,
+        )?;
+        if account.get_lamports() != account_info.lamports() {
+            account.set_lamports(account_info.lamports())?;
+        }
+        let data = account_info
+            .try_borrow_data()
+            .map_err(|_| InstructionError::AccountBorrowFailed)?;
+        if account.get_data() != *data {
+            account.set_data_from_slice(&data)?;
+        }
+        // The owner is changed last, so the lamports and data can still be
+        // modified by the current owner.
+        if account.get_owner() != account_info.owner {
+            account.set_owner(account_info.owner.as_ref())?;
+        }
+        if instruction_account.is_writable {
+            writable_accounts.push((instruction_account.index_in_caller, account_info_index));
+        }
+    }
+
+    invoke_context.process_instruction(
+        &instruction.data,
+        &instruction_accounts,
+        &program_indices,
+        &mut 0,
+        &mut ExecuteTimings::default(),
+    )?;
+
+    // Write the callee's changes back to the caller's account infos.
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    for (index_in_caller, account_info_index) in writable_accounts {
+        let account = instruction_context
+            .try_borrow_instruction_account(transaction_context, index_in_caller)?;
+        let account_info = &account_infos[account_info_index];
+        **account_info
+            .try_borrow_mut_lamports()
+            .map_err(|_| InstructionError::AccountBorrowFailed)? = account.get_lamports();
+        if account_info.owner != account.get_owner() {
+            account_info.assign(account.get_owner());
+        }
+        if account_info.data_len() != account.get_data().len() {
+            account_info
+                .realloc(account.get_data().len(), false)
+                .map_err(|_| InstructionError::InvalidRealloc)?;
+        }
+        account_info
+            .try_borrow_mut_data()
+            .map_err(|_| InstructionError::AccountBorrowFailed)?
+            .copy_from_slice(account.get_data());
+    }
+
+    Ok(())
+}
+
+/// Write a sysvar from the sysvar cache to the provided address.
+fn get_sysvar<T: Sysvar + Clone>(
+    sysvar: Result<Arc<T>, InstructionError>,
+    var_addr: *mut u8,
+) -> u64 {
+    match sysvar {
+        Ok(sysvar) => {
+            // SAFETY: `Sysvar::get` passes the address of a `T` to the stub.
+            unsafe { *(var_addr as *mut T) = T::clone(&sysvar) };
+            entrypoint::SUCCESS
+        }
+        Err(_) => UNSUPPORTED_SYSVAR,
+    }
+}
+
+/// The syscall stubs `solana_program` falls back to when none are installed.
+struct DefaultSyscallStubs;
+
+impl SyscallStubs for DefaultSyscallStubs {}
+
+/// Syscall stubs which are routed to the natively executing program's invoke
+/// context. Syscalls made outside of native execution go to the previously
+/// installed stubs.
+struct NativeSyscallStubs {
+    fallback: Box<dyn SyscallStubs>,
+}
+
+impl SyscallStubs for NativeSyscallStubs {
+    fn sol_log(&self, message: &str) {
+        with_invoke_context(|invoke_context| {
+            stable_log::program_log(&invoke_context.get_log_collector(), message)
+        })
+        .unwrap_or_else(|| self.fallback.sol_log(message))
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        with_invoke_context(|invoke_context| {
+            stable_log::program_data(&invoke_context.get_log_collector(), fields)
+        })
+        .unwrap_or_else(|| self.fallback.sol_log_data(fields))
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        match with_invoke_context(|invoke_context| {
+            invoke_signed(invoke_context, instruction, account_infos, signers_seeds)
+        }) {
+            Some(Ok(())) => Ok(()),
+            // Just like the syscall, a failed invocation aborts the caller.
+            Some(Err(error)) => panic::resume_unwind(Box::new(InvokeFailure(error))),
+            None => self
+                .fallback
+                .sol_invoke_signed(instruction, account_infos, signers_seeds),
+        }
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        with_invoke_context(|invoke_context| {
+            get_sysvar(invoke_context.get_sysvar_cache().get_clock(), var_addr)
+        })
+        .unwrap_or_else(|| self.fallback.sol_get_clock_sysvar(var_addr))
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        with_invoke_context(|invoke_context| {
+            get_sysvar(
+                invoke_context.get_sysvar_cache().get_epoch_schedule(),
+                var_addr,
+            )
+        })
+        .unwrap_or_else(|| self.fallback.sol_get_epoch_schedule_sysvar(var_addr))
+    }
+
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        with_invoke_context(|invoke_context| {
+            get_sysvar(
+                invoke_context.get_sysvar_cache().get_epoch_rewards(),
+                var_addr,
+            )
+        })
+        .unwrap_or_else(|| self.fallback.sol_get_epoch_rewards_sysvar(var_addr))
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        with_invoke_context(|invoke_context| {
+            get_sysvar(invoke_context.get_sysvar_cache().get_rent(), var_addr)
+        })
+        .unwrap_or_else(|| self.fallback.sol_get_rent_sysvar(var_addr))
+    }
+
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        with_invoke_context(|invoke_context| {
+            get_sysvar(
+                invoke_context.get_sysvar_cache().get_last_restart_slot(),
+                var_addr,
+            )
+        })
+        .unwrap_or_else(|| self.fallback.sol_get_last_restart_slot(var_addr))
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        with_invoke_context(|invoke_context| {
+            let (program_id, data) = invoke_context.transaction_context.get_return_data();
+            (!data.is_empty()).then(|| (*program_id, data.to_vec()))
+        })
+        .unwrap_or_else(|| self.fallback.sol_get_return_data())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        with_invoke_context(|invoke_context| {
+            let transaction_context = &mut invoke_context.transaction_context;
+            let program_id = *transaction_context
+                .get_current_instruction_context()
+                .and_then(|instruction_context| {
+                    instruction_context.get_last_program_key(transaction_context)
+                })
+                .unwrap();
+            transaction_context
+                .set_return_data(program_id, data.to_vec())
+                .unwrap();
+        })
+        .unwrap_or_else(|| self.fallback.sol_set_return_data(data))
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        with_invoke_context(|invoke_context| invoke_context.get_stack_height() as u64)
+            .unwrap_or_else(|| self.fallback.sol_get_stack_height())
+    }
+}
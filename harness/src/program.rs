@@ -1,17 +1,24 @@
 //! Module for working with Solana programs.
 
 use {
+    crate::location::DebugInfo,
     solana_bpf_loader_program::syscalls::create_program_runtime_environment_v1,
     solana_compute_budget::compute_budget::ComputeBudget,
     solana_program_runtime::{
         invoke_context::BuiltinFunctionWithContext,
-        loaded_programs::{LoadProgramMetrics, ProgramCacheEntry, ProgramCacheForTxBatch},
+        loaded_programs::{
+            LoadProgramMetrics, ProgramCacheEntry, ProgramCacheEntryType, ProgramCacheForTxBatch,
+        },
     },
     solana_sdk::{
-        account::Account, bpf_loader_upgradeable::UpgradeableLoaderState, feature_set::FeatureSet,
-        native_loader, pubkey::Pubkey, rent::Rent,
+        account::Account, bpf_loader_upgradeable::UpgradeableLoaderState,
+        entrypoint::ProcessInstruction, feature_set::FeatureSet, native_loader, pubkey::Pubkey,
+        rent::Rent,
+    },
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::{Arc, RwLock},
     },
-    std::sync::{Arc, RwLock},
 };
 
 /// Loader keys, re-exported from `solana_sdk` for convenience.
@@ -42,6 +49,15 @@ pub mod precompile_keys {
 
 pub struct ProgramCache {
     cache: RwLock<ProgramCacheForTxBatch>,
+    debug_info: HashMap<Pubkey, DebugInfo>,
+    /// The loader and ELF of each program added from an ELF, ordered by
+    /// program ID. Only populated while `retain_elfs` is set.
+    elfs: BTreeMap<Pubkey, (Pubkey, Vec<u8>)>,
+    /// The `process_instruction` function of each natively executed program.
+    /// Shared with the thread processing an instruction, rather than copied.
+    native_programs: Arc<HashMap<Pubkey, ProcessInstruction>>,
+    instruction_tracing: bool,
+    retain_elfs: bool,
 }
 
 impl Default for ProgramCache {
@@ -54,6 +70,12 @@ impl Default for ProgramCache {
         });
         Self {
             cache: RwLock::new(cache),
+            debug_info: HashMap::default(),
+            elfs: BTreeMap::default(),
+            native_programs: Arc::default(),
+            instruction_tracing: false,
+            // Embedding programs in ejected fixtures requires their ELFs.
+            retain_elfs: std::env::var("EJECT_FUZZ_FIXTURES_PROGRAMS").is_ok(),
         }
     }
 }
@@ -63,14 +85,39 @@ impl ProgramCache {
         &self.cache
     }
 
+    pub(crate) fn native_programs(&self) -> &Arc<HashMap<Pubkey, ProcessInstruction>> {
+        &self.native_programs
+    }
+
     /// Add a builtin program to the cache.
     pub fn add_builtin(&mut self, builtin: Builtin) {
         let program_id = builtin.program_id;
         let entry = builtin.program_cache_entry();
+        self.elfs.remove(&program_id);
         self.cache.write().unwrap().replenish(program_id, entry);
     }
 
+    /// Enable or disable instruction tracing for programs added to the cache
+    /// afterwards, regardless of whether they have debug info. Tracing is
+    /// required to collect SBF coverage when fuzzing.
+    pub fn set_instruction_tracing(&mut self, enabled: bool) {
+        self.instruction_tracing = enabled;
+    }
+
+    /// Enable or disable retaining the ELFs of programs added to the cache
+    /// afterwards, so they can be embedded in fixtures. Enabled by default
+    /// when `EJECT_FUZZ_FIXTURES_PROGRAMS` is set, otherwise ELFs are
+    /// dropped once loaded.
+    pub fn set_elf_retention(&mut self, enabled: bool) {
+        self.retain_elfs = enabled;
+    }
+
     /// Add a program to the cache.
+    ///
+    /// If the ELF is unstripped, debug info was previously registered for the
+    /// program with `add_debug_info`, or instruction tracing was enabled with
+    /// `set_instruction_tracing`, the program is executed with instruction
+    /// tracing enabled, so failures can be resolved to source locations.
     pub fn add_program(
         &mut self,
         program_id: &Pubkey,
@@ -79,9 +126,19 @@ impl ProgramCache {
         compute_budget: &ComputeBudget,
         feature_set: &FeatureSet,
     ) {
+        if let Some(debug_info) = DebugInfo::from_elf(elf) {
+            self.debug_info.insert(*program_id, debug_info);
+        }
+        let debugging_features =
+            self.instruction_tracing || self.debug_info.contains_key(program_id);
         let environment = Arc::new(
-            create_program_runtime_environment_v1(feature_set, compute_budget, false, false)
-                .unwrap(),
+            create_program_runtime_environment_v1(
+                feature_set,
+                compute_budget,
+                false,
+                debugging_features,
+            )
+            .unwrap(),
         );
         self.cache.write().unwrap().replenish(
             *program_id,
@@ -98,12 +155,66 @@ impl ProgramCache {
                 .unwrap(),
             ),
         );
+        if self.retain_elfs {
+            self.elfs.insert(*program_id, (*loader_key, elf.to_vec()));
+        } else {
+            self.elfs.remove(program_id);
+        }
+    }
+
+    /// Add a program to the cache, executed natively through its
+    /// `process_instruction` function rather than an ELF.
+    ///
+    /// See the `native` module for the differences from executing the ELF.
+    pub fn add_native_program(
+        &mut self,
+        program_id: &Pubkey,
+        process_instruction: ProcessInstruction,
+    ) {
+        crate::native::install_syscall_stubs();
+        Arc::make_mut(&mut self.native_programs).insert(*program_id, process_instruction);
+        self.add_builtin(Builtin {
+            program_id: *program_id,
+            name: "native_program",
+            entrypoint: crate::native::Entrypoint::vm,
+        });
+    }
+
+    /// Register DWARF debug info for a program, from its unstripped ELF.
+    ///
+    /// Must be called before the program is added to the cache. Returns
+    /// `false` if the provided ELF contains no debug info.
+    pub fn add_debug_info(&mut self, program_id: &Pubkey, debug_elf: &[u8]) -> bool {
+        DebugInfo::from_elf(debug_elf)
+            .map(|debug_info| self.debug_info.insert(*program_id, debug_info))
+            .is_some()
+    }
+
+    /// Get the debug info registered for a program, if any.
+    pub fn debug_info(&self, program_id: &Pubkey) -> Option<&DebugInfo> {
+        self.debug_info.get(program_id)
+    }
+
+    /// The loader and ELF of each program added to the cache from an ELF
+    /// while ELF retention was enabled, ordered by program ID. See
+    /// `set_elf_retention`.
+    pub fn program_elfs(&self) -> impl Iterator<Item = (&Pubkey, &Pubkey, &[u8])> {
+        self.elfs
+            .iter()
+            .map(|(program_id, (loader_key, elf))| (program_id, loader_key, elf.as_slice()))
     }
 
     /// Load a program from the cache.
     pub fn load_program(&self, program_id: &Pubkey) -> Option<Arc<ProgramCacheEntry>> {
         self.cache.read().unwrap().find(program_id)
     }
+
+    /// Whether the program is executed by the SBF VM (as opposed to being a
+    /// builtin).
+    pub(crate) fn is_sbf_program(&self, program_id: &Pubkey) -> bool {
+        self.load_program(program_id)
+            .is_some_and(|entry| matches!(entry.program, ProgramCacheEntryType::Loaded(_)))
+    }
 }
 
 pub struct Builtin {
@@ -122,6 +233,13 @@ impl Builtin {
     }
 }
 
+/// The entrypoint used for the BPF loaders. With the `debugger` feature, the
+/// loaders are shimmed so programs can be served to a debugger.
+#[cfg(not(feature = "debugger"))]
+const BPF_LOADER_ENTRYPOINT: BuiltinFunctionWithContext = solana_bpf_loader_program::Entrypoint::vm;
+#[cfg(feature = "debugger")]
+const BPF_LOADER_ENTRYPOINT: BuiltinFunctionWithContext = crate::debugger::Entrypoint::vm;
+
 static BUILTINS: &[Builtin] = &[
     Builtin {
         program_id: solana_system_program::id(),
@@ -131,12 +249,12 @@ static BUILTINS: &[Builtin] = &[
     Builtin {
         program_id: loader_keys::LOADER_V2,
         name: "solana_bpf_loader_program",
-        entrypoint: solana_bpf_loader_program::Entrypoint::vm,
+        entrypoint: BPF_LOADER_ENTRYPOINT,
     },
     Builtin {
         program_id: loader_keys::LOADER_V3,
         name: "solana_bpf_loader_upgradeable_program",
-        entrypoint: solana_bpf_loader_program::Entrypoint::vm,
+        entrypoint: BPF_LOADER_ENTRYPOINT,
     },
     #[cfg(feature = "all-builtins")]
     Builtin {
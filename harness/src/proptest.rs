@@ -0,0 +1,247 @@
+//! Module for property-based testing with `proptest`.
+//!
+//! Provides strategies for generating pubkeys, accounts, account meta
+//! permutations, and instruction data, along with a runner which processes
+//! generated instructions against a property and shrinks any failure to a
+//! minimal case.
+//!
+//! ```rust
+//! use {
+//!     mollusk_svm::{proptest::ProptestConfig, Mollusk},
+//!     proptest::prelude::*,
+//!     solana_sdk::{account::Account, pubkey::Pubkey, system_instruction, system_program},
+//! };
+//!
+//! let mollusk = Mollusk::default();
+//! let sender = Pubkey::new_unique();
+//! let recipient = Pubkey::new_unique();
+//! let accounts = vec![
+//!     (sender, Account::new(1_000_000, 0, &system_program::id())),
+//!     (recipient, Account::new(1_000_000, 0, &system_program::id())),
+//! ];
+//!
+//! let strategy = (0u64..1_000_000).prop_map(move |amount| {
+//!     let instruction = system_instruction::transfer(&sender, &recipient, amount);
+//!     (instruction, accounts.clone())
+//! });
+//!
+//! mollusk
+//!     .test_property(ProptestConfig::default(), strategy, |result, _, _| {
+//!         if result.program_result.is_err() {
+//!             return Err(format!("Transfer failed: {:?}", result.program_result));
+//!         }
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+//!
+//! When the `fuzz` or `fuzz-fd` features are also enabled, the minimal
+//! failing case is ejected as a fixture, following the same
+//! `EJECT_FUZZ_FIXTURES` environment variables as
+//! `process_and_validate_instruction`.
+//!
+//! Note: requires the `proptest` feature.
+
+pub use proptest::test_runner::Config as ProptestConfig;
+use {
+    crate::{result::InstructionResult, Mollusk},
+    proptest::{
+        arbitrary::{any, Arbitrary},
+        collection::{self, SizeRange},
+        strategy::{Just, Strategy},
+        test_runner::{TestCaseError, TestError, TestRunner},
+    },
+    solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+    },
+    std::fmt,
+};
+
+/// A generated test case: an instruction and its input accounts.
+pub type InstructionCase = (Instruction, Vec<(Pubkey, Account)>);
+
+/// A strategy for arbitrary pubkeys.
+pub fn pubkey() -> impl Strategy<Value = Pubkey> {
+    any::<[u8; 32]>().prop_map(Pubkey::new_from_array)
+}
+
+/// A strategy for accounts owned by `owner`, with arbitrary lamports and
+/// arbitrary data within the provided size range.
+pub fn account(owner: Pubkey, space: impl Into<SizeRange>) -> impl Strategy<Value = Account> {
+    (any::<u64>(), collection::vec(any::<u8>(), space)).prop_map(move |(lamports, data)| Account {
+        lamports,
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    })
+}
+
+/// A strategy for rent-exempt accounts owned by `owner`, with `space` bytes
+/// of arbitrary data. Lamports range from the rent-exempt minimum balance up
+/// to one SOL above it.
+pub fn rent_exempt_account(
+    owner: Pubkey,
+    space: usize,
+    rent: &Rent,
+) -> impl Strategy<Value = Account> {
+    let minimum_balance = rent.minimum_balance(space);
+    (
+        minimum_balance..=minimum_balance.saturating_add(LAMPORTS_PER_SOL),
+        collection::vec(any::<u8>(), space),
+    )
+        .prop_map(move |(lamports, data)| Account {
+            lamports,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        })
+}
+
+/// A strategy for permutations of account metas, reordering them and
+/// toggling their signer and writable flags. Shrinks towards the provided
+/// metas.
+pub fn account_meta_permutations(
+    metas: Vec<AccountMeta>,
+) -> impl Strategy<Value = Vec<AccountMeta>> {
+    let len = metas.len();
+    (
+        Just(metas).prop_shuffle(),
+        collection::vec(any::<(bool, bool)>(), len),
+    )
+        .prop_map(|(mut metas, toggles)| {
+            for (meta, (toggle_signer, toggle_writable)) in metas.iter_mut().zip(toggles) {
+                meta.is_signer ^= toggle_signer;
+                meta.is_writable ^= toggle_writable;
+            }
+            metas
+        })
+}
+
+/// A strategy for instruction data, generating values of a user type and
+/// serializing them with the provided function.
+pub fn instruction_data<T: Arbitrary>(
+    serialize: impl Fn(&T) -> Vec<u8>,
+) -> impl Strategy<Value = Vec<u8>> {
+    any::<T>().prop_map(move |value| serialize(&value))
+}
+
+/// A strategy for instruction data, generating values of a user type and
+/// serializing them with Borsh.
+///
+/// Note: requires the `borsh` feature.
+#[cfg(feature = "borsh")]
+pub fn borsh_instruction_data<T: Arbitrary + borsh::BorshSerialize>(
+) -> impl Strategy<Value = Vec<u8>> {
+    instruction_data(|value: &T| borsh::to_vec(value).unwrap())
+}
+
+/// The minimal failing case of a property test.
+#[derive(Debug)]
+pub struct PropertyFailure {
+    /// The reason the property failed.
+    pub reason: String,
+    /// The minimal failing instruction.
+    pub instruction: Instruction,
+    /// The minimal failing input accounts.
+    pub accounts: Vec<(Pubkey, Account)>,
+    /// The result of processing the minimal failing case.
+    pub result: InstructionResult,
+}
+
+impl fmt::Display for PropertyFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "PROPERTY FAILED: {}", self.reason)?;
+        writeln!(f, "  Minimal failing instruction: {:?}", self.instruction)?;
+        writeln!(f, "  Minimal failing accounts: {:?}", self.accounts)?;
+        write!(f, "  Program result: {:?}", self.result.program_result)
+    }
+}
+
+/// Process every generated case against the property, shrinking the first
+/// failure to a minimal case.
+///
+/// Panics if the runner aborts, ie. when too many cases are rejected.
+pub(crate) fn run<S: Strategy<Value = InstructionCase>>(
+    mollusk: &Mollusk,
+    config: ProptestConfig,
+    strategy: S,
+    property: impl Fn(&InstructionResult, &Instruction, &[(Pubkey, Account)]) -> Result<(), String>,
+) -> Result<(), Box<PropertyFailure>> {
+    let mut runner = TestRunner::new(config);
+    let outcome = runner.run(&strategy, |(instruction, accounts)| {
+        let result = mollusk.process_instruction(&instruction, &accounts);
+        property(&result, &instruction, &accounts).map_err(TestCaseError::fail)
+    });
+
+    match outcome {
+        Ok(()) => Ok(()),
+        Err(TestError::Abort(reason)) => panic!("Property test aborted: {}", reason),
+        Err(TestError::Fail(reason, (instruction, accounts))) => {
+            let result = mollusk.process_instruction(&instruction, &accounts);
+
+            #[cfg(any(feature = "fuzz", feature = "fuzz-fd"))]
+            crate::fuzz::generate_fixtures_from_mollusk_test(
+                mollusk,
+                &instruction,
+                &accounts,
+                &result,
+            );
+
+            Err(Box::new(PropertyFailure {
+                reason: reason.message().to_string(),
+                instruction,
+                accounts,
+                result,
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, proptest::strategy::ValueTree};
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_borsh_instruction_data() {
+        let mut runner = TestRunner::deterministic();
+        let data = borsh_instruction_data::<(u8, u64)>();
+        for _ in 0..64 {
+            assert_eq!(data.new_tree(&mut runner).unwrap().current().len(), 9);
+        }
+    }
+
+    #[test]
+    fn test_strategies() {
+        let mut runner = TestRunner::deterministic();
+        let rent = Rent::default();
+        let owner = Pubkey::new_unique();
+        let metas = vec![
+            AccountMeta::new(Pubkey::new_unique(), true),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new(Pubkey::new_unique(), false),
+        ];
+
+        let accounts = rent_exempt_account(owner, 16, &rent);
+        let permutations = account_meta_permutations(metas.clone());
+
+        for _ in 0..64 {
+            let account = accounts.new_tree(&mut runner).unwrap().current();
+            assert_eq!(account.owner, owner);
+            assert_eq!(account.data.len(), 16);
+            assert!(rent.is_exempt(account.lamports, 16));
+
+            let permutation = permutations.new_tree(&mut runner).unwrap().current();
+            assert_eq!(permutation.len(), metas.len());
+            assert!(metas
+                .iter()
+                .all(|meta| permutation.iter().any(|other| other.pubkey == meta.pubkey)));
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Post-execution rent state checks, mirroring the runtime's rules for
+//! transitions between rent states.
+
+use solana_sdk::{
+    account::{Account, ReadableAccount},
+    incinerator,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    transaction::TransactionError,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+enum RentState {
+    /// The account has no lamports.
+    Uninitialized,
+    /// The account holds lamports, but not enough to be rent-exempt.
+    RentPaying { lamports: u64, data_size: usize },
+    /// The account is rent-exempt.
+    RentExempt,
+}
+
+impl RentState {
+    fn from_account(account: &impl ReadableAccount, rent: &Rent) -> Self {
+        if account.lamports() == 0 {
+            Self::Uninitialized
+        } else if rent.is_exempt(account.lamports(), account.data().len()) {
+            Self::RentExempt
+        } else {
+            Self::RentPaying {
+                lamports: account.lamports(),
+                data_size: account.data().len(),
+            }
+        }
+    }
+
+    /// An account may only end up rent-paying if it already was, without
+    /// being resized or receiving lamports.
+    fn transition_allowed_from(&self, pre: &Self) -> bool {
+        match self {
+            Self::Uninitialized | Self::RentExempt => true,
+            Self::RentPaying {
+                lamports: post_lamports,
+                data_size: post_data_size,
+            } => match pre {
+                Self::Uninitialized | Self::RentExempt => false,
+                Self::RentPaying {
+                    lamports: pre_lamports,
+                    data_size: pre_data_size,
+                } => post_data_size == pre_data_size && post_lamports <= pre_lamports,
+            },
+        }
+    }
+}
+
+/// Check the rent state transition of every account written by a
+/// transaction's instructions, from before the transaction to after it,
+/// failing with `InsufficientFundsForRent` on the first account whose
+/// transition the runtime would reject.
+///
+/// Since Mollusk doesn't compile a transaction message, the reported
+/// `account_index` is the account's position among the instructions'
+/// accounts, in the order they first appear.
+pub(crate) fn check_transitions<'a>(
+    instructions: impl IntoIterator<Item = &'a Instruction>,
+    pre: &[(Pubkey, Account)],
+    post: &[(Pubkey, Account)],
+    rent: &Rent,
+) -> Result<(), TransactionError> {
+    // Each account, and whether any instruction writes to it.
+    let mut keys: Vec<(Pubkey, bool)> = Vec::new();
+    for meta in instructions
+        .into_iter()
+        .flat_map(|instruction| &instruction.accounts)
+    {
+        match keys.iter_mut().find(|(key, _)| key == &meta.pubkey) {
+            Some((_, is_writable)) => *is_writable |= meta.is_writable,
+            None => keys.push((meta.pubkey, meta.is_writable)),
+        }
+    }
+    let state = |accounts: &[(Pubkey, Account)], pubkey: &Pubkey| {
+        accounts
+            .iter()
+            .find(|(key, _)| key == pubkey)
+            .map(|(_, account)| RentState::from_account(account, rent))
+            .unwrap_or(RentState::Uninitialized)
+    };
+    for (account_index, (pubkey, is_writable)) in keys.iter().enumerate() {
+        if !is_writable || incinerator::check_id(pubkey) {
+            continue;
+        }
+        if !state(post, pubkey).transition_allowed_from(&state(pre, pubkey)) {
+            return Err(TransactionError::InsufficientFundsForRent {
+                account_index: account_index as u8,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::account::AccountSharedData};
+
+    #[test]
+    fn test_transition_allowed() {
+        let rent = Rent::default();
+        let exempt = rent.minimum_balance(8);
+        let state = |lamports: u64, space: usize| {
+            let account = AccountSharedData::new(lamports, space, &Pubkey::default());
+            RentState::from_account(&account, &rent)
+        };
+
+        let uninitialized = state(0, 0);
+        let rent_exempt = state(exempt, 8);
+        let rent_paying = state(exempt - 1, 8);
+        assert_eq!(uninitialized, RentState::Uninitialized);
+        assert_eq!(rent_exempt, RentState::RentExempt);
+
+        // Becoming rent-paying is rejected.
+        assert!(!rent_paying.transition_allowed_from(&uninitialized));
+        assert!(!rent_paying.transition_allowed_from(&rent_exempt));
+
+        // Remaining rent-paying is allowed, unless resized or credited.
+        assert!(rent_paying.transition_allowed_from(&rent_paying));
+        assert!(state(exempt - 2, 8).transition_allowed_from(&rent_paying));
+        assert!(!state(exempt - 1, 9).transition_allowed_from(&rent_paying));
+        assert!(!rent_paying.transition_allowed_from(&state(exempt - 2, 8)));
+
+        // Anything can become uninitialized or rent-exempt.
+        assert!(uninitialized.transition_allowed_from(&rent_paying));
+        assert!(rent_exempt.transition_allowed_from(&rent_paying));
+    }
+}
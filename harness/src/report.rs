@@ -0,0 +1,388 @@
+//! Structured reports of checks run against instruction results.
+//!
+//! Every check run through `InstructionResult::run_checks_with_report` or
+//! `InstructionResult::compare_with_report` is recorded in a `CheckReport`,
+//! along with whether it passed and the expected and actual values. Reports
+//! can be rendered, aggregated, or (with the `serde` feature) serialized,
+//! rather than relying on panics or messages printed to stderr.
+
+use {
+    crate::{
+        diff::{self, Colored, DataDiff, StateDiff},
+        result::Config,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::fmt,
+};
+
+/// The outcome of a single check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct CheckOutcome {
+    /// The name of the check, ie. `account_lamports`.
+    pub check: String,
+    /// The address of the account checked, if the check targets an account.
+    pub account: Option<String>,
+    /// Whether the check passed.
+    pub passed: bool,
+    /// The expected value, if applicable.
+    pub expected: Option<String>,
+    /// The actual value, if applicable.
+    pub actual: Option<String>,
+    /// Additional details, such as a data diff or an error location.
+    pub detail: Option<String>,
+}
+
+impl fmt::Display for CheckOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passed {
+            write!(f, "CHECK PASSED: {}", self.check)?;
+        } else {
+            write!(f, "CHECK FAILED: {}", self.check)?;
+        }
+        if let Some(account) = &self.account {
+            write!(f, "\n  Account: {}", account)?;
+        }
+        if let Some(detail) = &self.detail {
+            write!(f, "\n  {}", detail.trim_end().replace('\n', "\n  "))?;
+        }
+        if let Some(expected) = &self.expected {
+            write!(f, "\n  Expected: `{}`,", expected)?;
+        }
+        if let Some(actual) = &self.actual {
+            write!(f, "\n Got: `{}`", actual)?;
+        }
+        Ok(())
+    }
+}
+
+/// A report of every check run against an instruction result.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct CheckReport {
+    /// The outcome of each check, in the order they were run.
+    pub outcomes: Vec<CheckOutcome>,
+}
+
+impl CheckReport {
+    /// Whether every check passed.
+    pub fn passed(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.passed)
+    }
+
+    /// The checks which failed.
+    pub fn failures(&self) -> impl Iterator<Item = &CheckOutcome> {
+        self.outcomes.iter().filter(|outcome| !outcome.passed)
+    }
+
+    /// Append the outcomes of another report.
+    pub fn extend(&mut self, other: CheckReport) {
+        self.outcomes.extend(other.outcomes);
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failures = self.failures().count();
+        writeln!(
+            f,
+            "{} of {} checks passed",
+            self.outcomes.len() - failures,
+            self.outcomes.len()
+        )?;
+        for failure in self.failures() {
+            writeln!(f, "{}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+/// The message for a check which failed with a diff.
+fn diff_failure<T>(check: &str, account: &Pubkey, diff: &T) -> String
+where
+    T: fmt::Display,
+    for<'a> Colored<'a, T>: fmt::Display,
+{
+    format!(
+        "CHECK FAILED: {}\n  Account: {}\n  (- expected, + got)\n{}",
+        check,
+        account,
+        diff::render_for_stderr(diff)
+    )
+}
+
+/// Records check outcomes into a report, panicking or printing failures as
+/// dictated by the provided `Config`.
+pub(crate) struct Reporter<'c> {
+    config: &'c Config,
+    report: CheckReport,
+}
+
+impl<'c> Reporter<'c> {
+    pub(crate) fn new(config: &'c Config) -> Self {
+        Self {
+            config,
+            report: CheckReport::default(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> CheckReport {
+        self.report
+    }
+
+    fn record(&mut self, outcome: CheckOutcome, message: impl FnOnce(&CheckOutcome) -> String) {
+        if !outcome.passed {
+            if self.config.panic {
+                panic!("{}", message(&outcome));
+            } else if self.config.verbose {
+                eprintln!("{}", message(&outcome));
+            }
+        }
+        self.report.outcomes.push(outcome);
+    }
+
+    /// Compare an expected value against an actual value.
+    pub(crate) fn compare<T: PartialEq + fmt::Debug>(
+        &mut self,
+        check: &str,
+        account: Option<&Pubkey>,
+        expected: T,
+        actual: T,
+    ) {
+        self.compare_with_detail(check, account, None, expected, actual)
+    }
+
+    /// Compare an expected value against an actual value, attaching details
+    /// to the outcome.
+    pub(crate) fn compare_with_detail<T: PartialEq + fmt::Debug>(
+        &mut self,
+        check: &str,
+        account: Option<&Pubkey>,
+        detail: Option<String>,
+        expected: T,
+        actual: T,
+    ) {
+        let outcome = CheckOutcome {
+            check: check.to_string(),
+            account: account.map(|pubkey| pubkey.to_string()),
+            passed: expected == actual,
+            expected: Some(format!("{:?}", expected)),
+            actual: Some(format!("{:?}", actual)),
+            detail,
+        };
+        self.record(outcome, CheckOutcome::to_string);
+    }
+
+    /// Record a check whose outcome has already been determined, ie. one
+    /// comparing against a range rather than an exact value.
+    pub(crate) fn check(
+        &mut self,
+        check: &str,
+        account: Option<&Pubkey>,
+        passed: bool,
+        expected: String,
+        actual: String,
+    ) {
+        let outcome = CheckOutcome {
+            check: check.to_string(),
+            account: account.map(|pubkey| pubkey.to_string()),
+            passed,
+            expected: Some(expected),
+            actual: Some(actual),
+            detail: None,
+        };
+        self.record(outcome, CheckOutcome::to_string);
+    }
+
+    /// Compare expected account data against actual account data, reporting
+    /// any mismatch as a diff. Diff offsets are shifted by `offset`.
+    pub(crate) fn compare_data(
+        &mut self,
+        check: &str,
+        account: &Pubkey,
+        offset: usize,
+        expected: &[u8],
+        actual: &[u8],
+    ) {
+        let diff = DataDiff::new(expected, actual).map(|mut diff| {
+            diff.ranges
+                .iter_mut()
+                .for_each(|range| range.offset += offset);
+            diff
+        });
+        let outcome = CheckOutcome {
+            check: check.to_string(),
+            account: Some(account.to_string()),
+            passed: diff.is_none(),
+            expected: None,
+            actual: None,
+            detail: diff
+                .as_ref()
+                .map(|diff| format!("(- expected, + got)\n{}", diff)),
+        };
+        self.record(outcome, |_| diff_failure(check, account, &diff.unwrap()));
+    }
+
+    /// Compare an expected account state against the state deserialized from
+    /// the account, reporting any mismatch as a field-level diff.
+    pub(crate) fn compare_state<T: fmt::Debug + PartialEq>(
+        &mut self,
+        check: &str,
+        account: &Pubkey,
+        expected: &T,
+        actual: Result<T, String>,
+    ) {
+        let actual = match actual {
+            Ok(actual) => actual,
+            Err(err) => return self.fail_deserialize(check, account, err),
+        };
+        let diff = StateDiff::new(expected, &actual);
+        let outcome = CheckOutcome {
+            check: check.to_string(),
+            account: Some(account.to_string()),
+            passed: diff.is_none(),
+            expected: Some(format!("{:?}", expected)),
+            actual: Some(format!("{:?}", actual)),
+            detail: diff
+                .as_ref()
+                .map(|diff| format!("(- expected, + got)\n{}", diff)),
+        };
+        self.record(outcome, |_| diff_failure(check, account, &diff.unwrap()));
+    }
+
+    /// Check the state deserialized from the account against a predicate.
+    pub(crate) fn match_state<T: fmt::Debug>(
+        &mut self,
+        check: &str,
+        account: &Pubkey,
+        predicate: impl FnOnce(&T) -> bool,
+        actual: Result<T, String>,
+    ) {
+        let actual = match actual {
+            Ok(actual) => actual,
+            Err(err) => return self.fail_deserialize(check, account, err),
+        };
+        let passed = predicate(&actual);
+        let outcome = CheckOutcome {
+            check: check.to_string(),
+            account: Some(account.to_string()),
+            passed,
+            expected: None,
+            actual: Some(format!("{:?}", actual)),
+            detail: (!passed).then(|| "State does not match predicate".to_string()),
+        };
+        self.record(outcome, CheckOutcome::to_string);
+    }
+
+    fn fail_deserialize(&mut self, check: &str, account: &Pubkey, err: String) {
+        let outcome = CheckOutcome {
+            check: check.to_string(),
+            account: Some(account.to_string()),
+            passed: false,
+            expected: None,
+            actual: None,
+            detail: Some(format!("Failed to deserialize account state: {}", err)),
+        };
+        self.record(outcome, CheckOutcome::to_string);
+    }
+
+    /// Record the outcomes of a nested report, prefixing each check's name
+    /// and appending a hint to failures.
+    #[cfg(feature = "serde")]
+    pub(crate) fn absorb(&mut self, prefix: &str, report: CheckReport, hint: &str) {
+        for mut outcome in report.outcomes {
+            outcome.check = format!("{}: {}", prefix, outcome.check);
+            self.record(outcome, |outcome| format!("{}\n{}", outcome, hint));
+        }
+    }
+
+    /// Record a check which failed outright.
+    pub(crate) fn fail(&mut self, check: &str, account: Option<&Pubkey>, detail: String) {
+        let outcome = CheckOutcome {
+            check: check.to_string(),
+            account: account.map(|pubkey| pubkey.to_string()),
+            passed: false,
+            expected: None,
+            actual: None,
+            detail: Some(detail),
+        };
+        self.record(outcome, |outcome| outcome.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::result::{Check, InstructionResult},
+        solana_sdk::account::Account,
+    };
+
+    #[test]
+    fn test_check_report() {
+        let key = Pubkey::new_unique();
+        let result = InstructionResult {
+            compute_units_consumed: 100,
+            resulting_accounts: vec![(key, Account::new(42, 4, &Pubkey::default()))],
+            ..Default::default()
+        };
+
+        let report = result.run_checks_with_report(
+            &[
+                Check::success(),
+                Check::compute_units(200),
+                Check::account(&key)
+                    .lamports(42)
+                    .data(&[0, 1, 0, 0])
+                    .build(),
+            ],
+            &Config::default(),
+        );
+
+        assert!(!report.passed());
+        assert_eq!(report.outcomes.len(), 4);
+
+        let failures = report.failures().collect::<Vec<_>>();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(
+            failures[0],
+            &CheckOutcome {
+                check: "compute_units".to_string(),
+                account: None,
+                passed: false,
+                expected: Some("200".to_string()),
+                actual: Some("100".to_string()),
+                detail: None,
+            }
+        );
+        assert_eq!(failures[1].check, "account_data");
+        assert_eq!(failures[1].account, Some(key.to_string()));
+        assert_eq!(
+            failures[1].detail.as_deref(),
+            Some(
+                "(- expected, + got)\n  data [0x0001..0x0002]:\n    - 0x0001: 01\n    + 0x0001: \
+                 00\n"
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "CHECK FAILED: compute_units")]
+    fn test_check_report_panic() {
+        InstructionResult::default().run_checks(&[Check::compute_units(1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "CHECK FAILED: account_exists")]
+    fn test_check_report_fail_panic() {
+        InstructionResult::default().run_checks(&[Check::account(&Pubkey::new_unique()).build()]);
+    }
+}
@@ -1,48 +1,26 @@
 //! Results of Mollusk program execution.
 
-use solana_sdk::{
-    account::{Account, ReadableAccount},
-    instruction::InstructionError,
-    program_error::ProgramError,
-    pubkey::Pubkey,
+use {
+    crate::{
+        diff::Diff,
+        invariant::InvariantViolation,
+        location::ErrorLocation,
+        report::{CheckReport, Reporter},
+        state::AccountState,
+    },
+    solana_sdk::{
+        account::{Account, ReadableAccount},
+        instruction::{Instruction, InstructionError},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        transaction::TransactionError,
+    },
+    std::ops::{Bound, RangeBounds},
 };
 
-macro_rules! compare {
-    ($c:expr, $check:expr, $left:expr, $right:expr $(,)?) => {{
-        if $left != $right {
-            let msg = format!(
-                "CHECK FAILED: {}\n  Expected: `{:?}`,\n Got: `{:?}`",
-                $check, $left, $right
-            );
-            if $c.panic {
-                panic!("{}", msg);
-            } else {
-                if $c.verbose {
-                    eprintln!("{}", msg);
-                }
-                return false;
-            }
-        }
-        true
-    }};
-}
-
-macro_rules! throw {
-    ($c:expr, $($arg:tt)+) => {{
-        let msg = format!($($arg)+);
-        if $c.panic {
-            panic!("{}", msg);
-        } else {
-            if $c.verbose {
-                eprintln!("{}", msg);
-            }
-        }
-        false
-    }};
-}
-
 /// The result code of the program's execution.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ProgramResult {
     /// The program executed successfully.
     Success,
@@ -50,6 +28,14 @@ pub enum ProgramResult {
     Failure(ProgramError),
     /// Mollusk encountered an error while executing the program.
     UnknownError(InstructionError),
+    /// The program executed successfully, but the runtime would reject the
+    /// transaction, ie. for an invalid rent state transition.
+    ///
+    /// Note: This variant was added alongside rent state enforcement, so
+    /// exhaustive matches on `ProgramResult` must now handle it. Fixture
+    /// effects can't record it, so building fixture effects from such a
+    /// result returns the transaction error.
+    TransactionError(TransactionError),
 }
 
 impl ProgramResult {
@@ -74,19 +60,45 @@ impl From<Result<(), InstructionError>> for ProgramResult {
     }
 }
 
+/// An instruction invoked by a program through CPI.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InnerInstruction {
+    /// The invoked instruction.
+    pub instruction: Instruction,
+    /// The invocation stack height, starting at 2 for instructions invoked
+    /// directly by the top-level program.
+    pub stack_height: u8,
+}
+
 /// The overall result of the instruction.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct InstructionResult {
     /// The number of compute units consumed by the instruction.
     pub compute_units_consumed: u64,
+    /// The invariants violated by the instruction, if any.
+    pub invariant_violations: Vec<InvariantViolation>,
+    /// Where the failing program stopped executing, if the instruction
+    /// failed.
+    pub error_location: Option<ErrorLocation>,
     /// The time taken to execute the instruction.
     pub execution_time: u64,
     /// The result code of the program's execution.
     pub program_result: ProgramResult,
     /// The raw result of the program's execution.
+    ///
+    /// Unlike `program_result`, this doesn't reflect failures after the
+    /// program's execution, such as rejected rent state transitions, which
+    /// are transaction errors rather than instruction errors.
     pub raw_result: Result<(), InstructionError>,
     /// The return data produced by the instruction, if any.
     pub return_data: Vec<u8>,
+    /// The program that set the return data, or the default pubkey if none
+    /// did.
+    pub return_data_program_id: Pubkey,
+    /// The log messages emitted while executing the instruction.
+    pub logs: Vec<String>,
+    /// The instructions invoked through CPI, in the order they were invoked.
+    pub inner_instructions: Vec<InnerInstruction>,
     /// The resulting accounts after executing the instruction.
     ///
     /// This includes all accounts provided to the processor, in the order
@@ -99,10 +111,15 @@ impl Default for InstructionResult {
     fn default() -> Self {
         Self {
             compute_units_consumed: 0,
+            invariant_violations: vec![],
+            error_location: None,
             execution_time: 0,
             program_result: ProgramResult::Success,
             raw_result: Ok(()),
             return_data: vec![],
+            return_data_program_id: Pubkey::default(),
+            logs: vec![],
+            inner_instructions: vec![],
             resulting_accounts: vec![],
         }
     }
@@ -123,98 +140,218 @@ impl InstructionResult {
             .map(|(_, a)| a)
     }
 
-    /// Perform checks on the instruction result.
+    /// Diff the resulting accounts against those of another result, ie.
+    /// the changes going from `self` to `other`.
+    pub fn diff(&self, other: &Self) -> Diff {
+        Diff::new(&self.resulting_accounts, &other.resulting_accounts)
+    }
+
+    /// Diff the resulting accounts against the accounts provided as inputs
+    /// to the instruction, ie. the changes made by the instruction.
+    pub fn diff_against_inputs(&self, accounts: &[(Pubkey, Account)]) -> Diff {
+        Diff::new(accounts, &self.resulting_accounts)
+    }
+
+    /// Perform checks on the instruction result, returning whether every
+    /// check passed.
+    ///
+    /// Panics on checks relative to the instruction's input accounts, such
+    /// as `lamports_increased_by`, since the inputs aren't known. Use
+    /// `run_checks_with_inputs` instead.
     pub fn run_checks_with_config(&self, checks: &[Check], config: &Config) -> bool {
-        let c = config;
-        let mut pass = true;
+        self.run_checks_with_report(checks, config).passed()
+    }
+
+    /// Perform checks on the instruction result, reporting the outcome of
+    /// each check.
+    ///
+    /// Like `run_checks_with_config`, panics on checks relative to the
+    /// instruction's input accounts, which require `run_checks_with_inputs`.
+    pub fn run_checks_with_report(&self, checks: &[Check], config: &Config) -> CheckReport {
+        assert!(
+            !checks.iter().any(Check::requires_inputs),
+            "Checks relative to the instruction's input accounts, such as \
+             `lamports_increased_by`, require inputs. Use `run_checks_with_inputs` instead."
+        );
+        self.run_checks_with_inputs(checks, &[], config)
+    }
+
+    /// Perform checks on the instruction result, given the accounts provided
+    /// as inputs to the instruction, reporting the outcome of each check.
+    pub fn run_checks_with_inputs(
+        &self,
+        checks: &[Check],
+        inputs: &[(Pubkey, Account)],
+        config: &Config,
+    ) -> CheckReport {
+        let mut r = Reporter::new(config);
+        for violation in &self.invariant_violations {
+            r.fail(
+                "invariant",
+                None,
+                format!(
+                    "INVARIANT VIOLATED: {}\n  {}",
+                    violation.invariant, violation.message
+                ),
+            );
+        }
         for check in checks {
             match &check.check {
                 CheckType::ComputeUnitsConsumed(units) => {
                     let check_units = *units;
                     let actual_units = self.compute_units_consumed;
-                    pass &= compare!(c, "compute_units", check_units, actual_units);
+                    r.compare("compute_units", None, check_units, actual_units);
+                }
+                CheckType::ComputeUnitsWithin(bounds) => {
+                    let actual_units = self.compute_units_consumed;
+                    r.check(
+                        "compute_units",
+                        None,
+                        bounds.contains(&actual_units),
+                        describe_bounds(bounds),
+                        actual_units.to_string(),
+                    );
                 }
                 CheckType::ExecutionTime(time) => {
                     let check_time = *time;
                     let actual_time = self.execution_time;
-                    pass &= compare!(c, "execution_time", check_time, actual_time);
+                    r.compare("execution_time", None, check_time, actual_time);
                 }
                 CheckType::ProgramResult(result) => {
                     let check_result = result;
                     let actual_result = &self.program_result;
-                    pass &= compare!(c, "program_result", check_result, actual_result);
+                    let location = self
+                        .error_location
+                        .as_ref()
+                        .map(|location| format!("Location: {}", location));
+                    r.compare_with_detail(
+                        "program_result",
+                        None,
+                        location,
+                        check_result,
+                        actual_result,
+                    );
                 }
                 CheckType::ReturnData(return_data) => {
-                    let check_return_data = return_data;
-                    let actual_return_data = &self.return_data;
-                    pass &= compare!(c, "return_data", check_return_data, actual_return_data);
+                    let check_return_data = *return_data;
+                    let actual_return_data = self.return_data.as_slice();
+                    r.compare("return_data", None, check_return_data, actual_return_data);
+                }
+                #[cfg(feature = "serde")]
+                CheckType::Snapshot(name) => crate::snapshot::check(self, name, &mut r),
+                CheckType::Custom(check) => {
+                    let passed = check(self);
+                    r.check("custom", None, passed, true.to_string(), passed.to_string());
                 }
                 CheckType::ResultingAccount(account) => {
                     let pubkey = account.pubkey;
+                    let key = Some(&pubkey);
                     let Some(resulting_account) = self
                         .resulting_accounts
                         .iter()
                         .find(|(k, _)| k == &pubkey)
                         .map(|(_, a)| a)
                     else {
-                        pass &= throw!(c, "Account not found in resulting accounts: {}", pubkey);
+                        r.fail(
+                            "account_exists",
+                            key,
+                            format!("Account not found in resulting accounts: {}", pubkey),
+                        );
                         continue;
                     };
                     if let Some(check_data) = account.check_data {
                         let actual_data = resulting_account.data();
-                        pass &= compare!(c, "account_data", check_data, actual_data);
+                        r.compare_data("account_data", &pubkey, 0, check_data, actual_data);
                     }
                     if let Some(check_executable) = account.check_executable {
                         let actual_executable = resulting_account.executable();
-                        pass &=
-                            compare!(c, "account_executable", check_executable, actual_executable);
+                        r.compare(
+                            "account_executable",
+                            key,
+                            check_executable,
+                            actual_executable,
+                        );
                     }
                     if let Some(check_lamports) = account.check_lamports {
                         let actual_lamports = resulting_account.lamports();
-                        pass &= compare!(c, "account_lamports", check_lamports, actual_lamports);
+                        r.compare("account_lamports", key, check_lamports, actual_lamports);
+                    }
+                    if let Some(check_delta) = account.check_lamports_delta {
+                        match inputs.iter().find(|(k, _)| k == &pubkey) {
+                            Some((_, input_account)) => {
+                                let actual_delta = resulting_account.lamports() as i128
+                                    - input_account.lamports() as i128;
+                                r.compare("account_lamports_delta", key, check_delta, actual_delta);
+                            }
+                            None => r.fail(
+                                "account_lamports_delta",
+                                key,
+                                format!("Account not found in input accounts: {}", pubkey),
+                            ),
+                        }
                     }
                     if let Some(check_owner) = account.check_owner {
                         let actual_owner = resulting_account.owner();
-                        pass &= compare!(c, "account_owner", check_owner, actual_owner);
+                        r.compare("account_owner", key, check_owner, actual_owner);
                     }
                     if let Some(check_space) = account.check_space {
                         let actual_space = resulting_account.data().len();
-                        pass &= compare!(c, "account_space", check_space, actual_space);
+                        r.compare("account_space", key, check_space, actual_space);
                     }
                     if let Some(check_state) = &account.check_state {
                         match check_state {
                             AccountStateCheck::Closed => {
-                                pass &= compare!(
-                                    c,
+                                r.compare(
                                     "account_closed",
+                                    key,
                                     true,
                                     resulting_account == &Account::default(),
                                 );
                             }
                         }
                     }
+                    for check_typed_state in &account.check_typed_states {
+                        check_typed_state(&mut r, &pubkey, resulting_account.data());
+                    }
                     if let Some((offset, check_data_slice)) = account.check_data_slice {
                         let actual_data = resulting_account.data();
                         if offset + check_data_slice.len() > actual_data.len() {
-                            pass &= throw!(
-                                c,
-                                "Account data slice: offset {} + slice length {} exceeds account \
-                                 data length {}",
-                                offset,
-                                check_data_slice.len(),
-                                actual_data.len(),
+                            r.fail(
+                                "account_data_slice",
+                                key,
+                                format!(
+                                    "Account data slice: offset {} + slice length {} exceeds \
+                                     account data length {}",
+                                    offset,
+                                    check_data_slice.len(),
+                                    actual_data.len(),
+                                ),
                             );
                             continue;
                         }
                         let actual_data_slice =
                             &actual_data[offset..offset + check_data_slice.len()];
-                        pass &=
-                            compare!(c, "account_data_slice", check_data_slice, actual_data_slice,);
+                        // Report offsets within the account data.
+                        r.compare_data(
+                            "account_data_slice",
+                            &pubkey,
+                            offset,
+                            check_data_slice,
+                            actual_data_slice,
+                        );
                     }
                 }
             }
         }
-        pass
+        r.finish()
+    }
+
+    /// Compare the instruction result against the named snapshot, stored
+    /// under `tests/snapshots`, panicking on any mismatches. See the
+    /// `snapshot` module.
+    #[cfg(feature = "serde")]
+    pub fn assert_snapshot(&self, name: &str) {
+        self.run_checks(&[Check::snapshot(name)]);
     }
 
     /// Perform checks on the instruction result, panicking on any mismatches.
@@ -230,10 +367,15 @@ impl InstructionResult {
 
     pub(crate) fn absorb(&mut self, other: Self) {
         self.compute_units_consumed += other.compute_units_consumed;
+        self.invariant_violations.extend(other.invariant_violations);
+        self.error_location = other.error_location;
         self.execution_time += other.execution_time;
         self.program_result = other.program_result;
         self.raw_result = other.raw_result;
         self.return_data = other.return_data;
+        self.return_data_program_id = other.return_data_program_id;
+        self.logs.extend(other.logs);
+        self.inner_instructions.extend(other.inner_instructions);
         self.resulting_accounts = other.resulting_accounts;
     }
 
@@ -243,73 +385,146 @@ impl InstructionResult {
         addresses: &[Pubkey],
         ignore_addresses: &[Pubkey],
         fields: CompareAccountFields,
-        config: &Config,
-    ) -> bool {
-        let c = config;
-        let mut pass = true;
+        r: &mut Reporter,
+    ) {
         for (a, b) in self
             .resulting_accounts
             .iter()
             .zip(b.resulting_accounts.iter())
         {
             if addresses.contains(&a.0) && !ignore_addresses.contains(&a.0) {
+                let key = Some(&a.0);
                 if fields.data {
-                    pass &= compare!(c, "resulting_account_data", a.1.data(), b.1.data());
+                    r.compare_data("resulting_account_data", &a.0, 0, a.1.data(), b.1.data());
                 }
                 if fields.executable {
-                    pass &= compare!(
-                        c,
+                    r.compare(
                         "resulting_account_executable",
+                        key,
                         a.1.executable(),
-                        b.1.executable()
+                        b.1.executable(),
                     );
                 }
                 if fields.lamports {
-                    pass &= compare!(
-                        c,
+                    r.compare(
                         "resulting_account_lamports",
+                        key,
                         a.1.lamports(),
-                        b.1.lamports()
+                        b.1.lamports(),
                     );
                 }
                 if fields.owner {
-                    pass &= compare!(c, "resulting_account_owner", a.1.owner(), b.1.owner());
+                    r.compare("resulting_account_owner", key, a.1.owner(), b.1.owner());
                 }
                 if fields.space {
-                    pass &= compare!(
-                        c,
+                    r.compare(
                         "resulting_account_space",
+                        key,
                         a.1.data().len(),
-                        b.1.data().len()
+                        b.1.data().len(),
                     );
                 }
             }
         }
-        pass
     }
 
-    /// Compare an `InstructionResult` against another `InstructionResult`.
+    /// Compare an `InstructionResult` against another `InstructionResult`,
+    /// returning whether every check passed.
     pub fn compare_with_config(&self, b: &Self, checks: &[Compare], config: &Config) -> bool {
-        let c = config;
-        let mut pass = true;
+        self.compare_with_report(b, checks, config).passed()
+    }
+
+    /// Compare an `InstructionResult` against another `InstructionResult`,
+    /// reporting the outcome of each check.
+    pub fn compare_with_report(
+        &self,
+        b: &Self,
+        checks: &[Compare],
+        config: &Config,
+    ) -> CheckReport {
+        let mut r = Reporter::new(config);
+        self.compare_into(b, checks, &mut r);
+        r.finish()
+    }
+
+    /// Compare an `InstructionResult` against fixture effects of the provided
+    /// version, reporting the outcome of each check.
+    ///
+    /// Unlike `compare_with_report`, checks of fields the effects don't
+    /// record fail, rather than passing unchecked.
+    pub fn compare_with_effects_report(
+        &self,
+        effects: &Self,
+        checks: &[Compare],
+        effects_version: u32,
+        config: &Config,
+    ) -> CheckReport {
+        let mut r = Reporter::new(config);
+        for check in checks {
+            if check.min_effects_version() > effects_version {
+                r.fail(
+                    check.name(),
+                    None,
+                    format!(
+                        "Not recorded by version {} fixture effects",
+                        effects_version
+                    ),
+                );
+            } else {
+                self.compare_into(effects, std::slice::from_ref(check), &mut r);
+            }
+        }
+        r.finish()
+    }
+
+    pub(crate) fn compare_into(&self, b: &Self, checks: &[Compare], r: &mut Reporter) {
         for check in checks {
             match check {
                 Compare::ComputeUnits => {
-                    pass &= compare!(
-                        c,
+                    r.compare(
                         "compute_units_consumed",
+                        None,
                         self.compute_units_consumed,
-                        b.compute_units_consumed
+                        b.compute_units_consumed,
                     );
                 }
                 Compare::ExecutionTime => {
-                    pass &= compare!(c, "execution_time", self.execution_time, b.execution_time);
+                    r.compare(
+                        "execution_time",
+                        None,
+                        self.execution_time,
+                        b.execution_time,
+                    );
                 }
                 Compare::ProgramResult => {
-                    pass &= compare!(c, "program_result", self.program_result, b.program_result);
+                    r.compare(
+                        "program_result",
+                        None,
+                        &self.program_result,
+                        &b.program_result,
+                    );
                 }
                 Compare::ReturnData => {
-                    pass &= compare!(c, "return_data", self.return_data, b.return_data);
+                    r.compare("return_data", None, &self.return_data, &b.return_data);
+                }
+                Compare::ReturnDataProgramId => {
+                    r.compare(
+                        "return_data_program_id",
+                        None,
+                        self.return_data_program_id,
+                        b.return_data_program_id,
+                    );
+                }
+                Compare::Logs => {
+                    r.compare("logs", None, &self.logs, &b.logs);
+                }
+                Compare::InnerInstructions => {
+                    r.compare(
+                        "inner_instructions",
+                        None,
+                        &self.inner_instructions,
+                        &b.inner_instructions,
+                    );
                 }
                 Compare::AllResultingAccounts {
                     data,
@@ -318,18 +533,18 @@ impl InstructionResult {
                     owner,
                     space,
                 } => {
-                    pass &= compare!(
-                        c,
+                    r.compare(
                         "resulting_accounts_length",
+                        None,
                         self.resulting_accounts.len(),
-                        b.resulting_accounts.len()
+                        b.resulting_accounts.len(),
                     );
                     let addresses = self
                         .resulting_accounts
                         .iter()
                         .map(|(k, _)| *k)
                         .collect::<Vec<_>>();
-                    pass &= self.compare_resulting_accounts(
+                    self.compare_resulting_accounts(
                         b,
                         &addresses,
                         &[],
@@ -340,7 +555,7 @@ impl InstructionResult {
                             owner: *owner,
                             space: *space,
                         },
-                        c,
+                        r,
                     );
                 }
                 Compare::OnlyResultingAccounts {
@@ -351,7 +566,7 @@ impl InstructionResult {
                     owner,
                     space,
                 } => {
-                    pass &= self.compare_resulting_accounts(
+                    self.compare_resulting_accounts(
                         b,
                         addresses,
                         &[],
@@ -362,7 +577,7 @@ impl InstructionResult {
                             owner: *owner,
                             space: *space,
                         },
-                        c,
+                        r,
                     );
                 }
                 Compare::AllResultingAccountsExcept {
@@ -378,7 +593,7 @@ impl InstructionResult {
                         .iter()
                         .map(|(k, _)| *k)
                         .collect::<Vec<_>>();
-                    pass &= self.compare_resulting_accounts(
+                    self.compare_resulting_accounts(
                         b,
                         &addresses,
                         ignore_addresses,
@@ -389,12 +604,11 @@ impl InstructionResult {
                             owner: *owner,
                             space: *space,
                         },
-                        c,
+                        r,
                     );
                 }
             }
         }
-        pass
     }
 
     /// Compare an `InstructionResult` against another `InstructionResult`,
@@ -414,6 +628,9 @@ impl InstructionResult {
 enum CheckType<'a> {
     /// Check the number of compute units consumed by the instruction.
     ComputeUnitsConsumed(u64),
+    /// Check the number of compute units consumed by the instruction falls
+    /// within a range.
+    ComputeUnitsWithin((Bound<u64>, Bound<u64>)),
     /// Check the time taken to execute the instruction.
     ExecutionTime(u64),
     /// Check the result code of the program's execution.
@@ -422,6 +639,26 @@ enum CheckType<'a> {
     ReturnData(&'a [u8]),
     /// Check a resulting account after executing the instruction.
     ResultingAccount(AccountCheck<'a>),
+    /// Check the result against a snapshot.
+    #[cfg(feature = "serde")]
+    Snapshot(&'a str),
+    /// Check the result against an arbitrary predicate.
+    Custom(Box<dyn Fn(&InstructionResult) -> bool + 'a>),
+}
+
+/// Describe a range of values, ie. `..=100`.
+fn describe_bounds(bounds: &(Bound<u64>, Bound<u64>)) -> String {
+    let start = match bounds.0 {
+        Bound::Included(start) => start.to_string(),
+        Bound::Excluded(start) => format!("{}<", start),
+        Bound::Unbounded => String::new(),
+    };
+    let end = match bounds.1 {
+        Bound::Included(end) => format!("={}", end),
+        Bound::Excluded(end) => end.to_string(),
+        Bound::Unbounded => String::new(),
+    };
+    format!("{}..{}", start, end)
 }
 
 pub struct Check<'a> {
@@ -429,6 +666,14 @@ pub struct Check<'a> {
 }
 
 impl<'a> Check<'a> {
+    /// Whether the check is relative to the instruction's input accounts.
+    fn requires_inputs(&self) -> bool {
+        matches!(
+            &self.check,
+            CheckType::ResultingAccount(account) if account.check_lamports_delta.is_some()
+        )
+    }
+
     fn new(check: CheckType<'a>) -> Self {
         Self { check }
     }
@@ -438,6 +683,27 @@ impl<'a> Check<'a> {
         Check::new(CheckType::ComputeUnitsConsumed(units))
     }
 
+    /// Check the number of compute units consumed by the instruction does
+    /// not exceed `max`.
+    pub fn compute_units_at_most(max: u64) -> Self {
+        Check::compute_units_within(..=max)
+    }
+
+    /// Check the number of compute units consumed by the instruction is at
+    /// least `min`.
+    pub fn compute_units_at_least(min: u64) -> Self {
+        Check::compute_units_within(min..)
+    }
+
+    /// Check the number of compute units consumed by the instruction falls
+    /// within a range.
+    pub fn compute_units_within(range: impl RangeBounds<u64>) -> Self {
+        Check::new(CheckType::ComputeUnitsWithin((
+            range.start_bound().cloned(),
+            range.end_bound().cloned(),
+        )))
+    }
+
     /// Check the time taken to execute the instruction.
     pub fn time(time: u64) -> Self {
         Check::new(CheckType::ExecutionTime(time))
@@ -458,6 +724,14 @@ impl<'a> Check<'a> {
         Check::new(CheckType::ProgramResult(ProgramResult::UnknownError(error)))
     }
 
+    /// Assert that the runtime would reject the transaction containing the
+    /// instruction with an error.
+    pub fn transaction_err(error: TransactionError) -> Self {
+        Check::new(CheckType::ProgramResult(ProgramResult::TransactionError(
+            error,
+        )))
+    }
+
     /// Assert that the instruction returned the provided result.
     pub fn program_result(result: ProgramResult) -> Self {
         Check::new(CheckType::ProgramResult(result))
@@ -469,24 +743,41 @@ impl<'a> Check<'a> {
     }
 
     /// Check a resulting account after executing the instruction.
-    pub fn account(pubkey: &Pubkey) -> AccountCheckBuilder {
+    pub fn account(pubkey: &Pubkey) -> AccountCheckBuilder<'a> {
         AccountCheckBuilder::new(pubkey)
     }
+
+    /// Check the result against the named snapshot, stored under
+    /// `tests/snapshots`. See the `snapshot` module.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(name: &'a str) -> Self {
+        Check::new(CheckType::Snapshot(name))
+    }
+
+    /// Check the result against an arbitrary predicate, which returns `true`
+    /// if the check passes.
+    pub fn custom(check: impl Fn(&InstructionResult) -> bool + 'a) -> Self {
+        Check::new(CheckType::Custom(Box::new(check)))
+    }
 }
 
 enum AccountStateCheck {
     Closed,
 }
 
+type StateCheck<'a> = Box<dyn Fn(&mut Reporter, &Pubkey, &[u8]) + 'a>;
+
 struct AccountCheck<'a> {
     pubkey: Pubkey,
     check_data: Option<&'a [u8]>,
     check_executable: Option<bool>,
     check_lamports: Option<u64>,
+    check_lamports_delta: Option<i128>,
     check_owner: Option<&'a Pubkey>,
     check_space: Option<usize>,
     check_state: Option<AccountStateCheck>,
     check_data_slice: Option<(usize, &'a [u8])>,
+    check_typed_states: Vec<StateCheck<'a>>,
 }
 
 impl AccountCheck<'_> {
@@ -496,10 +787,12 @@ impl AccountCheck<'_> {
             check_data: None,
             check_executable: None,
             check_lamports: None,
+            check_lamports_delta: None,
             check_owner: None,
             check_space: None,
             check_state: None,
             check_data_slice: None,
+            check_typed_states: Vec::new(),
         }
     }
 }
@@ -535,6 +828,20 @@ impl<'a> AccountCheckBuilder<'a> {
         self
     }
 
+    /// Check the account's lamports increased by `lamports` relative to the
+    /// instruction's input accounts.
+    pub fn lamports_increased_by(mut self, lamports: u64) -> Self {
+        self.check.check_lamports_delta = Some(lamports as i128);
+        self
+    }
+
+    /// Check the account's lamports decreased by `lamports` relative to the
+    /// instruction's input accounts.
+    pub fn lamports_decreased_by(mut self, lamports: u64) -> Self {
+        self.check.check_lamports_delta = Some(-(lamports as i128));
+        self
+    }
+
     pub fn owner(mut self, owner: &'a Pubkey) -> Self {
         self.check.check_owner = Some(owner);
         self
@@ -550,6 +857,37 @@ impl<'a> AccountCheckBuilder<'a> {
         self
     }
 
+    /// Check the account's data, deserialized as `T`, against an expected
+    /// state.
+    pub fn state<T: AccountState>(mut self, expected: &'a T) -> Self {
+        self.check
+            .check_typed_states
+            .push(Box::new(move |r, pubkey, data| {
+                r.compare_state(
+                    "account_state",
+                    pubkey,
+                    expected,
+                    T::from_account_data(data),
+                );
+            }));
+        self
+    }
+
+    /// Check the account's data, deserialized as `T`, against a predicate.
+    pub fn state_matches<T: AccountState>(mut self, predicate: impl Fn(&T) -> bool + 'a) -> Self {
+        self.check
+            .check_typed_states
+            .push(Box::new(move |r, pubkey, data| {
+                r.match_state(
+                    "account_state_matches",
+                    pubkey,
+                    &predicate,
+                    T::from_account_data(data),
+                );
+            }));
+        self
+    }
+
     pub fn build(self) -> Check<'a> {
         Check::new(CheckType::ResultingAccount(self.check))
     }
@@ -569,7 +907,7 @@ struct CompareAccountFields {
 /// to run on two results. This is useful for comparing the results of two
 /// instructions, or for comparing the result of an instruction against a
 /// fixture.
-
+#[derive(Clone)]
 #[cfg_attr(
     feature = "serde",
     derive(serde::Serialize, serde::Deserialize),
@@ -584,6 +922,12 @@ pub enum Compare {
     ProgramResult,
     /// Validate the return data.
     ReturnData,
+    /// Validate the program that set the return data.
+    ReturnDataProgramId,
+    /// Validate the log messages.
+    Logs,
+    /// Validate the instructions invoked through CPI.
+    InnerInstructions,
     /// Validate all resulting accounts.
     AllResultingAccounts {
         /// Whether or not to validate each account's data.
@@ -691,4 +1035,58 @@ impl Compare {
             Self::all_resulting_accounts(),
         ]
     }
+
+    /// Validate everything recorded by the current version of fixture
+    /// effects, which also includes the program that set the return data,
+    /// the log messages, and the instructions invoked through CPI.
+    pub fn all_effects() -> Vec<Self> {
+        let mut checks = Self::everything();
+        checks.extend([
+            Self::ReturnDataProgramId,
+            Self::Logs,
+            Self::InnerInstructions,
+        ]);
+        checks
+    }
+
+    /// The name of the check, as recorded in a `CheckReport`. Checks of
+    /// resulting accounts are recorded under more specific names.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::ComputeUnits => "compute_units_consumed",
+            Self::ExecutionTime => "execution_time",
+            Self::ProgramResult => "program_result",
+            Self::ReturnData => "return_data",
+            Self::ReturnDataProgramId => "return_data_program_id",
+            Self::Logs => "logs",
+            Self::InnerInstructions => "inner_instructions",
+            Self::AllResultingAccounts { .. }
+            | Self::OnlyResultingAccounts { .. }
+            | Self::AllResultingAccountsExcept { .. } => "resulting_accounts",
+        }
+    }
+
+    /// The earliest version of fixture effects recording the compared
+    /// field. Fixtures with effects of an earlier version can't be validated
+    /// with this check.
+    pub fn min_effects_version(&self) -> u32 {
+        match self {
+            Self::ReturnDataProgramId | Self::Logs | Self::InnerInstructions => 1,
+            _ => 0,
+        }
+    }
+
+    /// Keep only the checks which can be validated against fixture effects
+    /// of the provided version, so fixtures recorded by earlier versions
+    /// still pass.
+    ///
+    /// Note: The other checks are dropped without a trace. To have them fail
+    /// instead, compare with `InstructionResult::compare_with_effects_report`.
+    pub fn for_effects_version(checks: &[Self], version: u32) -> Vec<Self> {
+        checks
+            .iter()
+            .filter(|check| check.min_effects_version() <= version)
+            .cloned()
+            .collect()
+    }
 }
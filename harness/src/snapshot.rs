@@ -0,0 +1,292 @@
+//! Module for snapshot (golden file) testing of instruction results.
+//!
+//! A snapshot captures the compute units consumed, program result, return
+//! data, logs, and resulting accounts of an instruction in a readable JSON
+//! file under `tests/snapshots`. Results are compared against the snapshot,
+//! using the same comparisons as `Compare::everything()`, along with the
+//! logs.
+//!
+//! Snapshots are only ever written when `MOLLUSK_UPDATE_SNAPSHOTS=1` is set,
+//! so a missing snapshot fails its check, rather than silently passing in
+//! CI. To write new snapshots, or rewrite existing ones after an intended
+//! change:
+//!
+//! ```ignore
+//! MOLLUSK_UPDATE_SNAPSHOTS=1 cargo test ...
+//! ```
+//!
+//! Note: requires the `serde` feature.
+
+use {
+    crate::{
+        report::Reporter,
+        result::{Compare, Config, InstructionResult, ProgramResult},
+    },
+    solana_sdk::{account::Account, pubkey::Pubkey},
+    std::{
+        fs,
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+};
+
+/// The environment variable which, when set, rewrites snapshots with the
+/// current results.
+pub const UPDATE_SNAPSHOTS_ENV: &str = "MOLLUSK_UPDATE_SNAPSHOTS";
+/// The directory snapshots are stored in, relative to the crate's manifest.
+pub const SNAPSHOT_DIR: &str = "tests/snapshots";
+/// The number of bytes per line of hex-encoded data.
+const BYTES_PER_LINE: usize = 32;
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotAccount {
+    pubkey: String,
+    lamports: u64,
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+    data: Vec<String>,
+}
+
+/// The serialized form of an `InstructionResult`.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    compute_units_consumed: u64,
+    program_result: ProgramResult,
+    return_data: Vec<String>,
+    // Snapshots written before logs were recorded fail the log comparison,
+    // rather than failing to parse.
+    #[serde(default)]
+    logs: Vec<String>,
+    resulting_accounts: Vec<SnapshotAccount>,
+}
+
+fn encode_hex(data: &[u8]) -> Vec<String> {
+    data.chunks(BYTES_PER_LINE)
+        .map(|line| line.iter().map(|byte| format!("{:02x}", byte)).collect())
+        .collect()
+}
+
+fn decode_hex(lines: &[String]) -> Result<Vec<u8>, String> {
+    let hex = lines.concat();
+    // Slicing pairs of bytes would split multi-byte characters.
+    if !hex.is_ascii() {
+        return Err(format!("Invalid hex data: {}", hex));
+    }
+    if hex.len() % 2 != 0 {
+        return Err(format!("Odd-length hex data: {}", hex));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| format!("Invalid hex data: {}", &hex[i..i + 2]))
+        })
+        .collect()
+}
+
+fn decode_pubkey(pubkey: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(pubkey).map_err(|_| format!("Invalid pubkey: {}", pubkey))
+}
+
+impl From<&InstructionResult> for Snapshot {
+    fn from(result: &InstructionResult) -> Self {
+        Self {
+            compute_units_consumed: result.compute_units_consumed,
+            program_result: result.program_result.clone(),
+            return_data: encode_hex(&result.return_data),
+            logs: result.logs.clone(),
+            resulting_accounts: result
+                .resulting_accounts
+                .iter()
+                .map(|(pubkey, account)| SnapshotAccount {
+                    pubkey: pubkey.to_string(),
+                    lamports: account.lamports,
+                    owner: account.owner.to_string(),
+                    executable: account.executable,
+                    rent_epoch: account.rent_epoch,
+                    data: encode_hex(&account.data),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<Snapshot> for InstructionResult {
+    type Error = String;
+
+    fn try_from(snapshot: Snapshot) -> Result<Self, Self::Error> {
+        let resulting_accounts = snapshot
+            .resulting_accounts
+            .iter()
+            .map(|account| {
+                Ok((
+                    decode_pubkey(&account.pubkey)?,
+                    Account {
+                        lamports: account.lamports,
+                        data: decode_hex(&account.data)?,
+                        owner: decode_pubkey(&account.owner)?,
+                        executable: account.executable,
+                        rent_epoch: account.rent_epoch,
+                    },
+                ))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            compute_units_consumed: snapshot.compute_units_consumed,
+            program_result: snapshot.program_result,
+            return_data: decode_hex(&snapshot.return_data)?,
+            logs: snapshot.logs,
+            resulting_accounts,
+            ..Default::default()
+        })
+    }
+}
+
+/// The path of the snapshot with the provided name.
+pub fn snapshot_path(name: &str) -> PathBuf {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    Path::new(&manifest_dir)
+        .join(SNAPSHOT_DIR)
+        .join(format!("{}.json", name))
+}
+
+fn update_snapshots() -> bool {
+    std::env::var(UPDATE_SNAPSHOTS_ENV).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+fn write_snapshot(path: &Path, result: &InstructionResult) -> Result<(), String> {
+    let snapshot = Snapshot::from(result);
+    let mut json = serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())?;
+    json.push('\n');
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, json).map_err(|e| e.to_string())
+}
+
+fn read_snapshot(path: &Path) -> Result<InstructionResult, String> {
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let snapshot: Snapshot = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    InstructionResult::try_from(snapshot)
+}
+
+/// The comparisons of the fields snapshots record.
+fn snapshot_comparisons() -> Vec<Compare> {
+    let mut comparisons = Compare::everything();
+    comparisons.push(Compare::Logs);
+    comparisons
+}
+
+/// Compare a result against the named snapshot, or write the snapshot if
+/// updates are requested.
+pub(crate) fn check(result: &InstructionResult, name: &str, r: &mut Reporter) {
+    let path = snapshot_path(name);
+    let check = format!("snapshot({})", name);
+
+    if update_snapshots() {
+        match write_snapshot(&path, result) {
+            Ok(()) => r.check(
+                &check,
+                None,
+                true,
+                path.display().to_string(),
+                path.display().to_string(),
+            ),
+            Err(err) => r.fail(
+                &check,
+                None,
+                format!("Failed to write snapshot {}: {}", path.display(), err),
+            ),
+        }
+        return;
+    }
+
+    if !path.exists() {
+        return r.fail(
+            &check,
+            None,
+            format!(
+                "Missing snapshot {}. Set {}=1 to write it.",
+                path.display(),
+                UPDATE_SNAPSHOTS_ENV
+            ),
+        );
+    }
+
+    let expected = match read_snapshot(&path) {
+        Ok(expected) => expected,
+        Err(err) => {
+            return r.fail(
+                &check,
+                None,
+                format!("Failed to read snapshot {}: {}", path.display(), err),
+            )
+        }
+    };
+
+    // Compare quietly, then report each comparison as part of the snapshot.
+    let config = Config::default();
+    let mut comparisons = Reporter::new(&config);
+    expected.compare_into(result, &snapshot_comparisons(), &mut comparisons);
+    r.absorb(
+        &check,
+        comparisons.finish(),
+        &format!(
+            "  Snapshot: {}\n  Set {}=1 to update it.",
+            path.display(),
+            UPDATE_SNAPSHOTS_ENV
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let result = InstructionResult {
+            compute_units_consumed: 150,
+            program_result: ProgramResult::Failure(
+                solana_sdk::program_error::ProgramError::Custom(3),
+            ),
+            return_data: vec![1, 2, 3],
+            logs: vec!["Program log: hello".to_string()],
+            resulting_accounts: vec![(
+                Pubkey::new_unique(),
+                Account {
+                    lamports: 42,
+                    data: (0..40).collect(),
+                    owner: Pubkey::new_unique(),
+                    executable: false,
+                    rent_epoch: 7,
+                },
+            )],
+            ..Default::default()
+        };
+
+        let snapshot = Snapshot::from(&result);
+        assert_eq!(snapshot.resulting_accounts[0].data.len(), 2);
+        assert_eq!(snapshot.return_data, vec!["010203"]);
+
+        let json = serde_json::to_string_pretty(&snapshot).unwrap();
+        let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(InstructionResult::try_from(snapshot).unwrap(), result);
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(
+            decode_hex(&["0102".to_string(), "Ff".to_string()]),
+            Ok(vec![1, 2, 255])
+        );
+        assert!(decode_hex(&["010".to_string()]).is_err());
+        assert!(decode_hex(&["0g".to_string()]).is_err());
+        // Non-ASCII characters are rejected rather than split.
+        assert!(decode_hex(&["é0".to_string()]).is_err());
+        assert!(decode_hex(&["0é".to_string(), "0".to_string()]).is_err());
+    }
+}
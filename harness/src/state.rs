@@ -0,0 +1,199 @@
+//! Module for checking deserialized account state.
+//!
+//! Rather than comparing raw account data, or slices of it at hand-computed
+//! offsets, account checks can deserialize the data into the program's own
+//! state type and compare it field by field.
+//!
+//! Any type implementing `AccountState` can be checked. Implementations
+//! usually defer to one of the provided layouts:
+//!
+//! ```rust,ignore
+//! use mollusk_svm::state::{self, AccountState};
+//!
+//! #[derive(BorshDeserialize, Debug, PartialEq)]
+//! struct Counter {
+//!     authority: Pubkey,
+//!     count: u64,
+//! }
+//!
+//! impl AccountState for Counter {
+//!     fn from_account_data(data: &[u8]) -> Result<Self, String> {
+//!         state::borsh(data)
+//!     }
+//! }
+//!
+//! mollusk.process_and_validate_instruction(
+//!     &instruction,
+//!     &accounts,
+//!     &[
+//!         Check::success(),
+//!         Check::account(&counter)
+//!             .state(&Counter { authority, count: 1 })
+//!             .state_matches(|counter: &Counter| counter.count > 0)
+//!             .build(),
+//!     ],
+//! );
+//! ```
+//!
+//! Note: the Borsh, bincode and `Pod` layouts require the `borsh`, `serde`
+//! and `bytemuck` features respectively.
+
+use std::fmt::Debug;
+
+/// An account state type which can be deserialized from account data.
+pub trait AccountState: Debug + PartialEq + Sized {
+    /// Deserialize the state from account data.
+    fn from_account_data(data: &[u8]) -> Result<Self, String>;
+}
+
+/// Deserialize a Borsh-encoded account state. Trailing bytes are ignored.
+///
+/// Note: requires the `borsh` feature.
+#[cfg(feature = "borsh")]
+pub fn borsh<T: borsh::BorshDeserialize>(mut data: &[u8]) -> Result<T, String> {
+    T::deserialize(&mut data).map_err(|e| e.to_string())
+}
+
+/// Deserialize a bincode-encoded account state. Trailing bytes are ignored.
+///
+/// Note: requires the `serde` feature.
+#[cfg(feature = "serde")]
+pub fn bincode<T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T, String> {
+    bincode::deserialize(data).map_err(|e| e.to_string())
+}
+
+/// Read a `Pod` account state from the start of the account data. Trailing
+/// bytes are ignored.
+///
+/// Note: requires the `bytemuck` feature.
+#[cfg(feature = "bytemuck")]
+pub fn pod<T: bytemuck::Pod>(data: &[u8]) -> Result<T, String> {
+    let len = std::mem::size_of::<T>();
+    let data = data.get(..len).ok_or_else(|| {
+        format!(
+            "account data length {} is less than state size {}",
+            data.len(),
+            len
+        )
+    })?;
+    bytemuck::try_pod_read_unaligned(data).map_err(|e| e.to_string())
+}
+
+#[cfg(all(test, feature = "borsh", feature = "bytemuck"))]
+mod tests {
+    use {
+        super::*,
+        crate::result::{Check, Config, InstructionResult},
+        solana_sdk::{account::Account, pubkey::Pubkey},
+    };
+
+    #[derive(borsh::BorshDeserialize, borsh::BorshSerialize, Debug, PartialEq)]
+    struct Counter {
+        authority: [u8; 4],
+        count: u64,
+    }
+
+    impl AccountState for Counter {
+        fn from_account_data(data: &[u8]) -> Result<Self, String> {
+            borsh(data)
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct PodCounter {
+        count: u64,
+    }
+
+    unsafe impl bytemuck::Zeroable for PodCounter {}
+    unsafe impl bytemuck::Pod for PodCounter {}
+
+    impl AccountState for PodCounter {
+        fn from_account_data(data: &[u8]) -> Result<Self, String> {
+            pod(data)
+        }
+    }
+
+    fn result_with_data(key: &Pubkey, data: Vec<u8>) -> InstructionResult {
+        let mut account = Account::new(1, data.len(), &Pubkey::default());
+        account.data = data;
+        InstructionResult {
+            resulting_accounts: vec![(*key, account)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_state() {
+        let key = Pubkey::new_unique();
+        let counter = Counter {
+            authority: [1; 4],
+            count: 7,
+        };
+        let mut data = borsh::to_vec(&counter).unwrap();
+        data.extend_from_slice(&[0; 4]); // Padding.
+        let result = result_with_data(&key, data);
+
+        result.run_checks(&[Check::account(&key)
+            .state(&counter)
+            .state_matches(|counter: &Counter| counter.count > 5)
+            .state(&PodCounter {
+                count: u64::from_le_bytes([1, 1, 1, 1, 7, 0, 0, 0]),
+            })
+            .build()]);
+
+        let report = result.run_checks_with_report(
+            &[Check::account(&key)
+                .state(&Counter {
+                    authority: [1; 4],
+                    count: 8,
+                })
+                .state_matches(|counter: &Counter| counter.count > 10)
+                .build()],
+            &Config::default(),
+        );
+        let failures = report.failures().collect::<Vec<_>>();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].check, "account_state");
+        assert_eq!(
+            failures[0].detail.as_deref(),
+            Some(concat!(
+                "(- expected, + got)\n",
+                "      Counter {\n",
+                "          authority: [\n",
+                "              1,\n",
+                "              1,\n",
+                "              1,\n",
+                "              1,\n",
+                "          ],\n",
+                "    -     count: 8,\n",
+                "    +     count: 7,\n",
+                "      }\n",
+            )),
+        );
+        assert_eq!(failures[1].check, "account_state_matches");
+        assert_eq!(
+            failures[1].actual.as_deref(),
+            Some("Counter { authority: [1, 1, 1, 1], count: 7 }"),
+        );
+    }
+
+    #[test]
+    fn test_state_deserialize_failure() {
+        let key = Pubkey::new_unique();
+        let result = result_with_data(&key, vec![0; 4]);
+
+        let report = result.run_checks_with_report(
+            &[Check::account(&key).state(&PodCounter { count: 0 }).build()],
+            &Config::default(),
+        );
+        assert!(!report.passed());
+        assert_eq!(
+            report.outcomes[0].detail.as_deref(),
+            Some(
+                "Failed to deserialize account state: account data length 4 is less than state \
+                 size 8"
+            ),
+        );
+    }
+}
@@ -404,3 +404,36 @@ fn test_account_dedupe() {
         );
     }
 }
+
+#[test]
+fn test_error_location() {
+    std::env::set_var("SBF_OUT_DIR", "../target/deploy");
+
+    let program_id = Pubkey::new_unique();
+
+    let mollusk = Mollusk::new(&program_id, "test_program_primary");
+
+    let instruction = Instruction::new_with_bytes(program_id, &[6], vec![]);
+
+    let result = mollusk.process_and_validate_instruction(
+        &instruction,
+        &[],
+        &[Check::instruction_err(
+            InstructionError::ProgramFailedToComplete,
+        )],
+    );
+
+    let location = result.error_location.unwrap();
+    assert_eq!(location.program_id, program_id);
+
+    // The line of the program's intentional panic.
+    let line = include_str!("../../test-programs/primary/src/lib.rs")
+        .lines()
+        .position(|line| line.contains("panic!(\"Intentional panic\")"))
+        .unwrap()
+        + 1;
+
+    let panic = location.panic.unwrap();
+    assert!(panic.file.ends_with("src/lib.rs"));
+    assert_eq!(panic.line as usize, line);
+}
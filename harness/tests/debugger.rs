@@ -0,0 +1,148 @@
+#![cfg(feature = "debugger")]
+
+use {
+    mollusk_svm::{
+        debugger::{Debugger, DEBUG_PORT_ENV, DEBUG_PROGRAM_ID_ENV, DEFAULT_DEBUG_PORT},
+        program::loader_keys::LOADER_V2,
+        result::{Config, InstructionResult},
+        Mollusk,
+    },
+    serial_test::serial,
+    solana_sdk::{instruction::Instruction, pubkey::Pubkey},
+    std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+        time::Duration,
+    },
+};
+
+const MEMO: &[u8] = include_bytes!("../../programs/memo/src/elf/memo-v1.so");
+
+fn mollusk(program_id: &Pubkey) -> Mollusk {
+    let mut mollusk = Mollusk {
+        config: Config {
+            panic: false,
+            verbose: true,
+        },
+        ..Default::default()
+    };
+    // Don't pick up a target from the environment.
+    mollusk.debugger = None;
+    mollusk.add_program_with_elf_and_loader(program_id, MEMO, &LOADER_V2);
+    mollusk
+}
+
+fn process(mollusk: &Mollusk, instruction: &Instruction) -> InstructionResult {
+    let mut result = mollusk.process_instruction(instruction, &[]);
+    result.execution_time = 0;
+    result
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// Act as a debugger which connects and immediately continues until the
+/// program exits.
+fn continue_on_connect(port: u16) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut stream = (0..100)
+            .find_map(|_| {
+                TcpStream::connect(("127.0.0.1", port))
+                    .map_err(|_| thread::sleep(Duration::from_millis(100)))
+                    .ok()
+            })
+            .expect("Debugger shim never listened");
+        stream
+            .set_read_timeout(Some(Duration::from_secs(30)))
+            .unwrap();
+        // Acknowledge the connection and send `c` (continue).
+        stream.write_all(b"+$c#63").unwrap();
+        // The stub disconnects once the program exits.
+        let mut replies = vec![];
+        stream.read_to_end(&mut replies).unwrap();
+    })
+}
+
+// Tests which set the environment run serially with the others, which read it
+// through `Mollusk::default`.
+#[test]
+#[serial]
+fn test_from_env() {
+    let program_id = Pubkey::new_unique();
+
+    std::env::remove_var(DEBUG_PROGRAM_ID_ENV);
+    std::env::remove_var(DEBUG_PORT_ENV);
+    assert_eq!(Debugger::from_env(), None);
+
+    std::env::set_var(DEBUG_PROGRAM_ID_ENV, program_id.to_string());
+    assert_eq!(
+        Debugger::from_env(),
+        Some(Debugger {
+            program_id,
+            port: DEFAULT_DEBUG_PORT,
+        })
+    );
+
+    std::env::set_var(DEBUG_PORT_ENV, " 9123 ");
+    assert_eq!(
+        Debugger::from_env(),
+        Some(Debugger {
+            program_id,
+            port: 9123,
+        })
+    );
+
+    // Malformed values are ignored, rather than panicking.
+    std::env::set_var(DEBUG_PORT_ENV, "not a port");
+    assert_eq!(Debugger::from_env(), None);
+    assert_eq!(Mollusk::default().debugger, None);
+    std::env::set_var(DEBUG_PROGRAM_ID_ENV, "not a program ID");
+    std::env::remove_var(DEBUG_PORT_ENV);
+    assert_eq!(Debugger::from_env(), None);
+
+    std::env::remove_var(DEBUG_PROGRAM_ID_ENV);
+    assert_eq!(Debugger::from_env(), None);
+}
+
+#[test]
+#[serial]
+fn test_shim_forwards_other_programs() {
+    let program_id = Pubkey::new_unique();
+    let instruction = Instruction::new_with_bytes(program_id, b"hello", vec![]);
+
+    let expected = process(&mollusk(&program_id), &instruction);
+
+    // Only the target is served, so nothing listens for this one.
+    let mut shimmed = mollusk(&program_id);
+    shimmed.debug_program(&Pubkey::new_unique(), free_port());
+    assert_eq!(process(&shimmed, &instruction), expected);
+}
+
+#[test]
+#[serial]
+fn test_shim_matches_bpf_loader() {
+    let program_id = Pubkey::new_unique();
+
+    // The shim mirrors the BPF loader's execution, so it must produce the
+    // same results, for both successful and failing programs.
+    for data in [&b"hello"[..], &[0xff, 0xfe][..]] {
+        let instruction = Instruction::new_with_bytes(program_id, data, vec![]);
+
+        let expected = process(&mollusk(&program_id), &instruction);
+
+        let port = free_port();
+        let mut shimmed = mollusk(&program_id);
+        shimmed.debug_program(&program_id, port);
+        let debugger = continue_on_connect(port);
+        let result = process(&shimmed, &instruction);
+        debugger.join().unwrap();
+
+        assert_eq!(result, expected);
+    }
+}
@@ -228,6 +228,69 @@ fn test_dump_mollusk() {
     clear(EJECT_FUZZ_FIXTURES);
 }
 
+#[cfg(feature = "fuzz")]
+#[test]
+#[serial]
+fn test_dump_mollusk_chain() {
+    use mollusk_svm_fuzz_fixture::chain::ChainFixture;
+
+    const EJECT_FUZZ_CHAIN_FIXTURES: &str = "./tests/mollusk-chain-fixtures";
+
+    clear(EJECT_FUZZ_CHAIN_FIXTURES);
+    std::env::set_var("EJECT_FUZZ_CHAIN_FIXTURES", EJECT_FUZZ_CHAIN_FIXTURES);
+    std::env::set_var("EJECT_FUZZ_CHAIN_FIXTURES_JSON", EJECT_FUZZ_CHAIN_FIXTURES);
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let mut setup = TestSetup::new(&sender, &recipient);
+
+    let result = setup.mollusk.process_and_validate_instruction_chain(
+        &[
+            (&setup.instruction, &[]),
+            (&setup.instruction, &[]),
+            (&setup.instruction, &[]),
+        ],
+        &setup.accounts,
+    );
+
+    // The whole chain is ejected as a single fixture, in order.
+    let blob_fixture_path = find_fixture(EJECT_FUZZ_CHAIN_FIXTURES, &FileType::Blob).unwrap();
+    let json_fixture_path = find_fixture(EJECT_FUZZ_CHAIN_FIXTURES, &FileType::Json).unwrap();
+    assert_filenames_match(&blob_fixture_path, &json_fixture_path);
+    assert!(Path::new(&blob_fixture_path)
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("chain-"));
+
+    let fixture = ChainFixture::load_from_blob_file(&blob_fixture_path);
+    assert_eq!(
+        fixture,
+        ChainFixture::load_from_json_file(&json_fixture_path)
+    );
+    assert_eq!(fixture.input.compute_budget, setup.mollusk.compute_budget);
+    compare_feature_sets(&fixture.input.feature_set, &setup.mollusk.feature_set);
+    assert_eq!(
+        fixture.input.instructions,
+        vec![setup.instruction.clone(); 3]
+    );
+    assert_eq!(fixture.input.accounts, setup.accounts);
+    assert_eq!(fixture.output.len(), 3);
+
+    // Replaying the fixture reproduces the chain.
+    let results = setup.mollusk.process_chain_fixture(&fixture);
+    assert_eq!(results.len(), 3);
+    assert_eq!(
+        results.last().unwrap().resulting_accounts,
+        result.resulting_accounts
+    );
+
+    std::env::remove_var("EJECT_FUZZ_CHAIN_FIXTURES");
+    std::env::remove_var("EJECT_FUZZ_CHAIN_FIXTURES_JSON");
+    clear(EJECT_FUZZ_CHAIN_FIXTURES);
+}
+
 #[cfg(feature = "fuzz-fd")]
 #[test]
 #[serial]
@@ -69,7 +69,8 @@ fn test_load_firedancer_fixtures() {
                         ..Default::default()
                     };
                     let generated_fixture =
-                        build_fixture_from_mollusk_test(&mollusk, &instruction, &accounts, &result);
+                        build_fixture_from_mollusk_test(&mollusk, &instruction, &accounts, &result)
+                            .unwrap();
 
                     assert_eq!(loaded_fixture.metadata, generated_fixture.metadata);
                     assert_eq!(
@@ -137,8 +138,8 @@ fn compare_accounts(
     let mut b_sorted = b.to_vec();
 
     // Sort by Pubkey
-    a_sorted.sort_by(|(pubkey_a, _, _), (pubkey_b, _, _)| pubkey_a.cmp(pubkey_b));
-    b_sorted.sort_by(|(pubkey_a, _, _), (pubkey_b, _, _)| pubkey_a.cmp(pubkey_b));
+    a_sorted.sort_by_key(|(pubkey, _, _)| *pubkey);
+    b_sorted.sort_by_key(|(pubkey, _, _)| *pubkey);
 
     // Compare sorted lists
     a_sorted == b_sorted
@@ -153,8 +154,8 @@ fn compare_instruction_accounts(a: &[InstructionAccount], b: &[InstructionAccoun
     let mut b_sorted = b.to_vec();
 
     // Sort by Pubkey
-    a_sorted.sort_by(|ia_a, ia_b| ia_a.index_in_transaction.cmp(&ia_b.index_in_transaction));
-    b_sorted.sort_by(|ia_a, ia_b| ia_a.index_in_transaction.cmp(&ia_b.index_in_transaction));
+    a_sorted.sort_by_key(|ia| ia.index_in_transaction);
+    b_sorted.sort_by_key(|ia| ia.index_in_transaction);
 
     // Compare sorted lists
     a_sorted == b_sorted
@@ -0,0 +1,288 @@
+use {
+    mollusk_svm::{
+        program::{create_keyed_account_for_builtin_program, keyed_account_for_system_program},
+        result::{Check, Config, InnerInstruction},
+        Mollusk,
+    },
+    solana_sdk::{
+        account::Account,
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_instruction::{self, SystemError},
+        system_program,
+    },
+};
+
+fn native_mollusk(program_id: &Pubkey) -> Mollusk {
+    let mut mollusk = Mollusk {
+        config: Config {
+            panic: true,
+            verbose: true,
+        },
+        ..Default::default()
+    };
+    mollusk.add_native_program(program_id, test_program_primary::process_instruction);
+    mollusk
+}
+
+#[test]
+fn test_write_data() {
+    let program_id = Pubkey::new_unique();
+
+    let mollusk = native_mollusk(&program_id);
+
+    let data = &[1, 2, 3, 4, 5];
+    let space = data.len();
+    let lamports = mollusk.sysvars.rent.minimum_balance(space);
+
+    let key = Pubkey::new_unique();
+    let account = Account::new(lamports, space, &program_id);
+
+    let instruction = {
+        let mut instruction_data = vec![1];
+        instruction_data.extend_from_slice(data);
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![AccountMeta::new(key, true)],
+        )
+    };
+
+    // Fail account not signer.
+    {
+        let mut account_not_signer_ix = instruction.clone();
+        account_not_signer_ix.accounts[0].is_signer = false;
+
+        mollusk.process_and_validate_instruction(
+            &account_not_signer_ix,
+            &[(key, account.clone())],
+            &[Check::err(ProgramError::MissingRequiredSignature)],
+        );
+    }
+
+    // Fail data too large.
+    {
+        let mut data_too_large_ix = instruction.clone();
+        data_too_large_ix.data = vec![1; space + 2];
+
+        mollusk.process_and_validate_instruction(
+            &data_too_large_ix,
+            &[(key, account.clone())],
+            &[Check::err(ProgramError::AccountDataTooSmall)],
+        );
+    }
+
+    // Success.
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[(key, account.clone())],
+        &[
+            Check::success(),
+            Check::account(&key)
+                .data(data)
+                .lamports(lamports)
+                .owner(&program_id)
+                .space(space)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn test_transfer() {
+    let program_id = Pubkey::new_unique();
+
+    let mollusk = native_mollusk(&program_id);
+
+    let payer = Pubkey::new_unique();
+    let payer_lamports = 100_000_000;
+    let payer_account = Account::new(payer_lamports, 0, &system_program::id());
+
+    let recipient = Pubkey::new_unique();
+    let recipient_account = Account::new(0, 0, &system_program::id());
+
+    let transfer_amount = 2_000_000_u64;
+
+    let instruction = {
+        let mut instruction_data = vec![2];
+        instruction_data.extend_from_slice(&transfer_amount.to_le_bytes());
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    };
+
+    // Fail insufficient lamports, from within the CPI.
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[
+            (payer, Account::default()),
+            (recipient, recipient_account.clone()),
+            keyed_account_for_system_program(),
+        ],
+        &[Check::err(ProgramError::Custom(
+            SystemError::ResultWithNegativeLamports as u32,
+        ))],
+    );
+
+    // Success.
+    let result = mollusk.process_and_validate_instruction(
+        &instruction,
+        &[
+            (payer, payer_account.clone()),
+            (recipient, recipient_account.clone()),
+            keyed_account_for_system_program(),
+        ],
+        &[
+            Check::success(),
+            Check::account(&payer)
+                .lamports(payer_lamports - transfer_amount)
+                .build(),
+            Check::account(&recipient).lamports(transfer_amount).build(),
+        ],
+    );
+
+    // The transfer is recorded as an inner instruction, and logged.
+    assert_eq!(
+        result.inner_instructions,
+        vec![InnerInstruction {
+            instruction: system_instruction::transfer(&payer, &recipient, transfer_amount),
+            stack_height: 2,
+        }],
+    );
+    assert!(result
+        .logs
+        .contains(&format!("Program {} invoke [2]", system_program::id())));
+}
+
+#[test]
+fn test_cpi() {
+    let program_id = Pubkey::new_unique();
+    let cpi_target_program_id = Pubkey::new_unique();
+
+    let mut mollusk = native_mollusk(&program_id);
+    mollusk.add_native_program(
+        &cpi_target_program_id,
+        test_program_cpi_target::process_instruction,
+    );
+
+    let data = &[1, 2, 3, 4, 5];
+    let space = data.len();
+    let lamports = mollusk.sysvars.rent.minimum_balance(space);
+
+    let key = Pubkey::new_unique();
+    let account = Account::new(lamports, space, &cpi_target_program_id);
+
+    let instruction = {
+        let mut instruction_data = vec![4];
+        instruction_data.extend_from_slice(cpi_target_program_id.as_ref());
+        instruction_data.extend_from_slice(data);
+        Instruction::new_with_bytes(
+            program_id,
+            &instruction_data,
+            vec![
+                AccountMeta::new(key, true),
+                AccountMeta::new_readonly(cpi_target_program_id, false),
+            ],
+        )
+    };
+    let accounts = [
+        (key, account.clone()),
+        create_keyed_account_for_builtin_program(&cpi_target_program_id, "native_program"),
+    ];
+
+    // Fail account not signer.
+    {
+        let mut account_not_signer_ix = instruction.clone();
+        account_not_signer_ix.accounts[0].is_signer = false;
+
+        mollusk.process_and_validate_instruction(
+            &account_not_signer_ix,
+            &accounts,
+            &[Check::instruction_err(
+                InstructionError::PrivilegeEscalation,
+            )],
+        );
+    }
+
+    // Success.
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &accounts,
+        &[
+            Check::success(),
+            Check::account(&key)
+                .data(data)
+                .lamports(lamports)
+                .owner(&cpi_target_program_id)
+                .space(space)
+                .build(),
+        ],
+    );
+}
+
+#[test]
+fn test_panic() {
+    let program_id = Pubkey::new_unique();
+
+    let mollusk = native_mollusk(&program_id);
+
+    let instruction = Instruction::new_with_bytes(program_id, &[6], vec![]);
+
+    mollusk.process_and_validate_instruction(
+        &instruction,
+        &[],
+        &[Check::instruction_err(
+            InstructionError::ProgramFailedToComplete,
+        )],
+    );
+}
+
+#[test]
+fn test_programs_per_instance() {
+    fn succeed(_: &Pubkey, _: &[AccountInfo], _: &[u8]) -> ProgramResult {
+        Ok(())
+    }
+
+    fn fail(_: &Pubkey, _: &[AccountInfo], _: &[u8]) -> ProgramResult {
+        Err(ProgramError::Custom(7))
+    }
+
+    // The same program ID, registered with a different function on each
+    // instance.
+    let program_id = Pubkey::new_unique();
+    let config = || Config {
+        panic: true,
+        verbose: true,
+    };
+
+    let mut succeeding = Mollusk {
+        config: config(),
+        ..Default::default()
+    };
+    succeeding.add_native_program(&program_id, succeed);
+
+    let mut failing = Mollusk {
+        config: config(),
+        ..Default::default()
+    };
+    failing.add_native_program(&program_id, fail);
+
+    let instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+    for _ in 0..2 {
+        succeeding.process_and_validate_instruction(&instruction, &[], &[Check::success()]);
+        failing.process_and_validate_instruction(
+            &instruction,
+            &[],
+            &[Check::err(ProgramError::Custom(7))],
+        );
+    }
+}
@@ -38,7 +38,8 @@ fn test_process_mollusk() {
         &instruction,
         &accounts,
         &result,
-    );
+    )
+    .unwrap();
 
     mollusk.process_and_validate_fixture(&fixture);
 
@@ -51,11 +52,400 @@ fn test_process_mollusk() {
         &instruction,
         &accounts,
         &result,
-    );
+    )
+    .unwrap();
 
     mollusk.process_and_validate_fixture(&fixture);
 }
 
+#[cfg(feature = "fuzz")]
+#[test]
+fn test_process_mollusk_chain() {
+    let ok_transfer_amount = 42_000;
+
+    let mut mollusk = Mollusk {
+        config: mollusk_svm::result::Config {
+            panic: true,
+            verbose: true,
+        },
+        ..Default::default()
+    };
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let accounts = vec![
+        (
+            sender,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+    ];
+
+    // The second transfer overdraws the sender, so the third is never
+    // processed.
+    let instructions = vec![
+        system_instruction::transfer(&sender, &recipient, ok_transfer_amount),
+        system_instruction::transfer(&sender, &recipient, BASE_LAMPORTS),
+        system_instruction::transfer(&sender, &recipient, ok_transfer_amount),
+    ];
+    let first = mollusk.process_instruction(&instructions[0], &accounts);
+    let second = mollusk.process_instruction(&instructions[1], &first.resulting_accounts);
+    assert!(second.program_result.is_err());
+    let results = vec![first, second];
+
+    let fixture = mollusk_svm::fuzz::mollusk::build_chain_fixture_from_mollusk_test(
+        &mollusk,
+        &instructions,
+        &accounts,
+        &results,
+    )
+    .unwrap();
+    assert_eq!(fixture.input.instructions, instructions);
+    assert_eq!(fixture.output.len(), 2);
+
+    let replayed = mollusk.process_and_validate_chain_fixture(&fixture);
+    assert_eq!(replayed, results);
+}
+
+#[cfg(feature = "fuzz")]
+#[test]
+#[should_panic(expected = "CHECK FAILED: instruction_count")]
+fn test_process_mollusk_chain_truncated() {
+    let mut mollusk = Mollusk {
+        config: mollusk_svm::result::Config {
+            panic: true,
+            verbose: true,
+        },
+        ..Default::default()
+    };
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let accounts = vec![
+        (
+            sender,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+    ];
+
+    let instruction = system_instruction::transfer(&sender, &recipient, 42_000);
+    let instructions = vec![instruction.clone(), instruction];
+    let first = mollusk.process_instruction(&instructions[0], &accounts);
+    let second = mollusk.process_instruction(&instructions[1], &first.resulting_accounts);
+
+    // Effects missing for the second instruction can't validate it.
+    let mut fixture = mollusk_svm::fuzz::mollusk::build_chain_fixture_from_mollusk_test(
+        &mollusk,
+        &instructions,
+        &accounts,
+        &[first, second],
+    )
+    .unwrap();
+    fixture.output.truncate(1);
+
+    mollusk.process_and_validate_chain_fixture(&fixture);
+}
+
+#[cfg(feature = "fuzz")]
+#[test]
+fn test_process_mollusk_effects_versions() {
+    use mollusk_svm::result::{Compare, Config};
+
+    let mut mollusk = Mollusk {
+        config: Config {
+            panic: true,
+            verbose: true,
+        },
+        ..Default::default()
+    };
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let accounts = vec![
+        (
+            sender,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+    ];
+
+    let instruction = system_instruction::transfer(&sender, &recipient, 42_000);
+    let result = mollusk.process_instruction(&instruction, &accounts);
+
+    let fixture = mollusk_svm::fuzz::mollusk::build_fixture_from_mollusk_test(
+        &mollusk,
+        &instruction,
+        &accounts,
+        &result,
+    )
+    .unwrap();
+    assert_eq!(
+        fixture.output.version,
+        mollusk_svm_fuzz_fixture::effects::EFFECTS_VERSION
+    );
+    assert_eq!(fixture.output.logs, result.logs);
+    assert!(!fixture.output.logs.is_empty());
+
+    // Fixtures recording different logs fail validation.
+    let mut tampered = fixture.clone();
+    tampered
+        .output
+        .logs
+        .push("Program log: tampered".to_string());
+    let expected = mollusk_svm::result::InstructionResult::from(&tampered.output);
+    assert!(!expected.compare_with_config(&result, &Compare::all_effects(), &Config::default()));
+    assert!(expected.compare_with_config(&result, &Compare::everything(), &Config::default()));
+
+    // Fixtures recorded before logs were, which have no version, don't.
+    let mut legacy = fixture;
+    legacy.output.version = 0;
+    legacy.output.logs.clear();
+    mollusk.process_and_validate_fixture(&legacy);
+
+    // Unless the logs are checked explicitly, which fails rather than
+    // passing unchecked.
+    let report = result.compare_with_effects_report(
+        &mollusk_svm::result::InstructionResult::from(&legacy.output),
+        &[Compare::ProgramResult, Compare::Logs],
+        legacy.output.version,
+        &Config::default(),
+    );
+    let failures = report.failures().collect::<Vec<_>>();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].check, "logs");
+    assert_eq!(
+        failures[0].detail.as_deref(),
+        Some("Not recorded by version 0 fixture effects")
+    );
+}
+
+#[cfg(feature = "fuzz")]
+#[test]
+fn test_bless_mollusk() {
+    use mollusk_svm::result::Config;
+
+    let mut mollusk = Mollusk {
+        config: Config {
+            panic: true,
+            verbose: true,
+        },
+        ..Default::default()
+    };
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let accounts = vec![
+        (
+            sender,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+    ];
+
+    let instruction = system_instruction::transfer(&sender, &recipient, 42_000);
+    let result = mollusk.process_instruction(&instruction, &accounts);
+
+    let fixture = mollusk_svm::fuzz::mollusk::build_fixture_from_mollusk_test(
+        &mollusk,
+        &instruction,
+        &accounts,
+        &result,
+    )
+    .unwrap();
+
+    // A stale fixture, recorded before logs were, with outdated effects.
+    let mut stale = fixture.clone();
+    stale.output.version = 0;
+    stale.output.logs.clear();
+    stale.output.compute_units_consumed += 1;
+    stale.output.resulting_accounts[1].1.lamports -= 1;
+
+    let report = mollusk.bless_fixture(&mut stale).unwrap();
+    let changed = report
+        .failures()
+        .map(|outcome| (outcome.check.as_str(), outcome.account.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changed,
+        vec![
+            ("compute_units_consumed", None),
+            ("resulting_account_lamports", Some(recipient.to_string())),
+        ]
+    );
+    // Execution time varies between runs.
+    stale.output.execution_time = fixture.output.execution_time;
+    assert_eq!(stale.input, fixture.input);
+    assert_eq!(stale.output, fixture.output);
+    mollusk.process_and_validate_fixture(&stale);
+
+    // Blessing again changes nothing.
+    assert!(mollusk.bless_fixture(&mut stale).unwrap().passed());
+
+    // Chains are blessed instruction by instruction.
+    let instructions = vec![instruction.clone(), instruction];
+    let first = mollusk.process_instruction(&instructions[0], &accounts);
+    let second = mollusk.process_instruction(&instructions[1], &first.resulting_accounts);
+    let mut chain = mollusk_svm::fuzz::mollusk::build_chain_fixture_from_mollusk_test(
+        &mollusk,
+        &instructions,
+        &accounts,
+        &[first, second],
+    )
+    .unwrap();
+    let expected = chain.output.clone();
+    chain.output[1].compute_units_consumed += 1;
+
+    let report = mollusk.bless_chain_fixture(&mut chain).unwrap();
+    let changed = report
+        .failures()
+        .map(|outcome| outcome.check.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(changed, vec!["instructions[1].compute_units_consumed"]);
+    for (effects, expected) in chain.output.iter_mut().zip(expected.iter()) {
+        effects.execution_time = expected.execution_time;
+    }
+    assert_eq!(chain.output, expected);
+}
+
+#[cfg(feature = "fuzz")]
+#[test]
+fn test_process_mollusk_embedded_programs() {
+    use {
+        mollusk_svm::{
+            program::loader_keys::LOADER_V2,
+            result::{Check, Config},
+        },
+        mollusk_svm_fuzz_fixture::Fixture,
+        mollusk_svm_fuzz_fs::SerializableFixture,
+        solana_sdk::instruction::Instruction,
+    };
+
+    const MEMO: &[u8] = include_bytes!("../../programs/memo/src/elf/memo.so");
+    const MEMO_V1: &[u8] = include_bytes!("../../programs/memo/src/elf/memo-v1.so");
+
+    let config = || Config {
+        panic: true,
+        verbose: true,
+    };
+
+    let program_id = Pubkey::new_unique();
+    let instruction = Instruction::new_with_bytes(program_id, b"hello", vec![]);
+
+    // ELFs are only retained on request.
+    let mut mollusk = Mollusk::default();
+    mollusk.program_cache.set_elf_retention(false);
+    mollusk.add_program_with_elf_and_loader(&program_id, MEMO, &LOADER_V2);
+    assert!(mollusk_svm::fuzz::mollusk::build_fixture_programs(&mollusk).is_empty());
+
+    let mut mollusk = Mollusk {
+        config: config(),
+        ..Default::default()
+    };
+    mollusk.program_cache.set_elf_retention(true);
+    mollusk.add_program_with_elf_and_loader(&program_id, MEMO, &LOADER_V2);
+    let result = mollusk.process_and_validate_instruction(&instruction, &[], &[Check::success()]);
+
+    let mut fixture = mollusk_svm::fuzz::mollusk::build_fixture_from_mollusk_test(
+        &mollusk,
+        &instruction,
+        &[],
+        &result,
+    )
+    .unwrap();
+    fixture.programs = mollusk_svm::fuzz::mollusk::build_fixture_programs(&mollusk);
+    assert_eq!(fixture.programs.len(), 1);
+    assert_eq!(fixture.programs[0].program_id, program_id);
+    assert_eq!(fixture.programs[0].loader_key, LOADER_V2);
+    assert_eq!(fixture.programs[0].elf, MEMO);
+
+    let blob = SerializableFixture::encode(&mollusk_svm_fuzz_fixture::proto::InstrFixture::from(
+        fixture.clone(),
+    ));
+    let fixture = Fixture::decode(&blob);
+
+    // The fixture replays on its own, against an empty program cache.
+    let mut fresh = Mollusk {
+        config: config(),
+        ..Default::default()
+    };
+    let replayed = fresh.process_and_validate_fixture(&fixture);
+    assert_eq!(replayed, result);
+
+    // Programs already in the cache take precedence over embedded ones.
+    let mut other = Mollusk::default();
+    other.program_cache.set_elf_retention(true);
+    other.add_program_with_elf_and_loader(&program_id, MEMO_V1, &LOADER_V2);
+    other.process_fixture(&fixture);
+    let (_, _, elf) = other.program_cache.program_elfs().next().unwrap();
+    assert_eq!(elf, MEMO_V1);
+
+    // Blessing embeds the programs the fixture was blessed with.
+    let mut blessed = fixture.clone();
+    other.bless_fixture(&mut blessed).unwrap();
+    assert_eq!(blessed.programs.len(), 1);
+    assert_eq!(blessed.programs[0].program_id, program_id);
+    assert_eq!(blessed.programs[0].elf, MEMO_V1);
+    other.process_and_validate_fixture(&blessed);
+
+    let mut chain = mollusk_svm::fuzz::mollusk::build_chain_fixture_from_mollusk_test(
+        &mollusk,
+        &[instruction],
+        &[],
+        &[result],
+    )
+    .unwrap();
+    chain.programs = fixture.programs.clone();
+    other.bless_chain_fixture(&mut chain).unwrap();
+    assert_eq!(chain.programs, blessed.programs);
+}
+
+#[cfg(feature = "fuzz")]
+#[test]
+#[should_panic(expected = "No ELF retained for embedded program")]
+fn test_bless_mollusk_embedded_programs_without_retention() {
+    use {mollusk_svm::program::loader_keys::LOADER_V2, solana_sdk::instruction::Instruction};
+
+    const MEMO: &[u8] = include_bytes!("../../programs/memo/src/elf/memo.so");
+
+    let program_id = Pubkey::new_unique();
+    let instruction = Instruction::new_with_bytes(program_id, b"hello", vec![]);
+
+    let mut mollusk = Mollusk::default();
+    mollusk.program_cache.set_elf_retention(true);
+    mollusk.add_program_with_elf_and_loader(&program_id, MEMO, &LOADER_V2);
+    let result = mollusk.process_instruction(&instruction, &[]);
+    let mut fixture = mollusk_svm::fuzz::mollusk::build_fixture_from_mollusk_test(
+        &mollusk,
+        &instruction,
+        &[],
+        &result,
+    )
+    .unwrap();
+    fixture.programs = mollusk_svm::fuzz::mollusk::build_fixture_programs(&mollusk);
+
+    // The embedded program is loaded, but its ELF isn't retained.
+    let mut fresh = Mollusk::default();
+    fresh.program_cache.set_elf_retention(false);
+    fresh.bless_fixture(&mut fixture).unwrap();
+}
+
 #[cfg(feature = "fuzz-fd")]
 #[test]
 fn test_process_firedancer() {
@@ -87,7 +477,8 @@ fn test_process_firedancer() {
         &instruction,
         &accounts,
         &result,
-    );
+    )
+    .unwrap();
 
     mollusk.process_and_validate_firedancer_fixture(&fixture);
 
@@ -100,7 +491,149 @@ fn test_process_firedancer() {
         &instruction,
         &accounts,
         &result,
-    );
+    )
+    .unwrap();
 
     mollusk.process_and_validate_firedancer_fixture(&fixture);
 }
+
+#[cfg(all(feature = "fuzz", feature = "fuzz-fd"))]
+#[test]
+fn test_convert_fixture_layouts() {
+    use mollusk_svm::{
+        fuzz::convert::{firedancer_to_mollusk, mollusk_to_firedancer},
+        result::Config,
+    };
+
+    let mut mollusk = Mollusk {
+        config: Config {
+            panic: true,
+            verbose: true,
+        },
+        ..Default::default()
+    };
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let accounts = vec![
+        (
+            sender,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+    ];
+
+    for amount in [42_000, BASE_LAMPORTS + 1] {
+        let instruction = system_instruction::transfer(&sender, &recipient, amount);
+        let result = mollusk.process_instruction(&instruction, &accounts);
+
+        let fixture = mollusk_svm::fuzz::mollusk::build_fixture_from_mollusk_test(
+            &mollusk,
+            &instruction,
+            &accounts,
+            &result,
+        )
+        .unwrap();
+
+        // Mollusk to Firedancer, which drops the logs.
+        let conversion = mollusk_to_firedancer(&fixture);
+        assert_eq!(conversion.dropped, vec!["effects.logs"]);
+        let fd_fixture = conversion.fixture;
+        mollusk.process_and_validate_firedancer_fixture(&fd_fixture);
+
+        // And back again, which drops nothing.
+        let conversion = firedancer_to_mollusk(&fd_fixture);
+        assert!(conversion.dropped.is_empty());
+        let converted = conversion.fixture;
+        assert_eq!(converted.output.version, 0);
+        assert_eq!(
+            converted.input.instruction_data,
+            fixture.input.instruction_data
+        );
+        assert_eq!(converted.input.sysvars, fixture.input.sysvars);
+        mollusk.process_and_validate_fixture(&converted);
+    }
+}
+
+#[cfg(all(feature = "fuzz", feature = "fuzz-fd"))]
+#[test]
+fn test_fixture_transaction_error() {
+    use solana_sdk::{rent::Rent, transaction::TransactionError};
+
+    let mut mollusk = Mollusk::default();
+
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let accounts = vec![
+        (
+            sender,
+            Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+        ),
+        (recipient, Account::default()),
+    ];
+
+    // Leaving the recipient rent-paying, which is only rejected once rent
+    // state is enforced.
+    let lamports = Rent::default().minimum_balance(0) - 1;
+    let instruction = system_instruction::transfer(&sender, &recipient, lamports);
+    let result = mollusk.process_instruction(&instruction, &accounts);
+
+    let mut fixture = mollusk_svm::fuzz::mollusk::build_fixture_from_mollusk_test(
+        &mollusk,
+        &instruction,
+        &accounts,
+        &result,
+    )
+    .unwrap();
+    let mut fd_fixture = mollusk_svm::fuzz::firedancer::build_fixture_from_mollusk_test(
+        &mollusk,
+        &instruction,
+        &accounts,
+        &result,
+    )
+    .unwrap();
+
+    mollusk.enforce_rent_state = true;
+    let err = TransactionError::InsufficientFundsForRent { account_index: 1 };
+    let result = mollusk.process_instruction(&instruction, &accounts);
+
+    // Neither layout can record the transaction error.
+    assert_eq!(
+        mollusk_svm::fuzz::mollusk::build_fixture_from_mollusk_test(
+            &mollusk,
+            &instruction,
+            &accounts,
+            &result,
+        )
+        .unwrap_err(),
+        err
+    );
+    assert_eq!(
+        mollusk_svm::fuzz::firedancer::build_fixture_from_mollusk_test(
+            &mollusk,
+            &instruction,
+            &accounts,
+            &result,
+        )
+        .unwrap_err(),
+        err
+    );
+
+    // Blessing leaves the fixtures untouched.
+    let expected = fixture.clone();
+    assert_eq!(mollusk.bless_fixture(&mut fixture).unwrap_err(), err);
+    assert_eq!(fixture, expected);
+    let expected = fd_fixture.clone();
+    assert_eq!(
+        mollusk
+            .bless_firedancer_fixture(&mut fd_fixture)
+            .unwrap_err(),
+        err
+    );
+    assert_eq!(fd_fixture, expected);
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "proptest")]
+
+use {
+    mollusk_svm::{
+        proptest::{InstructionCase, ProptestConfig},
+        result::ProgramResult,
+        Mollusk,
+    },
+    proptest::strategy::Strategy,
+    solana_sdk::{account::Account, pubkey::Pubkey, system_instruction, system_program},
+};
+
+const BASE_LAMPORTS: u64 = 100_000_000;
+
+fn transfers(sender: Pubkey, recipient: Pubkey) -> impl Strategy<Value = InstructionCase> {
+    (0..=BASE_LAMPORTS * 2).prop_map(move |amount| {
+        let instruction = system_instruction::transfer(&sender, &recipient, amount);
+        let accounts = vec![
+            (
+                sender,
+                Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+            ),
+            (
+                recipient,
+                Account::new(BASE_LAMPORTS, 0, &system_program::id()),
+            ),
+        ];
+        (instruction, accounts)
+    })
+}
+
+fn config() -> ProptestConfig {
+    ProptestConfig {
+        cases: 64,
+        failure_persistence: None,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_transfer_property() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let mollusk = Mollusk::default();
+
+    // Transfers within the sender's balance succeed, and others fail.
+    mollusk
+        .test_property(
+            config(),
+            transfers(sender, recipient),
+            |result, instruction, _| {
+                let amount = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap());
+                if result.program_result.is_err() == (amount > BASE_LAMPORTS) {
+                    Ok(())
+                } else {
+                    Err(format!("Unexpected result for amount {}", amount))
+                }
+            },
+        )
+        .unwrap();
+
+    // A property which doesn't hold is shrunk to the smallest failing amount.
+    let failure = mollusk
+        .test_property(config(), transfers(sender, recipient), |result, _, _| {
+            let sender_lamports = result.get_account(&sender).unwrap().lamports;
+            if sender_lamports >= BASE_LAMPORTS / 2 {
+                Ok(())
+            } else {
+                Err(format!("Sender left with {} lamports", sender_lamports))
+            }
+        })
+        .unwrap_err();
+
+    let expected_instruction =
+        system_instruction::transfer(&sender, &recipient, BASE_LAMPORTS / 2 + 1);
+    assert_eq!(failure.instruction, expected_instruction);
+    assert_eq!(
+        failure.reason,
+        format!("Sender left with {} lamports", BASE_LAMPORTS / 2 - 1),
+    );
+    assert_eq!(failure.result.program_result, ProgramResult::Success);
+}
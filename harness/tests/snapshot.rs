@@ -0,0 +1,76 @@
+#![cfg(feature = "serde")]
+
+use {
+    mollusk_svm::{
+        result::{Check, Config},
+        snapshot, Mollusk,
+    },
+    solana_sdk::{account::Account, pubkey::Pubkey, system_instruction, system_program},
+};
+
+fn transfer() -> (solana_sdk::instruction::Instruction, Vec<(Pubkey, Account)>) {
+    // Snapshots record addresses, so they can't be generated.
+    let sender = Pubkey::new_from_array([1; 32]);
+    let recipient = Pubkey::new_from_array([2; 32]);
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let instruction = system_instruction::transfer(&sender, &recipient, transfer_amount);
+    let accounts = vec![
+        (
+            sender,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+    ];
+    (instruction, accounts)
+}
+
+#[test]
+fn test_snapshot() {
+    let (instruction, accounts) = transfer();
+    let mollusk = Mollusk::default();
+
+    // Honors `MOLLUSK_UPDATE_SNAPSHOTS` to update the golden file.
+    let result = mollusk.process_instruction(&instruction, &accounts);
+    result.assert_snapshot("system_transfer");
+    assert!(snapshot::snapshot_path("system_transfer").exists());
+
+    // The checks below must fail, so they can't be allowed to write the
+    // golden file. Env vars are process-wide, which is why these checks all
+    // live in this one test.
+    let update = std::env::var_os(snapshot::UPDATE_SNAPSHOTS_ENV);
+    std::env::remove_var(snapshot::UPDATE_SNAPSHOTS_ENV);
+    let failures = |result: &mollusk_svm::result::InstructionResult, name: &str| {
+        result
+            .run_checks_with_report(&[Check::snapshot(name)], &Config::default())
+            .failures()
+            .map(|failure| failure.check.clone())
+            .collect::<Vec<_>>()
+    };
+
+    // A different transfer no longer matches.
+    let mut changed_accounts = accounts.clone();
+    changed_accounts[0].1.lamports += 1;
+    let changed = mollusk.process_instruction(&instruction, &changed_accounts);
+    let changed_failures = failures(&changed, "system_transfer");
+
+    // Missing snapshots fail, rather than being written.
+    let missing_failures = failures(&result, "system_transfer_missing");
+    let missing_exists = snapshot::snapshot_path("system_transfer_missing").exists();
+
+    if let Some(update) = update {
+        std::env::set_var(snapshot::UPDATE_SNAPSHOTS_ENV, update);
+    }
+
+    assert_eq!(
+        changed_failures,
+        vec!["snapshot(system_transfer): resulting_account_lamports"]
+    );
+    assert_eq!(missing_failures, vec!["snapshot(system_transfer_missing)"]);
+    assert!(!missing_exists);
+}
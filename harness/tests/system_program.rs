@@ -1,8 +1,13 @@
 use {
-    mollusk_svm::{result::Check, Mollusk},
+    mollusk_svm::{
+        invariant::Invariant,
+        mutation::Mutation,
+        result::{Check, Config},
+        Mollusk,
+    },
     solana_sdk::{
-        account::Account, instruction::InstructionError, pubkey::Pubkey, system_instruction,
-        system_program,
+        account::Account, instruction::InstructionError, pubkey::Pubkey, rent::Rent,
+        system_instruction, system_program, transaction::TransactionError,
     },
     solana_system_program::system_processor::DEFAULT_COMPUTE_UNITS,
 };
@@ -40,6 +45,281 @@ fn test_transfer() {
     Mollusk::default().process_and_validate_instruction(&instruction, &accounts, &checks);
 }
 
+#[test]
+fn test_transfer_relative_checks() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let instruction = system_instruction::transfer(&sender, &recipient, transfer_amount);
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+    ];
+
+    let result = Mollusk::default().process_instruction(&instruction, &accounts);
+
+    let report = result.run_checks_with_inputs(
+        &[
+            Check::compute_units_at_most(DEFAULT_COMPUTE_UNITS),
+            Check::compute_units_at_least(1),
+            Check::compute_units_within(1..=DEFAULT_COMPUTE_UNITS),
+            Check::account(&sender)
+                .lamports_decreased_by(transfer_amount)
+                .build(),
+            Check::account(&recipient)
+                .lamports_increased_by(transfer_amount)
+                .build(),
+            Check::custom(|result| result.resulting_accounts.len() == 2),
+        ],
+        &accounts,
+        &Config::default(),
+    );
+    assert!(report.passed(), "{}", report);
+
+    let report = result.run_checks_with_inputs(
+        &[
+            Check::compute_units_at_most(DEFAULT_COMPUTE_UNITS - 1),
+            Check::account(&recipient)
+                .lamports_decreased_by(transfer_amount)
+                .build(),
+            Check::custom(|result| result.return_data == [1]),
+        ],
+        &accounts,
+        &Config::default(),
+    );
+    let failures = report
+        .failures()
+        .map(|failure| (failure.check.as_str(), failure.expected.as_deref()))
+        .collect::<Vec<_>>();
+    let expected_units = format!("..={}", DEFAULT_COMPUTE_UNITS - 1);
+    let expected_delta = format!("-{}", transfer_amount);
+    assert_eq!(
+        failures,
+        vec![
+            ("compute_units", Some(expected_units.as_str())),
+            ("account_lamports_delta", Some(expected_delta.as_str())),
+            ("custom", Some("true")),
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "require inputs. Use `run_checks_with_inputs` instead.")]
+fn test_relative_checks_require_inputs() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let instruction = system_instruction::transfer(&sender, &recipient, 42_000);
+    let accounts = [
+        (sender, Account::new(100_000_000, 0, &system_program::id())),
+        (recipient, Account::new(0, 0, &system_program::id())),
+    ];
+
+    let result = Mollusk::default().process_instruction(&instruction, &accounts);
+
+    result.run_checks_with_config(
+        &[Check::account(&recipient)
+            .lamports_increased_by(42_000)
+            .build()],
+        &Config::default(),
+    );
+}
+
+#[test]
+fn test_transfer_invariants() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let mut mollusk = Mollusk::default();
+    mollusk.add_invariants(Invariant::all());
+    mollusk.add_invariant(Invariant::custom("sender_funded", move |ctx| {
+        match ctx.result.get_account(&sender) {
+            Some(account) if account.lamports >= base_lamports - transfer_amount => Ok(()),
+            _ => Err("sender overdrawn".to_string()),
+        }
+    }));
+
+    let transfer = system_instruction::transfer(&sender, &recipient, transfer_amount);
+
+    // The recipient already exists, and remains rent-exempt.
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+    ];
+    let result = mollusk.process_instruction(&transfer, &accounts);
+    assert!(result.invariant_violations.is_empty());
+
+    // The recipient is created without enough lamports to be rent-exempt,
+    // and the second transfer in the chain overdraws the sender.
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+        (recipient, Account::default()),
+    ];
+    let result = mollusk.process_instruction_chain(&[transfer.clone(), transfer], &accounts);
+    let violations = result
+        .invariant_violations
+        .iter()
+        .map(|violation| violation.invariant.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        violations,
+        vec!["rent_exempt", "rent_exempt", "sender_funded"]
+    );
+
+    // Violations fail any checks run against the result.
+    let report = result.run_checks_with_report(&[Check::success()], &Config::default());
+    assert_eq!(report.failures().count(), 3);
+}
+
+#[test]
+fn test_transfer_rent_state() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let rent_exempt_lamports = Rent::default().minimum_balance(0);
+
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+        (recipient, Account::default()),
+    ];
+
+    let mut mollusk = Mollusk {
+        enforce_rent_state: true,
+        ..Default::default()
+    };
+
+    // Funding the recipient with enough lamports to be rent-exempt.
+    let instruction = system_instruction::transfer(&sender, &recipient, rent_exempt_lamports);
+    mollusk
+        .process_instruction(&instruction, &accounts)
+        .run_checks(&[
+            Check::success(),
+            Check::account(&recipient)
+                .lamports(rent_exempt_lamports)
+                .build(),
+        ]);
+
+    // Leaving the recipient rent-paying.
+    let instruction = system_instruction::transfer(&sender, &recipient, rent_exempt_lamports - 1);
+    mollusk
+        .process_instruction(&instruction, &accounts)
+        .run_checks(&[
+            Check::transaction_err(TransactionError::InsufficientFundsForRent { account_index: 1 }),
+            Check::account(&sender).lamports(base_lamports).build(),
+            Check::account(&recipient).lamports(0).build(),
+        ]);
+
+    // A chain is checked once, like a transaction, so the recipient may be
+    // rent-paying in between instructions.
+    let top_up = system_instruction::transfer(&sender, &recipient, 1);
+    mollusk.process_and_validate_instruction_chain(
+        &[
+            (&instruction, &[Check::success()]),
+            (
+                &top_up,
+                &[
+                    Check::success(),
+                    Check::account(&recipient)
+                        .lamports(rent_exempt_lamports)
+                        .build(),
+                ],
+            ),
+        ],
+        &accounts,
+    );
+
+    // But not once the chain is done, which reverts the whole chain.
+    mollusk
+        .process_instruction_chain(
+            &[
+                top_up.clone(),
+                system_instruction::transfer(&sender, &recipient, rent_exempt_lamports - 2),
+            ],
+            &accounts,
+        )
+        .run_checks(&[
+            Check::transaction_err(TransactionError::InsufficientFundsForRent { account_index: 1 }),
+            Check::account(&sender).lamports(base_lamports).build(),
+            Check::account(&recipient).lamports(0).build(),
+        ]);
+
+    // Without enforcement, the transfer succeeds.
+    mollusk.enforce_rent_state = false;
+    mollusk
+        .process_instruction(&instruction, &accounts)
+        .run_checks(&[Check::success()]);
+}
+
+#[test]
+fn test_transfer_min_compute_units() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+    ];
+
+    let mut mollusk = Mollusk::default();
+    let limit = mollusk.compute_budget.compute_unit_limit;
+
+    let instruction = system_instruction::transfer(&sender, &recipient, transfer_amount);
+    assert_eq!(
+        mollusk.find_min_compute_units(&instruction, &accounts),
+        Some(DEFAULT_COMPUTE_UNITS),
+    );
+    assert_eq!(mollusk.compute_budget.compute_unit_limit, limit);
+
+    // A limit of exactly the compute units consumed is the minimum.
+    mollusk.compute_budget.compute_unit_limit = DEFAULT_COMPUTE_UNITS;
+    assert_eq!(
+        mollusk.find_min_compute_units(&instruction, &accounts),
+        Some(DEFAULT_COMPUTE_UNITS),
+    );
+    mollusk.compute_budget.compute_unit_limit = limit;
+
+    // Instructions which fail regardless of the limit have no minimum.
+    let instruction = system_instruction::transfer(&sender, &recipient, base_lamports + 1);
+    assert_eq!(
+        mollusk.find_min_compute_units(&instruction, &accounts),
+        None
+    );
+}
+
 #[test]
 fn test_transfer_account_ordering() {
     let sender = Pubkey::new_unique();
@@ -101,3 +381,56 @@ fn test_transfer_bad_owner() {
 
     Mollusk::default().process_and_validate_instruction(&instruction, &accounts, &checks);
 }
+
+#[test]
+fn test_transfer_privilege_mutations() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+
+    let base_lamports = 100_000_000u64;
+    let transfer_amount = 42_000u64;
+
+    let instruction = system_instruction::transfer(&sender, &recipient, transfer_amount);
+    let accounts = [
+        (
+            sender,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+        (
+            recipient,
+            Account::new(base_lamports, 0, &system_program::id()),
+        ),
+    ];
+
+    let report = Mollusk::default().test_privilege_mutations(&instruction, &accounts);
+    let survivors = report.surviving_mutations().collect::<Vec<_>>();
+
+    // The System program checks the sender's signature and ownership, and
+    // the runtime checks both accounts are writable.
+    assert!(!survivors.contains(&&Mutation::DropSigner { pubkey: sender }));
+    assert!(!survivors.contains(&&Mutation::DropWritable { pubkey: sender }));
+    assert!(!survivors.contains(&&Mutation::DropWritable { pubkey: recipient }));
+    assert!(!survivors.iter().any(
+        |mutation| matches!(mutation, Mutation::SwapOwner { pubkey, .. } if *pubkey == sender)
+    ));
+
+    // But anyone can receive lamports, including the sender itself.
+    assert!(survivors.iter().any(
+        |mutation| matches!(mutation, Mutation::SwapOwner { pubkey, .. } if *pubkey == recipient)
+    ));
+    assert!(survivors.contains(&&Mutation::DuplicateAccount {
+        index: 1,
+        duplicate_of: 0,
+    }));
+
+    // Duplicating the recipient in place of the sender doesn't make the
+    // recipient a signer, so the transfer fails.
+    let escalating = Mutation::DuplicateAccount {
+        index: 0,
+        duplicate_of: 1,
+    };
+    let (mutated, _) = escalating.apply(&instruction, &accounts);
+    assert_eq!(mutated.accounts[0].pubkey, recipient);
+    assert!(!mutated.accounts[0].is_signer);
+    assert!(!survivors.contains(&&escalating));
+}
@@ -10,6 +10,7 @@ CRATES=(
   "mollusk-svm-fuzz-fs"
   "mollusk-svm-fuzz-fixture"
   "mollusk-svm-fuzz-fixture-firedancer"
+  "mollusk-svm-fuzz-mutator"
   "mollusk-svm"
   "mollusk-svm-bencher"
   "mollusk-svm-programs-memo"
@@ -11,9 +11,10 @@ use solana_program::{
 
 solana_program::declare_id!("239vxAL9Q7e3uLoinJpJ873r3bvT9sPFxH7yekwPppNF");
 
+#[cfg(not(feature = "no-entrypoint"))]
 solana_program::entrypoint!(process_instruction);
 
-fn process_instruction(
+pub fn process_instruction(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
     input: &[u8],
@@ -90,7 +91,7 @@ fn process_instruction(
                 vec![AccountMeta::new(*account_info.key, true)],
             );
 
-            invoke(&instruction, &[account_info.clone()])?;
+            invoke(&instruction, std::slice::from_ref(account_info))?;
         }
         Some((5, _)) => {
             // Load the same account twice and assert both infos share the
@@ -110,6 +111,10 @@ fn process_instruction(
                 return Err(ProgramError::MissingRequiredSignature);
             }
         }
+        Some((6, _)) => {
+            // Panic, to exercise error location reporting.
+            panic!("Intentional panic");
+        }
         _ => return Err(ProgramError::InvalidInstructionData),
     }
 